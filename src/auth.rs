@@ -0,0 +1,400 @@
+/// Answering the challenge of a `401 Unauthorized` response with credentials, using the `Basic` or `Digest`
+/// authentication scheme, and authenticating requests with bearer tokens that are refreshed when rejected.
+///
+#[cfg(feature = "digest-auth")]
+use core::cell::RefCell;
+#[cfg(feature = "digest-auth")]
+use core::fmt::Write as _;
+#[cfg(feature = "digest-auth")]
+use heapless::String;
+#[cfg(feature = "digest-auth")]
+use md5::{Digest as _, Md5};
+#[cfg(feature = "digest-auth")]
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+
+use crate::request::{Auth, Method};
+use crate::Error;
+
+/// The maximum length of a `Digest` authorization header value.
+#[cfg(feature = "digest-auth")]
+pub const MAX_DIGEST_LEN: usize = 512;
+
+/// Credentials used to answer the challenge of a `401 Unauthorized` response.
+///
+/// With the `digest-auth` feature, the `Digest` scheme is supported with the `MD5` algorithm, with or without
+/// `qop=auth`, once a random generator for the client nonces is set with [`Credentials::with_rng()`].
+#[derive(Clone, Copy)]
+pub struct Credentials<'a> {
+    pub username: &'a str,
+    pub password: &'a str,
+    #[cfg(feature = "digest-auth")]
+    rng: Option<&'a RefCell<dyn CryptoRngCore>>,
+}
+
+impl<'a> Credentials<'a> {
+    pub fn new(username: &'a str, password: &'a str) -> Self {
+        Self {
+            username,
+            password,
+            #[cfg(feature = "digest-auth")]
+            rng: None,
+        }
+    }
+
+    /// Answer `Digest` challenges, taking the client nonces from `rng`, which should be a cryptographically secure
+    /// random number generator.
+    #[cfg(feature = "digest-auth")]
+    pub fn with_rng(mut self, rng: &'a RefCell<dyn CryptoRngCore>) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Answer the first supported challenge of the `WWW-Authenticate` header values, preferring `Digest`.
+    ///
+    /// The `uri` is the request target, in parts that are concatenated.
+    #[cfg_attr(not(feature = "digest-auth"), allow(unused_variables))]
+    pub(crate) fn authorize<'v>(
+        &self,
+        method: Method,
+        uri: &[&str],
+        challenges: impl Iterator<Item = &'v [u8]>,
+    ) -> Option<Authorization> {
+        let mut basic = false;
+        for value in challenges {
+            let Ok(value) = core::str::from_utf8(value) else {
+                continue;
+            };
+            #[cfg(feature = "digest-auth")]
+            if let Some(authorization) = self.answer_digest(method, uri, value) {
+                return Some(Authorization::Digest(authorization));
+            }
+            basic |= Tokens(value)
+                .any(|token| matches!(token, Token::Scheme(scheme) if scheme.eq_ignore_ascii_case("basic")));
+        }
+        basic.then_some(Authorization::Basic)
+    }
+
+    /// Answer the first supported `Digest` challenge of a `WWW-Authenticate` header value.
+    #[cfg(feature = "digest-auth")]
+    fn answer_digest(&self, method: Method, uri: &[&str], value: &str) -> Option<String<MAX_DIGEST_LEN>> {
+        let answer = |challenge: DigestChallenge| {
+            let Some(rng) = self.rng else {
+                warn!("No random generator for the client nonce of a digest challenge");
+                return None;
+            };
+            self.digest(&challenge, method, uri, &mut *rng.borrow_mut())
+        };
+
+        let mut digest: Option<DigestChallenge> = None;
+        for token in Tokens(value) {
+            match token {
+                Token::Scheme(scheme) => {
+                    if let Some(authorization) = digest.take().and_then(answer) {
+                        return Some(authorization);
+                    }
+                    if scheme.eq_ignore_ascii_case("digest") {
+                        digest = Some(DigestChallenge::default());
+                    }
+                }
+                Token::Param(name, value) => {
+                    if let Some(digest) = digest.as_mut() {
+                        digest.set(name, value);
+                    }
+                }
+            }
+        }
+        digest.and_then(answer)
+    }
+
+    /// Compute the `Digest` authorization header value with a random client nonce, if the challenge is supported.
+    #[cfg(feature = "digest-auth")]
+    fn digest(
+        &self,
+        challenge: &DigestChallenge,
+        method: Method,
+        uri: &[&str],
+        rng: &mut (impl RngCore + CryptoRng + ?Sized),
+    ) -> Option<String<MAX_DIGEST_LEN>> {
+        let (realm, nonce) = (challenge.realm?, challenge.nonce?);
+        if !challenge.algorithm.map_or(true, |a| a.eq_ignore_ascii_case("md5")) {
+            warn!("Unsupported digest algorithm");
+            return None;
+        }
+
+        let ha1 = md5_hex(&[self.username, ":", realm, ":", self.password]);
+        let mut a2: heapless::Vec<&str, 6> = heapless::Vec::new();
+        a2.extend_from_slice(&[method.as_str(), ":"]).ok()?;
+        a2.extend_from_slice(uri).ok()?;
+        let ha2 = md5_hex(&a2);
+
+        let mut cnonce = [0; 8];
+        rng.fill_bytes(&mut cnonce);
+        let cnonce: String<16> = hex(&cnonce);
+        let response = if challenge.qop_auth {
+            md5_hex(&[
+                ha1.as_str(),
+                ":",
+                nonce,
+                ":",
+                NONCE_COUNT,
+                ":",
+                cnonce.as_str(),
+                ":auth:",
+                ha2.as_str(),
+            ])
+        } else {
+            md5_hex(&[ha1.as_str(), ":", nonce, ":", ha2.as_str()])
+        };
+
+        let mut value = String::new();
+        write!(
+            value,
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"",
+            self.username, realm, nonce
+        )
+        .ok()?;
+        for part in uri {
+            value.push_str(part).ok()?;
+        }
+        write!(value, "\", response=\"{}\"", response).ok()?;
+        if let Some(opaque) = challenge.opaque {
+            write!(value, ", opaque=\"{}\"", opaque).ok()?;
+        }
+        if challenge.qop_auth {
+            write!(value, ", qop=auth, nc={}, cnonce=\"{}\"", NONCE_COUNT, cnonce).ok()?;
+        }
+        if let Some(algorithm) = challenge.algorithm {
+            write!(value, ", algorithm={}", algorithm).ok()?;
+        }
+        Some(value)
+    }
+}
+
+/// The nonce count, as a nonce is only used for a single retry.
+#[cfg(feature = "digest-auth")]
+const NONCE_COUNT: &str = "00000001";
+
+/// The authorization answering a challenge.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Authorization {
+    Basic,
+    #[cfg(feature = "digest-auth")]
+    Digest(String<MAX_DIGEST_LEN>),
+}
+
+impl Authorization {
+    pub(crate) fn auth<'a>(&'a self, credentials: &Credentials<'a>) -> Auth<'a> {
+        match self {
+            Authorization::Basic => Auth::Basic {
+                username: credentials.username,
+                password: credentials.password,
+            },
+            #[cfg(feature = "digest-auth")]
+            Authorization::Digest(value) => Auth::Raw { value: value.as_str() },
+        }
+    }
+}
+
+/// A provider of the bearer tokens requests are authenticated with, such as OAuth access tokens.
+pub trait TokenProvider {
+    /// Get the current token, e.g. from storage, or by requesting one if there is none.
+    async fn token(&mut self) -> Result<&str, Error>;
+
+    /// Replace the current token, which the server rejected with `401 Unauthorized`, e.g. using a refresh token.
+    async fn refresh(&mut self) -> Result<(), Error>;
+}
+
+#[cfg(feature = "digest-auth")]
+#[derive(Default)]
+struct DigestChallenge<'a> {
+    realm: Option<&'a str>,
+    nonce: Option<&'a str>,
+    opaque: Option<&'a str>,
+    algorithm: Option<&'a str>,
+    qop_auth: bool,
+}
+
+#[cfg(feature = "digest-auth")]
+impl<'a> DigestChallenge<'a> {
+    fn set(&mut self, name: &str, value: &'a str) {
+        if name.eq_ignore_ascii_case("realm") {
+            self.realm = Some(value);
+        } else if name.eq_ignore_ascii_case("nonce") {
+            self.nonce = Some(value);
+        } else if name.eq_ignore_ascii_case("opaque") {
+            self.opaque = Some(value);
+        } else if name.eq_ignore_ascii_case("algorithm") {
+            self.algorithm = Some(value);
+        } else if name.eq_ignore_ascii_case("qop") {
+            self.qop_auth = value.split(',').any(|qop| qop.trim().eq_ignore_ascii_case("auth"));
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "digest-auth"), allow(dead_code))]
+enum Token<'a> {
+    Scheme(&'a str),
+    Param(&'a str, &'a str),
+}
+
+/// The auth schemes and parameters of a `WWW-Authenticate` header value, which may contain multiple challenges.
+struct Tokens<'a>(&'a str);
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        let end = rest
+            .find(|c: char| c == '=' || c == ',' || c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        if end == 0 {
+            self.0 = "";
+            return None;
+        }
+        let (name, rest) = rest.split_at(end);
+        let rest = rest.trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            self.0 = rest;
+            return Some(Token::Scheme(name));
+        };
+
+        let rest = rest.trim_start();
+        let (value, rest) = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut escaped = false;
+            let end = quoted
+                .find(|c: char| {
+                    let end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    end
+                })
+                .unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or_default())
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            (rest[..end].trim_end(), &rest[end..])
+        };
+        self.0 = rest;
+        Some(Token::Param(name, value))
+    }
+}
+
+#[cfg(feature = "digest-auth")]
+fn md5_hex(parts: &[&str]) -> String<32> {
+    let mut md5 = Md5::new();
+    for part in parts {
+        md5.update(part.as_bytes());
+    }
+    hex(&md5.finalize())
+}
+
+#[cfg(feature = "digest-auth")]
+fn hex<const N: usize>(bytes: &[u8]) -> String<N> {
+    let mut hex = String::new();
+    for b in bytes {
+        write!(hex, "{:02x}", b).unwrap();
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "digest-auth")]
+    use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+    #[cfg(feature = "digest-auth")]
+    #[test]
+    fn rfc2617_digest() {
+        let rng = RefCell::new(ChaCha8Rng::seed_from_u64(0));
+        let credentials = Credentials::new("Mufasa", "Circle Of Life").with_rng(&rng);
+        let challenge = br#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#;
+        let Some(Authorization::Digest(value)) =
+            credentials.authorize(Method::GET, &["/dir/index.html"], [challenge.as_slice()].into_iter())
+        else {
+            panic!("Expected a digest authorization");
+        };
+
+        let mut cnonce = [0; 8];
+        ChaCha8Rng::seed_from_u64(0).fill_bytes(&mut cnonce);
+        let cnonce: String<16> = hex(&cnonce);
+        let ha1 = md5_hex(&["Mufasa:testrealm@host.com:Circle Of Life"]);
+        let ha2 = md5_hex(&["GET:/dir/index.html"]);
+        let response = md5_hex(&[
+            ha1.as_str(),
+            ":dcd98b7102dd2f0e8b11d0f600bfb0c093:00000001:",
+            cnonce.as_str(),
+            ":auth:",
+            ha2.as_str(),
+        ]);
+        assert!(value.starts_with(
+            r#"Digest username="Mufasa", realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", uri="/dir/index.html", response=""#
+        ));
+        assert!(value.contains(response.as_str()));
+        assert!(value.ends_with(&format!(
+            r#", opaque="5ccc069c403ebaf9f0171e9517f40e41", qop=auth, nc=00000001, cnonce="{}""#,
+            cnonce
+        )));
+
+        let Some(Authorization::Digest(other)) =
+            credentials.authorize(Method::GET, &["/dir/index.html"], [challenge.as_slice()].into_iter())
+        else {
+            panic!("Expected a digest authorization");
+        };
+        assert_ne!(value, other);
+    }
+
+    #[cfg(feature = "digest-auth")]
+    #[test]
+    fn rfc2069_digest() {
+        let rng = RefCell::new(ChaCha8Rng::seed_from_u64(0));
+        let credentials = Credentials::new("Mufasa", "CircleOfLife").with_rng(&rng);
+        let challenge = br#"Digest realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093""#;
+        let Some(Authorization::Digest(value)) =
+            credentials.authorize(Method::GET, &["/dir/index.html"], [challenge.as_slice()].into_iter())
+        else {
+            panic!("Expected a digest authorization");
+        };
+        assert!(value.contains(r#"response="1949323746fe6a43ef61f9606e7febea""#));
+    }
+
+    #[cfg(feature = "digest-auth")]
+    #[test]
+    fn prefers_digest_over_basic() {
+        let rng = RefCell::new(ChaCha8Rng::seed_from_u64(0));
+        let credentials = Credentials::new("user", "password").with_rng(&rng);
+        let challenges = [
+            br#"Basic realm="a""#.as_slice(),
+            br#"Newauth realm="b", Digest realm="c", nonce="d", algorithm=MD5"#,
+        ];
+        let Some(Authorization::Digest(value)) = credentials.authorize(Method::GET, &["/"], challenges.into_iter())
+        else {
+            panic!("Expected a digest authorization");
+        };
+        assert!(value.starts_with(r#"Digest username="user", realm="c", nonce="d""#));
+        assert!(value.ends_with(", algorithm=MD5"));
+
+        let challenges = [
+            br#"Basic realm="a""#.as_slice(),
+            br#"Digest realm="c", nonce="d", algorithm=SHA-256"#,
+        ];
+        assert!(matches!(
+            credentials.authorize(Method::GET, &["/"], challenges.into_iter()),
+            Some(Authorization::Basic)
+        ));
+    }
+
+    #[test]
+    fn answers_basic_challenge() {
+        let credentials = Credentials::new("user", "password");
+        let challenges = [br#"Basic realm="a""#.as_slice(), br#"Digest realm="c", nonce="d""#];
+        assert!(matches!(
+            credentials.authorize(Method::GET, &["/"], challenges.into_iter()),
+            Some(Authorization::Basic)
+        ));
+        assert!(credentials
+            .authorize(Method::GET, &["/"], [b"Bearer".as_slice()].into_iter())
+            .is_none());
+    }
+}
@@ -3,7 +3,7 @@ use embedded_io_async::Write;
 
 use super::chunked::write_chunked_header;
 
-const EMPTY_CHUNK: &[u8; 5] = b"0\r\n\r\n";
+const LAST_CHUNK: &[u8; 3] = b"0\r\n";
 const NEWLINE: &[u8; 2] = b"\r\n";
 
 /// A body writer that buffers internally and emits chunks as expected by the
@@ -26,6 +26,9 @@ const NEWLINE: &[u8; 2] = b"\r\n";
 /// such that the header and payload can be written to the underlying connection in
 /// a single write.
 ///
+/// After the last chunk is written using [`BufferingChunkedBodyWriter::start_trailers()`],
+/// written data is buffered as-is until the writer is terminated.
+///
 pub struct BufferingChunkedBodyWriter<'a, C: Write> {
     conn: C,
     buf: &'a mut [u8],
@@ -36,6 +39,8 @@ pub struct BufferingChunkedBodyWriter<'a, C: Write> {
     allocated_header: usize,
     /// The position of the data in the chunk
     pos: usize,
+    /// Whether the last chunk is written and trailer fields are being written
+    trailers: bool,
     terminated: bool,
 }
 
@@ -53,33 +58,63 @@ where
             header_pos: written,
             pos: written + allocated_header,
             allocated_header,
+            trailers: false,
             terminated: false,
         }
     }
 
-    /// Terminate the request body by writing an empty chunk
-    pub async fn terminate(&mut self) -> Result<(), C::Error> {
-        assert!(!self.terminated);
+    /// Write the last, empty chunk after which trailer fields can be written
+    pub async fn start_trailers(&mut self) -> Result<(), C::Error> {
+        assert!(!self.terminated && !self.trailers);
 
         if self.pos > self.header_pos + self.allocated_header {
             // There are bytes written in the current chunk
             self.finish_current_chunk();
         }
 
-        if self.header_pos + EMPTY_CHUNK.len() > self.buf.len() {
-            // There is not enough space to fit the empty chunk in the buffer
-            self.emit_buffered().await?;
+        // From now on, the buffered data ends at `header_pos`
+        self.trailers = true;
+        self.allocated_header = 0;
+        self.pos = self.header_pos;
+        self.append_raw(LAST_CHUNK).await
+    }
+
+    /// Terminate the request body by writing an empty chunk, or by ending the trailers if they are started
+    pub async fn terminate(&mut self) -> Result<(), C::Error> {
+        assert!(!self.terminated);
+
+        if !self.trailers {
+            self.start_trailers().await?;
         }
 
-        self.buf[self.header_pos..self.header_pos + EMPTY_CHUNK.len()].copy_from_slice(EMPTY_CHUNK);
-        self.header_pos += EMPTY_CHUNK.len();
-        self.allocated_header = 0;
-        self.pos = self.header_pos + self.allocated_header;
-        self.emit_buffered().await?;
+        self.append_raw(NEWLINE).await?;
+        self.emit_raw().await?;
         self.terminated = true;
         Ok(())
     }
 
+    /// Append data as-is after the last chunk, emitting the buffer whenever it is full
+    async fn append_raw(&mut self, mut data: &[u8]) -> Result<(), C::Error> {
+        while !data.is_empty() {
+            if self.header_pos == self.buf.len() {
+                self.emit_raw().await?;
+            }
+            let len = usize::min(data.len(), self.buf.len() - self.header_pos);
+            self.buf[self.header_pos..self.header_pos + len].copy_from_slice(&data[..len]);
+            self.header_pos += len;
+            self.pos = self.header_pos;
+            data = &data[len..];
+        }
+        Ok(())
+    }
+
+    async fn emit_raw(&mut self) -> Result<(), C::Error> {
+        self.conn.write_all(&self.buf[..self.header_pos]).await?;
+        self.header_pos = 0;
+        self.pos = 0;
+        Ok(())
+    }
+
     /// Append data to the current chunk and return the number of bytes appended.
     /// This returns 0 if there is no current chunk to append to.
     fn append_current_chunk(&mut self, buf: &[u8]) -> usize {
@@ -146,6 +181,12 @@ where
             return Ok(0);
         }
 
+        if self.trailers {
+            // Trailer fields are written as-is
+            self.append_raw(buf).await.map_err(|e| e.kind())?;
+            return Ok(buf.len());
+        }
+
         let mut written = self.append_current_chunk(buf);
         if written == 0 {
             // Unable to append any data to the buffer
@@ -161,7 +202,9 @@ where
     }
 
     async fn flush(&mut self) -> Result<(), Self::Error> {
-        if self.pos > self.header_pos + self.allocated_header {
+        if self.trailers {
+            self.emit_raw().await.map_err(|e| e.kind())?;
+        } else if self.pos > self.header_pos + self.allocated_header {
             // There are bytes written in the current chunk
             self.finish_current_chunk();
             self.emit_buffered().await.map_err(|e| e.kind())?;
@@ -208,6 +251,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn can_get_max_chunk_header_size() {
         assert_eq!(0, get_max_chunk_header_size(0));
         assert_eq!(0, get_max_chunk_header_size(1));
@@ -381,4 +425,40 @@ mod tests {
         // Then
         assert_eq!(b"HELLO2\r\nBO\r\n2\r\nDY\r\n0\r\n\r\n", conn.as_slice());
     }
+
+    #[tokio::test]
+    async fn can_write_trailers() {
+        // Given
+        let mut conn = Vec::new();
+        let mut buf = [0; 1024];
+        buf[..5].copy_from_slice(b"HELLO");
+
+        // When
+        let mut writer = BufferingChunkedBodyWriter::new_with_data(&mut conn, &mut buf, 5);
+        writer.write_all(b"BODY").await.unwrap();
+        writer.start_trailers().await.unwrap();
+        writer.write_all(b"X-Checksum: abc\r\n").await.unwrap();
+        writer.terminate().await.unwrap();
+
+        // Then
+        assert_eq!(b"HELLO4\r\nBODY\r\n0\r\nX-Checksum: abc\r\n\r\n", conn.as_slice());
+    }
+
+    #[tokio::test]
+    async fn trailers_larger_than_buffer_are_emitted() {
+        // Given
+        let mut conn = Vec::new();
+        let mut buf = [0; 12];
+        buf[..5].copy_from_slice(b"HELLO");
+
+        // When
+        let mut writer = BufferingChunkedBodyWriter::new_with_data(&mut conn, &mut buf, 5);
+        writer.write_all(b"BO").await.unwrap();
+        writer.start_trailers().await.unwrap();
+        writer.write_all(b"X-Checksum: abc\r\n").await.unwrap(); // Cannot fit
+        writer.terminate().await.unwrap();
+
+        // Then
+        assert_eq!(b"HELLO2\r\nBO\r\n0\r\nX-Checksum: abc\r\n\r\n", conn.as_slice());
+    }
 }
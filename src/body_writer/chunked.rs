@@ -3,9 +3,13 @@ use core::mem::size_of;
 use embedded_io::{Error, ErrorType};
 use embedded_io_async::Write;
 
-pub struct ChunkedBodyWriter<C: Write>(C);
+/// A body writer that emits chunks as expected by the `Transfer-Encoding: chunked` header specification.
+///
+/// Once trailers are started, written data is passed through as-is.
+pub struct ChunkedBodyWriter<C: Write>(C, bool);
 
 const EMPTY_CHUNK: &[u8; 5] = b"0\r\n\r\n";
+const LAST_CHUNK: &[u8; 3] = b"0\r\n";
 const NEWLINE: &[u8; 2] = b"\r\n";
 
 impl<C> ChunkedBodyWriter<C>
@@ -13,12 +17,24 @@ where
     C: Write,
 {
     pub fn new(conn: C) -> Self {
-        Self(conn)
+        Self(conn, false)
     }
 
-    /// Terminate the request body by writing an empty chunk
+    /// Write the last, empty chunk after which trailer fields can be written
+    pub async fn start_trailers(&mut self) -> Result<(), C::Error> {
+        assert!(!self.1);
+        self.0.write_all(LAST_CHUNK).await?;
+        self.1 = true;
+        Ok(())
+    }
+
+    /// Terminate the request body by writing an empty chunk, or by ending the trailers if they are started
     pub async fn terminate(&mut self) -> Result<(), C::Error> {
-        self.0.write_all(EMPTY_CHUNK).await
+        if self.1 {
+            self.0.write_all(NEWLINE).await
+        } else {
+            self.0.write_all(EMPTY_CHUNK).await
+        }
     }
 }
 
@@ -39,6 +55,11 @@ where
     }
 
     async fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        if self.1 {
+            // Trailer fields are written as-is
+            return self.0.write_all(buf).await.map_err(|e| e.kind());
+        }
+
         let len = buf.len();
 
         // Do not write an empty chunk as that will terminate the body
@@ -98,4 +119,16 @@ mod tests {
         let len = write_chunked_header(&mut buf, 0x10);
         assert_eq!(b"10\r\n", &buf[..len]);
     }
+
+    #[tokio::test]
+    async fn can_write_trailers() {
+        let mut conn = Vec::new();
+        let mut writer = ChunkedBodyWriter::new(&mut conn);
+        writer.write_all(b"BODY").await.unwrap();
+        writer.start_trailers().await.unwrap();
+        writer.write_all(b"X-Checksum: abc\r\n").await.unwrap();
+        writer.terminate().await.unwrap();
+
+        assert_eq!(b"4\r\nBODY\r\n0\r\nX-Checksum: abc\r\n\r\n", conn.as_slice());
+    }
 }
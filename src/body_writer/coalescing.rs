@@ -0,0 +1,63 @@
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+
+/// A writer that collects small writes in a buffer, which is written to the connection once full or when finished.
+///
+/// This is used to send the request header in a single write, rather than one write per token, which would
+/// produce a segment per token on unbuffered connections.
+pub struct CoalescingWriter<C: Write, const N: usize> {
+    conn: C,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<C, const N: usize> CoalescingWriter<C, N>
+where
+    C: Write,
+{
+    pub fn new(conn: C) -> Self {
+        Self {
+            conn,
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Write the buffered bytes to the connection, without flushing it.
+    pub async fn finish(&mut self) -> Result<(), C::Error> {
+        if self.len > 0 {
+            self.conn.write_all(&self.buf[..self.len]).await?;
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<C, const N: usize> ErrorType for CoalescingWriter<C, N>
+where
+    C: Write,
+{
+    type Error = C::Error;
+}
+
+impl<C, const N: usize> Write for CoalescingWriter<C, N>
+where
+    C: Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.len + buf.len() > N {
+            self.finish().await?;
+            if buf.len() >= N {
+                return self.conn.write(buf).await;
+            }
+        }
+        self.buf[self.len..self.len + buf.len()].copy_from_slice(buf);
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.finish().await?;
+        self.conn.flush().await
+    }
+}
@@ -1,7 +1,9 @@
 mod buffering_chunked;
 mod chunked;
+mod coalescing;
 mod fixed;
 
 pub use buffering_chunked::BufferingChunkedBodyWriter;
 pub use chunked::ChunkedBodyWriter;
+pub use coalescing::CoalescingWriter;
 pub use fixed::FixedBodyWriter;
@@ -0,0 +1,183 @@
+/// Circuit breaker short-circuiting requests to hosts that keep failing.
+///
+use core::future::Future;
+use heapless::String;
+
+use crate::pool::MAX_HOST_LEN;
+use crate::Error;
+
+/// Tracks consecutive failures of up to `N` hosts, and rejects requests to a host with
+/// [`Error::CircuitOpen`] for a cool-down period once its failures reach the threshold.
+///
+/// After the cool-down period, a single request is let through: the circuit is closed again if it succeeds,
+/// and opened for another cool-down period if it fails.
+///
+/// The breaker has no notion of time, so all operations take a monotonic `now` timestamp in seconds.
+/// A breaker without slots (`N` = 0) tracks no hosts, so its circuits never open.
+pub struct CircuitBreaker<const N: usize> {
+    hosts: [HostState; N],
+    threshold: u32,
+    cool_down: u64,
+}
+
+struct HostState {
+    host: String<MAX_HOST_LEN>,
+    /// The number of consecutive failures, where zero means that the slot is unused.
+    failures: u32,
+    /// The timestamp of the last failure.
+    last_failure: u64,
+}
+
+impl HostState {
+    const fn empty() -> Self {
+        Self {
+            host: String::new(),
+            failures: 0,
+            last_failure: 0,
+        }
+    }
+}
+
+impl<const N: usize> CircuitBreaker<N> {
+    /// Create a circuit breaker that opens after `threshold` consecutive failures for `cool_down` seconds.
+    pub fn new(threshold: u32, cool_down: u64) -> Self {
+        Self {
+            hosts: core::array::from_fn(|_| HostState::empty()),
+            threshold: threshold.max(1),
+            cool_down,
+        }
+    }
+
+    fn find(&mut self, host: &str) -> Option<&mut HostState> {
+        self.hosts
+            .iter_mut()
+            .find(|state| state.failures > 0 && state.host.eq_ignore_ascii_case(host))
+    }
+
+    /// Check whether a request to `host` may be made.
+    ///
+    /// Returns [`Error::CircuitOpen`] while the circuit of the host is open.
+    pub fn check(&mut self, host: &str, now: u64) -> Result<(), Error> {
+        let (threshold, cool_down) = (self.threshold, self.cool_down);
+        match self.find(host) {
+            Some(state) if state.failures >= threshold && now < state.last_failure.saturating_add(cool_down) => {
+                debug!("Circuit for {} is open", host);
+                Err(Error::CircuitOpen)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a successful request to `host`, closing its circuit.
+    pub fn record_success(&mut self, host: &str) {
+        if let Some(state) = self.find(host) {
+            state.failures = 0;
+        }
+    }
+
+    /// Record a failed request to `host`.
+    ///
+    /// If all slots are in use by other hosts, the host with the oldest failure is forgotten.
+    pub fn record_failure(&mut self, host: &str, now: u64) {
+        let threshold = self.threshold;
+        let state = match self.find(host) {
+            Some(state) => state,
+            None => {
+                let Ok(name) = String::try_from(host) else {
+                    warn!("Host name is too long to be tracked");
+                    return;
+                };
+                // A breaker without slots tracks no hosts, so it never opens
+                let Some(state) = self
+                    .hosts
+                    .iter_mut()
+                    .min_by_key(|state| (state.failures > 0, state.last_failure))
+                else {
+                    return;
+                };
+                state.host = name;
+                state.failures = 0;
+                state
+            }
+        };
+
+        state.failures = state.failures.saturating_add(1);
+        state.last_failure = now;
+        if state.failures == threshold {
+            warn!("Opening circuit for {} after {} failures", host, threshold);
+        }
+    }
+
+    /// Run the request made by `request` to `host` if its circuit is closed, and record its outcome.
+    ///
+    /// Only errors are recorded as failures, so responses such as `503 Service Unavailable` should be turned into
+    /// an error by `request`, or recorded with [`CircuitBreaker::record_failure()`].
+    pub async fn run<F, T>(&mut self, host: &str, now: u64, request: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        self.check(host, now)?;
+        let result = request.await;
+        match &result {
+            Ok(_) => self.record_success(host),
+            Err(_) => self.record_failure(host, now),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold() {
+        let mut breaker = CircuitBreaker::<2>::new(2, 30);
+
+        breaker.record_failure("example.com", 10);
+        assert!(breaker.check("example.com", 10).is_ok());
+        breaker.record_failure("EXAMPLE.com", 11);
+        assert!(matches!(breaker.check("example.com", 20), Err(Error::CircuitOpen)));
+        assert!(breaker.check("other.com", 20).is_ok());
+
+        // Half-open after the cool-down, where a single failure opens the circuit again
+        assert!(breaker.check("example.com", 41).is_ok());
+        breaker.record_failure("example.com", 41);
+        assert!(matches!(breaker.check("example.com", 42), Err(Error::CircuitOpen)));
+
+        breaker.record_success("example.com");
+        assert!(breaker.check("example.com", 42).is_ok());
+    }
+
+    #[test]
+    fn forgets_oldest_failure() {
+        let mut breaker = CircuitBreaker::<2>::new(1, 30);
+
+        breaker.record_failure("a.com", 1);
+        breaker.record_failure("b.com", 2);
+        breaker.record_failure("c.com", 3);
+        assert!(breaker.check("a.com", 4).is_ok());
+        assert!(matches!(breaker.check("b.com", 4), Err(Error::CircuitOpen)));
+        assert!(matches!(breaker.check("c.com", 4), Err(Error::CircuitOpen)));
+    }
+
+    #[test]
+    fn never_opens_without_slots() {
+        let mut breaker = CircuitBreaker::<0>::new(1, 30);
+
+        breaker.record_failure("example.com", 1);
+        assert!(breaker.check("example.com", 2).is_ok());
+    }
+
+    #[tokio::test]
+    async fn records_outcome_of_request() {
+        let mut breaker = CircuitBreaker::<1>::new(1, 30);
+
+        let result = breaker.run("example.com", 0, async { Err::<(), _>(Error::Dns) }).await;
+        assert!(matches!(result, Err(Error::Dns)));
+        let result = breaker.run("example.com", 1, async { Ok(()) }).await;
+        assert!(matches!(result, Err(Error::CircuitOpen)));
+        let result = breaker.run("example.com", 30, async { Ok(()) }).await;
+        assert!(result.is_ok());
+    }
+}
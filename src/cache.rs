@@ -0,0 +1,438 @@
+/// Client-side HTTP cache implementing a subset of RFC 9111.
+///
+/// Responses to `GET` requests are kept in a user provided [`CacheStorage`], which may be backed by
+/// RAM or flash. Fresh responses are served without contacting the server, and stale responses are
+/// revalidated with a conditional request, such that an unchanged body is not transferred again.
+///
+/// The cache is a private cache without heuristic freshness: responses are only considered fresh
+/// if the server provides `Cache-Control: max-age` or `Expires`.
+use embedded_io_async::{Read, Write};
+
+use crate::client::HttpResource;
+use crate::request::{Request, RequestBuilder};
+use crate::response::{Response, Status, StatusCode};
+use crate::validator::{resource_key, Validators};
+use crate::Error;
+
+/// A stored response, without its body.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The timestamp at which the response was received or last revalidated.
+    pub stored_at: u64,
+    /// The age of the response when it was received, as reported by the `Age` header.
+    pub initial_age: u64,
+    /// The number of seconds the response is fresh for.
+    pub freshness_lifetime: u64,
+    /// Whether the response must be revalidated before every use.
+    pub no_cache: bool,
+    /// The validators of the response, used for revalidation.
+    pub validators: Validators,
+    /// The length of the response body.
+    pub body_len: usize,
+}
+
+impl CacheEntry {
+    /// Create an entry for a response received at `now`.
+    ///
+    /// Returns `None` if the response may not be stored, or if storing it would be useless because
+    /// it is neither fresh nor can be revalidated.
+    pub fn from_response<C, const MAX_HEADERS: usize>(
+        response: &Response<'_, '_, C, MAX_HEADERS>,
+        now: u64,
+    ) -> Option<Self>
+    where
+        C: Read,
+    {
+        if response.status != Status::Ok || response.cache_control().is_some_and(|cc| cc.no_store) {
+            return None;
+        }
+
+        let mut entry = CacheEntry {
+            stored_at: now,
+            initial_age: 0,
+            freshness_lifetime: 0,
+            no_cache: false,
+            validators: Validators::default(),
+            body_len: 0,
+        };
+        entry.update(response, now);
+
+        if entry.freshness_lifetime == 0 && entry.validators.is_empty() {
+            return None;
+        }
+        Some(entry)
+    }
+
+    /// Update the entry with the headers of a response, e.g. a `304 Not Modified` response to a revalidation.
+    pub fn update<C, const MAX_HEADERS: usize>(&mut self, response: &Response<'_, '_, C, MAX_HEADERS>, now: u64)
+    where
+        C: Read,
+    {
+        self.stored_at = now;
+        self.initial_age = response
+            .header("age")
+            .and_then(|age| core::str::from_utf8(age).ok())
+            .and_then(|age| age.parse().ok())
+            .unwrap_or(0);
+
+        let cache_control = response.cache_control();
+        if cache_control.is_some() || response.header("expires").is_some() {
+            self.no_cache = cache_control.is_some_and(|cc| cc.no_cache);
+            self.freshness_lifetime = match cache_control.and_then(|cc| cc.max_age) {
+                Some(max_age) => max_age,
+                // An invalid Expires value means that the response is already expired
+                None => match (response.expires(), response.date()) {
+                    (Some(expires), Some(date)) => expires.saturating_sub(date),
+                    _ => 0,
+                },
+            };
+        }
+
+        self.validators.update(response);
+    }
+
+    /// The current age of the response in seconds.
+    pub fn age(&self, now: u64) -> u64 {
+        self.initial_age + now.saturating_sub(self.stored_at)
+    }
+
+    /// Whether the response can be used without revalidation.
+    pub fn is_fresh(&self, now: u64) -> bool {
+        !self.no_cache && self.age(now) < self.freshness_lifetime
+    }
+}
+
+/// Storage of cache entries, e.g. in RAM or flash.
+pub trait CacheStorage {
+    /// Load the entry stored for `key`, copying its body into `body`.
+    ///
+    /// Returns `None` if no entry is stored, or if its body does not fit.
+    async fn load(&mut self, key: &str, body: &mut [u8]) -> Result<Option<CacheEntry>, Error>;
+
+    /// Store an entry and its body for `key`, replacing any previously stored entry.
+    async fn store(&mut self, key: &str, entry: &CacheEntry, body: &[u8]) -> Result<(), Error>;
+
+    /// Remove the entry stored for `key`, if any.
+    async fn remove(&mut self, key: &str) -> Result<(), Error>;
+}
+
+/// How a cached response was obtained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CacheStatus {
+    /// The stored response was fresh and the server was not contacted.
+    Hit,
+    /// The stored response was stale, but the server confirmed that it is still valid.
+    Revalidated,
+    /// The response was received from the server.
+    Miss,
+}
+
+/// A response served by a [`HttpCache`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CachedResponse<'b> {
+    /// The HTTP response status code.
+    pub status: StatusCode,
+    /// How the response was obtained.
+    pub cache_status: CacheStatus,
+    /// The response body.
+    pub body: &'b [u8],
+}
+
+/// A client-side HTTP cache for `GET` requests.
+///
+/// The cache has no notion of time, so all operations take a monotonic `now` timestamp in seconds.
+pub struct HttpCache<S>
+where
+    S: CacheStorage,
+{
+    storage: S,
+}
+
+impl<S> HttpCache<S>
+where
+    S: CacheStorage,
+{
+    /// Create a new cache using the provided storage.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Get the underlying storage.
+    pub fn storage(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Get a resource, serving it from the cache if possible.
+    ///
+    /// The path is relative to the base path of the resource, as for [`HttpResource::get()`].
+    /// The response headers are stored in the provided rx_buf, and the entire body is read into `body_buf`.
+    pub async fn get<'b, C>(
+        &mut self,
+        resource: &mut HttpResource<'_, C>,
+        path: &str,
+        now: u64,
+        rx_buf: &mut [u8],
+        body_buf: &'b mut [u8],
+    ) -> Result<CachedResponse<'b>, Error>
+    where
+        C: Read + Write,
+    {
+        let key = resource_key(resource.host, resource.base_path, path)?;
+
+        let stored = match self.storage.load(&key, body_buf).await {
+            Ok(stored) => stored.filter(|entry| entry.body_len <= body_buf.len()),
+            Err(e) => {
+                warn!("Unable to load cache entry: {:?}", e);
+                None
+            }
+        };
+
+        if let Some(entry) = stored.as_ref().filter(|entry| entry.is_fresh(now)) {
+            debug!("Serving fresh response from cache");
+            return Ok(CachedResponse {
+                status: StatusCode(200),
+                cache_status: CacheStatus::Hit,
+                body: &body_buf[..entry.body_len],
+            });
+        }
+
+        let validators = stored
+            .as_ref()
+            .map(|entry| entry.validators.clone())
+            .unwrap_or_default();
+        let mut request = Request::get(path).host(resource.host).build();
+        validators.apply(&mut request);
+
+        let response = resource.send(request, rx_buf).await?;
+
+        if let Some(mut entry) = stored.filter(|_| response.status == Status::NotModified) {
+            debug!("Stored response was revalidated");
+            entry.update(&response, now);
+            response.body().discard().await?;
+            if let Err(e) = self.storage.store(&key, &entry, &body_buf[..entry.body_len]).await {
+                warn!("Unable to update cache entry: {:?}", e);
+            }
+            return Ok(CachedResponse {
+                status: StatusCode(200),
+                cache_status: CacheStatus::Revalidated,
+                body: &body_buf[..entry.body_len],
+            });
+        }
+
+        let status = response.status;
+        let entry = CacheEntry::from_response(&response, now);
+        let body_len = response.body().reader().read_to_end(body_buf).await?;
+
+        let result = match entry {
+            Some(mut entry) => {
+                entry.body_len = body_len;
+                self.storage.store(&key, &entry, &body_buf[..body_len]).await
+            }
+            None if status == Status::Ok => self.storage.remove(&key).await,
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("Unable to store cache entry: {:?}", e);
+        }
+
+        Ok(CachedResponse {
+            status,
+            cache_status: CacheStatus::Miss,
+            body: &body_buf[..body_len],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpConnection;
+    use crate::fake::FakeConnection;
+    use crate::response::ResponseConfig;
+
+    #[derive(Default)]
+    struct MemoryStorage(Option<(std::string::String, CacheEntry, Vec<u8>)>);
+
+    impl CacheStorage for MemoryStorage {
+        async fn load(&mut self, key: &str, body: &mut [u8]) -> Result<Option<CacheEntry>, Error> {
+            match &self.0 {
+                Some((k, entry, data)) if k == key && data.len() <= body.len() => {
+                    body[..data.len()].copy_from_slice(data);
+                    Ok(Some(entry.clone()))
+                }
+                _ => Ok(None),
+            }
+        }
+
+        async fn store(&mut self, key: &str, entry: &CacheEntry, body: &[u8]) -> Result<(), Error> {
+            self.0 = Some((key.into(), entry.clone(), body.to_vec()));
+            Ok(())
+        }
+
+        async fn remove(&mut self, key: &str) -> Result<(), Error> {
+            if self.0.as_ref().is_some_and(|(k, _, _)| k == key) {
+                self.0 = None;
+            }
+            Ok(())
+        }
+    }
+
+    /// Get `/config` from the cache, returning the cache status, the body and the request sent to the server.
+    async fn get(
+        cache: &mut HttpCache<MemoryStorage>,
+        response: &'static [u8],
+        now: u64,
+    ) -> (CacheStatus, Vec<u8>, Vec<u8>) {
+        let mut conn = FakeConnection::new(response);
+        let mut resource = HttpResource {
+            conn: HttpConnection::Plain(&mut conn),
+            host: "example.com",
+            base_path: "/api",
+            response_config: ResponseConfig::new(),
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        };
+        let mut rx_buf = [0; 256];
+        let mut body_buf = [0; 64];
+        let response = cache
+            .get(&mut resource, "/config", now, &mut rx_buf, &mut body_buf)
+            .await
+            .unwrap();
+        let result = (response.cache_status, response.body.to_vec());
+        (result.0, result.1, conn.request)
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_response_from_cache() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        let (status, body, _) = get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+        assert_eq!(CacheStatus::Miss, status);
+        assert_eq!(b"HELLO", body.as_slice());
+
+        let (status, body, request) = get(&mut cache, b"", 159).await;
+        assert_eq!(CacheStatus::Hit, status);
+        assert_eq!(b"HELLO", body.as_slice());
+        assert!(request.is_empty());
+    }
+
+    #[tokio::test]
+    async fn revalidates_stale_response() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nETag: \"v1\"\r\nLast-Modified: Sun, 06 Nov 1994 08:49:37 GMT\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+
+        let (status, body, request) = get(
+            &mut cache,
+            b"HTTP/1.1 304 Not Modified\r\nCache-Control: max-age=120\r\n\r\n",
+            160,
+        )
+        .await;
+        assert_eq!(CacheStatus::Revalidated, status);
+        assert_eq!(b"HELLO", body.as_slice());
+        assert_eq!(
+            b"GET /api/config HTTP/1.1\r\nHost: example.com\r\nIf-None-Match: \"v1\"\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+            request.as_slice()
+        );
+
+        // The revalidated response is fresh again
+        let (status, _, _) = get(&mut cache, b"", 279).await;
+        assert_eq!(CacheStatus::Hit, status);
+    }
+
+    #[tokio::test]
+    async fn replaces_changed_response() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+
+        let (status, body, _) = get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nETag: \"v2\"\r\nContent-Length: 5\r\n\r\nWORLD",
+            100,
+        )
+        .await;
+        assert_eq!(CacheStatus::Miss, status);
+        assert_eq!(b"WORLD", body.as_slice());
+        assert_eq!(
+            Some("\"v2\""),
+            cache.storage().0.as_ref().unwrap().1.validators.etag.as_deref()
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_store_uncacheable_responses() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nCache-Control: no-store, max-age=60\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+        assert!(cache.storage().0.is_none());
+
+        get(&mut cache, b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHELLO", 100).await;
+        assert!(cache.storage().0.is_none());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 404 Not Found\r\nCache-Control: max-age=60\r\nContent-Length: 0\r\n\r\n",
+            100,
+        )
+        .await;
+        assert!(cache.storage().0.is_none());
+    }
+
+    #[tokio::test]
+    async fn always_revalidates_no_cache_response() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nCache-Control: no-cache, max-age=60\r\nETag: \"v1\"\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+
+        let (status, _, request) = get(&mut cache, b"HTTP/1.1 304 Not Modified\r\n\r\n", 101).await;
+        assert_eq!(CacheStatus::Revalidated, status);
+        assert!(!request.is_empty());
+    }
+
+    #[tokio::test]
+    async fn can_compute_freshness_from_expires() {
+        let mut cache = HttpCache::new(MemoryStorage::default());
+
+        get(
+            &mut cache,
+            b"HTTP/1.1 200 OK\r\nDate: Sun, 06 Nov 1994 08:49:37 GMT\r\nExpires: Sun, 06 Nov 1994 08:50:37 GMT\r\nAge: 10\r\nContent-Length: 5\r\n\r\nHELLO",
+            100,
+        )
+        .await;
+
+        let entry = &cache.storage().0.as_ref().unwrap().1;
+        assert_eq!(60, entry.freshness_lifetime);
+        assert!(entry.is_fresh(149));
+        assert!(!entry.is_fresh(150));
+    }
+}
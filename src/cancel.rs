@@ -0,0 +1,210 @@
+/// Cooperative cancellation of in-flight requests.
+///
+use embedded_io::{ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::timeout::unless;
+use crate::transport::ConnectionDecorator;
+
+/// A token through which a supervisor requests in-flight requests to be cancelled.
+///
+/// This is typically implemented on a reference to a signal of the executor in use.
+pub trait Cancellation {
+    /// Wait until cancellation is requested, returning immediately if it already has been.
+    async fn cancelled(&self);
+}
+
+impl<K> Cancellation for &K
+where
+    K: Cancellation,
+{
+    async fn cancelled(&self) {
+        K::cancelled(self).await
+    }
+}
+
+/// Wait for `future` to complete, unless cancellation is requested first, in which case `None` is returned.
+///
+/// This can be used to cancel establishing a connection, while [`Cancellable`] cancels the reads and writes
+/// on an established connection.
+pub async fn with_cancellation<K, F>(token: &K, future: F) -> Option<F::Output>
+where
+    K: Cancellation,
+    F: core::future::Future,
+{
+    unless(future, token.cancelled()).await
+}
+
+/// A [`ConnectionDecorator`] that makes the reads and writes on connections cancellable.
+///
+/// When cancellation is requested, the pending read or write fails with [`ErrorKind::Interrupted`],
+/// as does every later operation, such that the connection is never used again and is closed when dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct Cancellable<K>(pub K);
+
+impl<C, K> ConnectionDecorator<C> for Cancellable<K>
+where
+    C: Read + Write,
+    K: Cancellation + Clone,
+{
+    type Connection = CancellableConnection<C, K>;
+
+    fn decorate(&self, conn: C) -> Self::Connection {
+        CancellableConnection {
+            conn,
+            token: self.0.clone(),
+            cancelled: false,
+        }
+    }
+}
+
+/// A connection whose reads and writes are aborted when cancellation is requested.
+pub struct CancellableConnection<C, K>
+where
+    C: Read + Write,
+{
+    conn: C,
+    token: K,
+    cancelled: bool,
+}
+
+impl<C, K> CancellableConnection<C, K>
+where
+    C: Read + Write,
+{
+    /// Whether an operation on the connection was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl<C, K> ErrorType for CancellableConnection<C, K>
+where
+    C: Read + Write,
+{
+    type Error = ErrorKind;
+}
+
+impl<C, K> CancellableConnection<C, K>
+where
+    C: Read + Write,
+    K: Cancellation,
+{
+    /// Map the outcome of an operation, remembering whether it was cancelled.
+    fn outcome<T, E: embedded_io::Error>(&mut self, outcome: Option<Result<T, E>>) -> Result<T, ErrorKind> {
+        match outcome {
+            Some(result) => result.map_err(|e| e.kind()),
+            None => {
+                debug!("Connection operation cancelled");
+                self.cancelled = true;
+                Err(ErrorKind::Interrupted)
+            }
+        }
+    }
+}
+
+impl<C, K> Read for CancellableConnection<C, K>
+where
+    C: Read + Write,
+    K: Cancellation,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.cancelled {
+            return Err(ErrorKind::Interrupted);
+        }
+        let outcome = unless(self.conn.read(buf), self.token.cancelled()).await;
+        self.outcome(outcome)
+    }
+}
+
+impl<C, K> Write for CancellableConnection<C, K>
+where
+    C: Read + Write,
+    K: Cancellation,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.cancelled {
+            return Err(ErrorKind::Interrupted);
+        }
+        let outcome = unless(self.conn.write(buf), self.token.cancelled()).await;
+        self.outcome(outcome)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        if self.cancelled {
+            return Err(ErrorKind::Interrupted);
+        }
+        let outcome = unless(self.conn.flush(), self.token.cancelled()).await;
+        self.outcome(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    use super::*;
+
+    /// A token that is cancelled once it has been checked a number of times.
+    struct CancelAfter(Cell<usize>);
+
+    impl Cancellation for CancelAfter {
+        async fn cancelled(&self) {
+            if self.0.get() == 0 {
+                return;
+            }
+            self.0.set(self.0.get() - 1);
+            core::future::pending().await
+        }
+    }
+
+    struct EchoConnection(Vec<u8>);
+
+    impl ErrorType for EchoConnection {
+        type Error = Infallible;
+    }
+
+    impl Read for EchoConnection {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let len = buf.len().min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0.drain(..len);
+            Ok(len)
+        }
+    }
+
+    impl Write for EchoConnection {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_connection_is_not_used_again() {
+        let token = CancelAfter(Cell::new(2));
+        let mut conn = Cancellable(&token).decorate(EchoConnection(Vec::new()));
+
+        conn.write_all(b"PING").await.unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(4, conn.read(&mut buf).await.unwrap());
+        assert!(!conn.is_cancelled());
+
+        assert_eq!(Err(ErrorKind::Interrupted), conn.write(b"PING").await);
+        assert!(conn.is_cancelled());
+        assert_eq!(Err(ErrorKind::Interrupted), conn.read(&mut buf).await);
+    }
+
+    #[tokio::test]
+    async fn can_cancel_future() {
+        assert_eq!(
+            Some(1),
+            with_cancellation(&CancelAfter(Cell::new(1)), async { 1 }).await
+        );
+        assert_eq!(
+            None,
+            with_cancellation(&CancelAfter(Cell::new(0)), core::future::pending::<()>()).await
+        );
+    }
+}
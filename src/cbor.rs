@@ -0,0 +1,125 @@
+/// Encoding request bodies and decoding response bodies as CBOR using `minicbor`, for bandwidth-sensitive
+/// deployments preferring CBOR over JSON.
+///
+use embedded_io_async::Write;
+use minicbor::Encode;
+
+use crate::request::RequestBody;
+use crate::Error;
+
+/// The number of bytes encoded at a time while the body is written.
+const CHUNK_LEN: usize = 128;
+
+/// A request body that encodes a value as CBOR while it is written.
+///
+/// The value is encoded again for every chunk of [`CHUNK_LEN`] bytes, as the encoder is not async, so this is
+/// intended for small values. Set the content type to [`ContentType::ApplicationCbor`](crate::headers::ContentType).
+pub struct CborBody<'a, T> {
+    value: &'a T,
+    len: usize,
+}
+
+impl<'a, T> CborBody<'a, T>
+where
+    T: Encode<()>,
+{
+    /// Create a body encoding `value`, which fails with [`Error::Codec`] if the value cannot be encoded.
+    pub fn new(value: &'a T) -> Result<Self, Error> {
+        let mut counter = Counter(0);
+        minicbor::encode(value, &mut counter).map_err(|_| Error::Codec)?;
+        Ok(Self { value, len: counter.0 })
+    }
+}
+
+impl<T> RequestBody for CborBody<'_, T>
+where
+    T: Encode<()>,
+{
+    fn len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+
+    async fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        let mut chunk = [0; CHUNK_LEN];
+        let mut written = 0;
+        while written < self.len {
+            let mut window = Window {
+                skip: written,
+                buf: &mut chunk,
+                len: 0,
+            };
+            // Encoding stops once the chunk is full
+            minicbor::encode(self.value, &mut window).ok();
+            let len = window.len;
+            if len == 0 {
+                // The body is incomplete, which is detected by the client
+                break;
+            }
+            writer.write_all(&chunk[..len]).await?;
+            written += len;
+        }
+        Ok(())
+    }
+
+    async fn rewind(&self) -> bool {
+        true
+    }
+}
+
+/// Counts the encoded bytes.
+struct Counter(usize);
+
+impl minicbor::encode::Write for Counter {
+    type Error = core::convert::Infallible;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.0 += buf.len();
+        Ok(())
+    }
+}
+
+/// Captures the encoded bytes following the first `skip` bytes, until `buf` is full.
+struct Window<'a> {
+    skip: usize,
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+struct Full;
+
+impl minicbor::encode::Write for Window<'_> {
+    type Error = Full;
+
+    fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        let skipped = self.skip.min(data.len());
+        self.skip -= skipped;
+        let data = &data[skipped..];
+        let len = data.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&data[..len]);
+        self.len += len;
+        if len < data.len() {
+            Err(Full)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_write_body_in_chunks() {
+        let value: [u32; 100] = core::array::from_fn(|i| i as u32 * 1000);
+        let body = CborBody::new(&value).unwrap();
+        let len = body.len().unwrap();
+        assert!(len > CHUNK_LEN);
+
+        let mut written = Vec::new();
+        body.write(&mut written).await.unwrap();
+        let mut expected = [0; 512];
+        minicbor::encode(&value, expected.as_mut_slice()).unwrap();
+        assert_eq!(&expected[..len], written.as_slice());
+    }
+}
@@ -0,0 +1,132 @@
+/// Loading certificates and keys from an `embedded-storage` region, so they can be updated in the field.
+///
+/// The region holds a sequence of records, each made of a one byte tag, a two byte big-endian length and
+/// the value. The sequence ends at the end of the region or at a record with tag `0x00` or `0xFF`, so erased
+/// flash ends the sequence. Records with an unknown tag are skipped.
+use embedded_storage::ReadStorage;
+
+use crate::Error;
+
+/// Tag of a record holding a root CA certificate in DER format.
+pub const TAG_CA_CERTIFICATE: u8 = 0x01;
+/// Tag of a record holding a client certificate in DER format.
+pub const TAG_CLIENT_CERTIFICATE: u8 = 0x02;
+/// Tag of a record holding the private key of the client certificate.
+pub const TAG_PRIVATE_KEY: u8 = 0x03;
+
+const HEADER_LEN: usize = 3;
+
+/// Certificates and keys loaded from storage.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StoredCertificates<'b> {
+    pub ca_certificate: Option<&'b [u8]>,
+    pub client_certificate: Option<&'b [u8]>,
+    pub private_key: Option<&'b [u8]>,
+}
+
+/// Load the records stored in `storage` starting at `offset`, copying their values into `buf`.
+///
+/// [`Error::BufferTooSmall`] is returned if the values do not fit in `buf`, and [`Error::Codec`] if a record
+/// exceeds the capacity of the storage.
+pub fn load<'b, S>(storage: &mut S, offset: u32, buf: &'b mut [u8]) -> Result<StoredCertificates<'b>, Error>
+where
+    S: ReadStorage,
+{
+    let capacity = storage.capacity();
+    let mut certificates = StoredCertificates::default();
+    let mut remaining = buf;
+    let mut pos = offset as usize;
+
+    while pos + HEADER_LEN <= capacity {
+        let mut header = [0; HEADER_LEN];
+        storage.read(pos as u32, &mut header).map_err(|_| Error::Storage)?;
+        let tag = header[0];
+        let len = u16::from_be_bytes([header[1], header[2]]) as usize;
+        if tag == 0x00 || tag == 0xFF {
+            break;
+        }
+
+        let value_pos = pos + HEADER_LEN;
+        if value_pos + len > capacity {
+            return Err(Error::Codec);
+        }
+        pos = value_pos + len;
+
+        let slot = match tag {
+            TAG_CA_CERTIFICATE => &mut certificates.ca_certificate,
+            TAG_CLIENT_CERTIFICATE => &mut certificates.client_certificate,
+            TAG_PRIVATE_KEY => &mut certificates.private_key,
+            _ => {
+                debug!("Skipping stored record with unknown tag {}", tag);
+                continue;
+            }
+        };
+
+        if len > remaining.len() {
+            return Err(Error::BufferTooSmall);
+        }
+        let (value, rest) = core::mem::take(&mut remaining).split_at_mut(len);
+        storage.read(value_pos as u32, value).map_err(|_| Error::Storage)?;
+        remaining = rest;
+        *slot = Some(value);
+    }
+
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeStorage(Vec<u8>);
+
+    impl ReadStorage for FakeStorage {
+        type Error = ();
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn can_load_certificates() {
+        let mut storage = FakeStorage(
+            [
+                b"HEADER".as_slice(),
+                &[TAG_CA_CERTIFICATE, 0, 2],
+                b"CA",
+                &[0x42, 0, 3],
+                b"???",
+                &[TAG_CLIENT_CERTIFICATE, 0, 4],
+                b"CERT",
+                &[TAG_PRIVATE_KEY, 0, 3],
+                b"KEY",
+                &[0xFF; 8],
+            ]
+            .concat(),
+        );
+        let mut buf = [0; 16];
+
+        let certificates = load(&mut storage, 6, &mut buf).unwrap();
+        assert_eq!(Some(b"CA".as_slice()), certificates.ca_certificate);
+        assert_eq!(Some(b"CERT".as_slice()), certificates.client_certificate);
+        assert_eq!(Some(b"KEY".as_slice()), certificates.private_key);
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        let mut storage = FakeStorage([[TAG_CA_CERTIFICATE, 0, 4].as_slice(), b"CA"].concat());
+        let mut buf = [0; 16];
+        assert!(matches!(load(&mut storage, 0, &mut buf), Err(Error::Codec)));
+
+        let mut storage = FakeStorage([[TAG_CA_CERTIFICATE, 0, 4].as_slice(), b"ROOT"].concat());
+        let mut buf = [0; 2];
+        assert!(matches!(load(&mut storage, 0, &mut buf), Err(Error::BufferTooSmall)));
+    }
+}
@@ -1,34 +1,72 @@
 /// Client using embedded-nal-async traits to establish connections and perform HTTP requests.
 ///
+use crate::auth::{Credentials, TokenProvider};
 use crate::body_writer::{BufferingChunkedBodyWriter, ChunkedBodyWriter, FixedBodyWriter};
 use crate::headers::ContentType;
+use crate::metrics::{record_error, record_request, Event, Recorder};
+use crate::proxy::{ForwardProxy, Proxy};
+use crate::reader::BufferingReader;
 use crate::request::*;
 use crate::response::*;
+use crate::resume::Resumed;
+use crate::retry::Delay;
+use crate::signing::{self, RequestSigner};
+use crate::timeout::{with_timeout, TimeoutConnection, Timeouts};
+use crate::tls::TlsProvider;
+use crate::transport::{ConnectionDecorator, Undecorated};
+use crate::url::{ip_literal, Url};
+use crate::validator::{self, resource_key, Conditional, ValidatorStore};
 use crate::Error;
 use buffered_io::asynch::BufferedWrite;
-use core::net::SocketAddr;
+use core::net::{IpAddr, SocketAddr};
 use embedded_io::Error as _;
-use embedded_io::ErrorType;
+use embedded_io::{ErrorKind, ErrorType, ReadReady};
 use embedded_io_async::{Read, Write};
-use embedded_nal_async::{Dns, TcpConnect};
-use nourl::{Url, UrlScheme};
+use embedded_nal_async::{AddrType, Dns, TcpConnect};
+use nourl::UrlScheme;
 
 /// An async HTTP client that can establish a TCP connection and perform
 /// HTTP requests.
-pub struct HttpClient<'a, T, D>
+///
+/// Established connections are wrapped by the [`ConnectionDecorator`] `W`, see [`HttpClient::decorate()`].
+pub struct HttpClient<'a, T, D, W = Undecorated>
 where
     T: TcpConnect + 'a,
     D: Dns + 'a,
 {
     client: &'a T,
     dns: &'a D,
-    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    decorator: W,
+    #[cfg(feature = "embedded-tls")]
     tls: Option<TlsConfig<'a>>,
+    #[cfg(feature = "esp-mbedtls")]
+    mbedtls: Option<MbedTlsConfig<'a>>,
+    #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    backend: TlsBackend,
+    proxy: Option<Proxy<'a>>,
+    response_config: ResponseConfig,
+    metrics: Option<&'a dyn Recorder>,
+    rx_buf: &'a mut [u8],
+    tx_buf: Option<&'a mut [u8]>,
+}
+
+/// The TLS backend used for HTTPS connections when both `embedded-tls` and `esp-mbedtls` are enabled.
+#[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TlsBackend {
+    #[default]
+    EmbeddedTls,
+    MbedTls,
 }
 
 /// Type for TLS configuration of HTTP client.
+#[cfg(all(feature = "esp-mbedtls", not(feature = "embedded-tls")))]
+pub type TlsConfig<'a, const RX_SIZE: usize = 4096, const TX_SIZE: usize = 4096> = MbedTlsConfig<'a, RX_SIZE, TX_SIZE>;
+
+/// Type for TLS configuration of HTTP client when using esp-mbedtls.
 #[cfg(feature = "esp-mbedtls")]
-pub struct TlsConfig<'a, const RX_SIZE: usize = 4096, const TX_SIZE: usize = 4096> {
+pub struct MbedTlsConfig<'a, const RX_SIZE: usize = 4096, const TX_SIZE: usize = 4096> {
     /// Minimum TLS version for the connection
     version: crate::TlsVersion,
 
@@ -42,10 +80,40 @@ pub struct TlsConfig<'a, const RX_SIZE: usize = 4096, const TX_SIZE: usize = 409
 /// Type for TLS configuration of HTTP client.
 #[cfg(feature = "embedded-tls")]
 pub struct TlsConfig<'a> {
-    seed: u64,
+    rng: TlsRng<'a>,
     read_buffer: &'a mut [u8],
     write_buffer: &'a mut [u8],
     verify: TlsVerify<'a>,
+    alpn_protocols: Option<&'a [&'a [u8]]>,
+    client_certificate: Option<ClientCertificate<'a>>,
+    cipher_suite: CipherSuite,
+}
+
+/// Cipher suites that can be used for TLS connections.
+#[cfg(feature = "embedded-tls")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CipherSuite {
+    #[default]
+    Aes128GcmSha256,
+    Aes256GcmSha384,
+}
+
+/// The source of randomness for the TLS handshakes.
+#[cfg(feature = "embedded-tls")]
+enum TlsRng<'a> {
+    /// A seed for a pseudo-random generator, which is replaced with the next seed after each handshake
+    Seed(u64),
+    /// A cryptographically secure random generator provided by the platform, e.g. a hardware TRNG
+    Entropy(&'a mut dyn rand_core::CryptoRngCore),
+}
+
+/// A client certificate used to authenticate to the remote host.
+#[cfg(feature = "embedded-tls")]
+#[derive(Clone, Copy)]
+struct ClientCertificate<'a> {
+    certificate: &'a [u8],
+    private_key: &'a [u8],
 }
 
 /// Supported verification modes.
@@ -55,22 +123,103 @@ pub enum TlsVerify<'a> {
     None,
     /// Use pre-shared keys for verifying
     Psk { identity: &'a [u8], psk: &'a [u8] },
+    /// Verify the certificate chain of the remote host against a root CA certificate in DER format
+    ///
+    /// The validity period of the certificates is checked against the `time` source, and connecting fails with
+    /// [`Error::UnknownTime`] while it does not know the time. Passing `None` explicitly disables the check, which
+    /// is required on devices without a clock. The time is passed to embedded-tls through a static shared by all
+    /// clients, so concurrent handshakes with different time sources may see each other's time. This requires a
+    /// `critical-section` implementation.
+    #[cfg(feature = "webpki")]
+    Ca {
+        certificate: &'a [u8],
+        time: Option<&'a dyn crate::date::TimeSource>,
+    },
+    /// Use the pre-shared key configured for the host of the url
+    ///
+    /// Connecting to a host without a pre-shared key fails with [`Error::NoTlsConfig`].
+    PskPerHost(&'a [HostPsk<'a>]),
+}
+
+/// A pre-shared key used for connections to a host.
+#[cfg(feature = "embedded-tls")]
+#[derive(Clone, Copy, Debug)]
+pub struct HostPsk<'a> {
+    pub host: &'a str,
+    pub identity: &'a [u8],
+    pub psk: &'a [u8],
+}
+
+/// Find the pre-shared key for `host`, which is compared case-insensitively.
+#[cfg(feature = "embedded-tls")]
+fn find_host_psk<'a>(psks: &[HostPsk<'a>], host: &str) -> Option<HostPsk<'a>> {
+    psks.iter().find(|psk| psk.host.eq_ignore_ascii_case(host)).copied()
 }
 
 #[cfg(feature = "embedded-tls")]
 impl<'a> TlsConfig<'a> {
     pub fn new(seed: u64, read_buffer: &'a mut [u8], write_buffer: &'a mut [u8], verify: TlsVerify<'a>) -> Self {
+        Self::new_with_rng_source(TlsRng::Seed(seed), read_buffer, write_buffer, verify)
+    }
+
+    /// Create a TLS configuration that seeds each handshake from a cryptographically secure random generator.
+    ///
+    /// This should be preferred over [`TlsConfig::new()`] whenever the platform provides a source of entropy,
+    /// as a predictable seed makes the handshakes predictable.
+    pub fn new_with_rng(
+        rng: &'a mut dyn rand_core::CryptoRngCore,
+        read_buffer: &'a mut [u8],
+        write_buffer: &'a mut [u8],
+        verify: TlsVerify<'a>,
+    ) -> Self {
+        Self::new_with_rng_source(TlsRng::Entropy(rng), read_buffer, write_buffer, verify)
+    }
+
+    fn new_with_rng_source(
+        rng: TlsRng<'a>,
+        read_buffer: &'a mut [u8],
+        write_buffer: &'a mut [u8],
+        verify: TlsVerify<'a>,
+    ) -> Self {
         Self {
-            seed,
+            rng,
             write_buffer,
             read_buffer,
             verify,
+            alpn_protocols: None,
+            client_certificate: None,
+            cipher_suite: CipherSuite::Aes128GcmSha256,
         }
     }
+
+    /// Set the cipher suite offered to the server, which is [`CipherSuite::Aes128GcmSha256`] by default.
+    pub fn with_cipher_suite(mut self, cipher_suite: CipherSuite) -> Self {
+        self.cipher_suite = cipher_suite;
+        self
+    }
+
+    /// Set the protocols offered to the server using ALPN, in order of preference, e.g. `&[b"http/1.1"]`.
+    ///
+    /// The handshake fails if the server selects a protocol that was not offered.
+    pub fn with_alpn_protocols(mut self, protocols: &'a [&'a [u8]]) -> Self {
+        self.alpn_protocols = Some(protocols);
+        self
+    }
+
+    /// Authenticate to the remote host using a client certificate in DER format and its private key.
+    ///
+    /// The certificate is sent when requested by the server, which can be combined with any [`TlsVerify`] mode.
+    pub fn with_client_certificate(mut self, certificate: &'a [u8], private_key: &'a [u8]) -> Self {
+        self.client_certificate = Some(ClientCertificate {
+            certificate,
+            private_key,
+        });
+        self
+    }
 }
 
 #[cfg(feature = "esp-mbedtls")]
-impl<'a, const RX_SIZE: usize, const TX_SIZE: usize> TlsConfig<'a, RX_SIZE, TX_SIZE> {
+impl<'a, const RX_SIZE: usize, const TX_SIZE: usize> MbedTlsConfig<'a, RX_SIZE, TX_SIZE> {
     pub fn new(
         version: crate::TlsVersion,
         certificates: crate::Certificates<'a>,
@@ -84,54 +233,692 @@ impl<'a, const RX_SIZE: usize, const TX_SIZE: usize> TlsConfig<'a, RX_SIZE, TX_S
     }
 }
 
+/// The TLS configuration used to establish a connection.
+#[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+pub(crate) enum TlsRef<'conn, 'a> {
+    #[cfg(feature = "embedded-tls")]
+    EmbeddedTls(&'conn mut TlsConfig<'a>),
+    #[cfg(feature = "esp-mbedtls")]
+    MbedTls(&'conn mut MbedTlsConfig<'a>),
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<'conn, 'a> From<&'conn mut TlsConfig<'a>> for TlsRef<'conn, 'a> {
+    fn from(config: &'conn mut TlsConfig<'a>) -> Self {
+        Self::EmbeddedTls(config)
+    }
+}
+
+#[cfg(feature = "esp-mbedtls")]
+impl<'conn, 'a> From<&'conn mut MbedTlsConfig<'a>> for TlsRef<'conn, 'a> {
+    fn from(config: &'conn mut MbedTlsConfig<'a>) -> Self {
+        Self::MbedTls(config)
+    }
+}
+
 impl<'a, T, D> HttpClient<'a, T, D>
 where
     T: TcpConnect + 'a,
     D: Dns + 'a,
 {
     /// Create a new HTTP client for a given connection handle and a target host.
+    ///
+    /// Plain HTTP requests are written directly to the connection unless a buffer is set using
+    /// [`HttpClient::tx_buffer()`] or the client is created with [`HttpClient::new_buffered()`].
     pub fn new(client: &'a T, dns: &'a D) -> Self {
         Self {
             client,
             dns,
-            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            #[cfg(feature = "embedded-tls")]
             tls: None,
+            #[cfg(feature = "esp-mbedtls")]
+            mbedtls: None,
+            #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            backend: TlsBackend::EmbeddedTls,
+            proxy: None,
+            response_config: ResponseConfig::new(),
+            metrics: None,
+            rx_buf: &mut [],
+            tx_buf: None,
+            decorator: Undecorated,
         }
     }
 
+    /// Create a new HTTP client which receives the responses in `rx_buf`, and buffers the requests written to
+    /// plain HTTP connections in `tx_buf`.
+    ///
+    /// The requests of the client can be sent using [`HttpRequestHandle::fetch()`], without passing a buffer
+    /// to each request.
+    pub fn new_with_buffers(client: &'a T, dns: &'a D, rx_buf: &'a mut [u8], tx_buf: &'a mut [u8]) -> Self {
+        Self::new(client, dns).rx_buffer(rx_buf).tx_buffer(tx_buf)
+    }
+
+    /// Create a new HTTP client which buffers the requests written to plain HTTP connections in `tx_buf`.
+    pub fn new_buffered(client: &'a T, dns: &'a D, tx_buf: &'a mut [u8]) -> Self {
+        Self::new(client, dns).tx_buffer(tx_buf)
+    }
+
     /// Create a new HTTP client for a given connection handle and a target host.
     #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
     pub fn new_with_tls(client: &'a T, dns: &'a D, tls: TlsConfig<'a>) -> Self {
-        Self {
-            client,
-            dns,
-            tls: Some(tls),
+        let mut this = Self::new(client, dns);
+        #[cfg(feature = "embedded-tls")]
+        {
+            this.tls = Some(tls);
+        }
+        #[cfg(not(feature = "embedded-tls"))]
+        {
+            this.mbedtls = Some(tls);
+        }
+        this
+    }
+}
+
+impl<'a, T, D, W> HttpClient<'a, T, D, W>
+where
+    T: TcpConnect + 'a,
+    D: Dns + 'a,
+{
+    /// Wrap the connections established by this client with the provided decorator.
+    ///
+    /// The decorator wraps the TCP connection before the TLS handshake, and can be used for e.g. counting bytes,
+    /// throttling or offloading.
+    pub fn decorate<V>(self, decorator: V) -> HttpClient<'a, T, D, V> {
+        HttpClient {
+            client: self.client,
+            dns: self.dns,
+            decorator,
+            #[cfg(feature = "embedded-tls")]
+            tls: self.tls,
+            #[cfg(feature = "esp-mbedtls")]
+            mbedtls: self.mbedtls,
+            #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            backend: self.backend,
+            proxy: self.proxy,
+            response_config: self.response_config,
+            metrics: self.metrics,
+            rx_buf: self.rx_buf,
+            tx_buf: self.tx_buf,
         }
     }
 
+    /// Set the esp-mbedtls configuration, which is used for HTTPS connections when selecting [`TlsBackend::MbedTls`].
+    #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    pub fn with_mbedtls(mut self, config: MbedTlsConfig<'a>) -> Self {
+        self.mbedtls = Some(config);
+        self
+    }
+
+    /// Select the TLS backend used for subsequent HTTPS connections.
+    #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    pub fn tls_backend(&mut self, backend: TlsBackend) {
+        self.backend = backend;
+    }
+
+    /// Set the configuration used to parse the responses of requests made with this client.
+    pub fn response_config(mut self, config: ResponseConfig) -> Self {
+        self.response_config = config;
+        self
+    }
+
+    /// Send requests through the provided proxy.
+    ///
+    /// HTTPS connections are tunneled through the proxy using `CONNECT`, while plain HTTP requests are sent to
+    /// the proxy with the absolute url as request target.
+    pub fn proxy(mut self, proxy: Proxy<'a>) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Record the requests sent, TLS handshakes performed and errors encountered by this client.
+    ///
+    /// The bytes written and read are recorded by decorating the connections with [`crate::metrics::Metered`].
+    pub fn metrics(mut self, metrics: &'a dyn Recorder) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Buffer the requests written to plain HTTP connections in `tx_buf`, such that the request is sent in
+    /// segments as large as the buffer instead of a segment for each part of the request.
+    ///
+    /// HTTPS connections are buffered by the TLS backend, and are not affected.
+    pub fn tx_buffer(mut self, tx_buf: &'a mut [u8]) -> Self {
+        self.tx_buf = Some(tx_buf);
+        self
+    }
+
+    /// Receive the responses of requests sent using [`HttpRequestHandle::fetch()`] in `rx_buf`, which should be
+    /// sized to contain at least the response headers.
+    ///
+    /// The buffer is reused by each request of the client.
+    pub fn rx_buffer(mut self, rx_buf: &'a mut [u8]) -> Self {
+        self.rx_buf = rx_buf;
+        self
+    }
+
+    /// The forward proxy that plain HTTP requests to `url` are sent to, unless they are sent to `remote`.
+    fn forward_proxy<'u>(&self, url: &Url<'u>, remote: Option<SocketAddr>) -> Option<ForwardProxy<'u>>
+    where
+        'a: 'u,
+    {
+        self.proxy
+            .filter(|_| remote.is_none() && url.scheme() == UrlScheme::HTTP)
+            .map(|proxy| proxy.forward(url))
+    }
+
+    /// Establish a TCP connection to `remote` if provided, or else to the host of `url` or the proxy if one is
+    /// configured.
+    async fn connect_transport<'conn>(&self, url: &Url<'_>, remote: Option<SocketAddr>) -> Result<W::Connection, Error>
+    where
+        'a: 'conn,
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        if let Some(remote) = remote {
+            let client: &'conn T = self.client;
+            let conn = client.connect(remote).await.map_err(|e| e.kind())?;
+            return Ok(self.decorator.decorate(conn));
+        }
+        if let Some(proxy) = self.proxy.filter(|_| url.scheme() == UrlScheme::HTTP) {
+            let conn = connect_tcp(self.client, self.dns, proxy.url.host(), proxy.url.port_or_default()).await?;
+            return Ok(self.decorator.decorate(conn));
+        }
+        self.connect_tunnel(url.bracketed_host(), url.port_or_default()).await
+    }
+
+    /// Establish a TCP connection to `host` and `port`, tunneled through the proxy if one is configured.
+    async fn connect_tunnel<'conn>(&self, host: &str, port: u16) -> Result<W::Connection, Error>
+    where
+        'a: 'conn,
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let (remote_host, remote_port) = match &self.proxy {
+            Some(proxy) => (proxy.url.host(), proxy.url.port_or_default()),
+            None => (host, port),
+        };
+        let conn = connect_tcp(self.client, self.dns, remote_host, remote_port).await?;
+        let mut conn = self.decorator.decorate(conn);
+        if let Some(proxy) = &self.proxy {
+            proxy.tunnel(&mut conn, host, port).await?;
+        }
+        Ok(conn)
+    }
+
+    /// Open a raw connection to `host` and `port`, tunneled through the proxy using `CONNECT` if one is
+    /// configured, for running protocols other than HTTP such as MQTT.
+    ///
+    /// IPv6 addresses must be enclosed in brackets, e.g. `[2001:db8::1]`.
+    pub async fn tunnel<'conn>(
+        &'conn self,
+        host: &str,
+        port: u16,
+    ) -> Result<HttpConnection<'conn, W::Connection>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let conn = self.connect_tunnel(host, port).await?;
+        Ok(HttpConnection::Plain(conn))
+    }
+
+    /// Establish a connection to the host of `url`, returning it along with the receive buffer of the client.
     async fn connect<'conn>(
         &'conn mut self,
         url: &Url<'_>,
-    ) -> Result<HttpConnection<'conn, T::Connection<'conn>>, Error> {
-        let host = url.host();
-        let port = url.port_or_default();
+        remote: Option<SocketAddr>,
+    ) -> Result<(HttpConnection<'conn, W::Connection>, &'conn mut [u8]), Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let metrics = self.metrics;
+        let conn = record_error(metrics, self.connect_transport(url, remote).await)?;
+        let conn = self.establish_connection(conn, url).await;
+        record_handshake(metrics, url, conn)
+    }
+
+    /// Perform the TLS handshake on a connection to the host of `url` if required, or buffer the requests written
+    /// to a plain HTTP connection if the client has a transmit buffer, returning the connection along with the
+    /// receive buffer of the client.
+    async fn establish_connection<'conn, C>(
+        &'conn mut self,
+        conn: C,
+        url: &Url<'_>,
+    ) -> Result<(HttpConnection<'conn, C>, &'conn mut [u8]), Error>
+    where
+        C: Read + Write,
+    {
+        if url.scheme() == UrlScheme::HTTP && self.tx_buf.is_some() {
+            let tx_buf = self.tx_buf.as_deref_mut().unwrap();
+            let conn = HttpConnection::PlainBuffered(BufferedWrite::new(conn, tx_buf));
+            return Ok((conn, &mut *self.rx_buf));
+        }
+        #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+        let (tls, rx_buf) = self.tls_ref();
+        #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+        let conn = establish(conn, url, tls).await?;
+        #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
+        let (conn, rx_buf) = (establish(conn, url).await?, &mut *self.rx_buf);
+        Ok((conn, rx_buf))
+    }
 
-        let remote = self
-            .dns
-            .get_host_by_name(host, embedded_nal_async::AddrType::Either)
-            .await
-            .map_err(|_| Error::Dns)?;
+    /// Get the configuration of the selected TLS backend, along with the receive buffer which is borrowed
+    /// at the same time.
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    fn tls_ref(&mut self) -> (Option<TlsRef<'_, 'a>>, &mut [u8]) {
+        let rx_buf = &mut *self.rx_buf;
+        #[cfg(all(feature = "embedded-tls", feature = "esp-mbedtls"))]
+        if self.backend == TlsBackend::MbedTls {
+            return (self.mbedtls.as_mut().map(TlsRef::from), rx_buf);
+        }
+        #[cfg(feature = "embedded-tls")]
+        return (self.tls.as_mut().map(TlsRef::from), rx_buf);
+        #[cfg(not(feature = "embedded-tls"))]
+        return (self.mbedtls.as_mut().map(TlsRef::from), rx_buf);
+    }
+
+    /// Create a single http request.
+    pub async fn request<'conn>(
+        &'conn mut self,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, W::Connection, ()>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        self.request_inner(None, method, url).await
+    }
+
+    /// Create a single http request to the server at `remote` instead of the resolved host of the url.
+    ///
+    /// The host of the url is still sent in the `Host` header and used as the TLS server name.
+    pub async fn request_to<'conn>(
+        &'conn mut self,
+        remote: SocketAddr,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, W::Connection, ()>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        self.request_inner(Some(remote), method, url).await
+    }
+
+    /// Send a `GET` request to `url`, and read the response body into `rx_buf`.
+    ///
+    /// The buffer must be large enough to contain both the response headers and the body. Use
+    /// [`HttpClient::request()`] to customize the request or to read the body in parts.
+    pub async fn get<'conn, 'buf>(
+        &'conn mut self,
+        url: &'conn str,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<(StatusCode, &'buf mut [u8]), Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let request = self.request(Method::GET, url).await?;
+        send_once(request, rx_buf).await
+    }
+
+    /// Send a `POST` request with `body` to `url`, and read the response body into `rx_buf`.
+    ///
+    /// The buffer must be large enough to contain both the response headers and the body.
+    pub async fn post<'conn, 'buf, B: RequestBody>(
+        &'conn mut self,
+        url: &'conn str,
+        body: B,
+        content_type: ContentType,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<(StatusCode, &'buf mut [u8]), Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let request = self.request(Method::POST, url).await?;
+        send_once(request.body(body).content_type(content_type), rx_buf).await
+    }
 
-        let conn = self
-            .client
-            .connect(SocketAddr::new(remote, port))
+    /// Send a `PUT` request with `body` to `url`, and read the response body into `rx_buf`.
+    ///
+    /// The buffer must be large enough to contain both the response headers and the body.
+    pub async fn put<'conn, 'buf, B: RequestBody>(
+        &'conn mut self,
+        url: &'conn str,
+        body: B,
+        content_type: ContentType,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<(StatusCode, &'buf mut [u8]), Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let request = self.request(Method::PUT, url).await?;
+        send_once(request.body(body).content_type(content_type), rx_buf).await
+    }
+
+    /// Send a `DELETE` request to `url`, and read the response body into `rx_buf`.
+    ///
+    /// The buffer must be large enough to contain both the response headers and the body.
+    pub async fn delete<'conn, 'buf>(
+        &'conn mut self,
+        url: &'conn str,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<(StatusCode, &'buf mut [u8]), Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let request = self.request(Method::DELETE, url).await?;
+        send_once(request, rx_buf).await
+    }
+
+    /// Send a `HEAD` request to `url`, and get the metadata of the resource without downloading it.
+    ///
+    /// The buffer must be large enough to contain the response headers.
+    pub async fn head<'conn>(&'conn mut self, url: &'conn str, rx_buf: &mut [u8]) -> Result<Metadata, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let mut request = self.request(Method::HEAD, url).await?;
+        let response = request.send(rx_buf).await?;
+        let metadata = Metadata::from_response(&response);
+        response.body().discard().await?;
+        Ok(metadata)
+    }
+
+    async fn request_inner<'conn>(
+        &'conn mut self,
+        remote: Option<SocketAddr>,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, W::Connection, ()>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+    {
+        let url = Url::parse(url)?;
+        let response_config = self.response_config;
+        let metrics = self.metrics;
+        let forward_proxy = self.forward_proxy(&url, remote);
+        let (conn, rx_buf) = self.connect(&url, remote).await?;
+        Ok(HttpRequestHandle {
+            conn,
+            request: Some(
+                Request::new(method, url.path())
+                    .host(url.authority())
+                    .forward_proxy(forward_proxy),
+            ),
+            response_config,
+            metrics,
+            rx_buf,
+        })
+    }
+
+    /// Create a connection to a server with the provided `resource_url`.
+    /// The path in the url is considered the base path for subsequent requests.
+    pub async fn resource<'res>(
+        &'res mut self,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, W::Connection>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'res>>,
+    {
+        self.resource_inner(None, resource_url).await
+    }
+
+    /// Create a connection to the server at `remote` instead of the resolved host of `resource_url`.
+    /// The path in the url is considered the base path for subsequent requests.
+    ///
+    /// The host of the url is still sent in the `Host` header and used as the TLS server name.
+    pub async fn resource_to<'res>(
+        &'res mut self,
+        remote: SocketAddr,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, W::Connection>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'res>>,
+    {
+        self.resource_inner(Some(remote), resource_url).await
+    }
+
+    async fn resource_inner<'res>(
+        &'res mut self,
+        remote: Option<SocketAddr>,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, W::Connection>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'res>>,
+    {
+        let resource_url = Url::parse(resource_url)?;
+        let response_config = self.response_config;
+        let metrics = self.metrics;
+        let forward_proxy = self.forward_proxy(&resource_url, remote);
+        let (conn, _) = self.connect(&resource_url, remote).await?;
+        Ok(HttpResource {
+            conn,
+            host: resource_url.authority(),
+            base_path: resource_url.path(),
+            response_config,
+            forward_proxy,
+            metrics,
+            auth: None,
+            credentials: None,
+        })
+    }
+
+    /// Create a single http request, where each phase of the request is bounded by the provided timeouts.
+    ///
+    /// A phase that takes too long fails with [`ErrorKind::TimedOut`].
+    pub async fn request_with_timeouts<'conn, M>(
+        &'conn mut self,
+        timeouts: Timeouts<M>,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, TimeoutConnection<W::Connection, M>, ()>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+        M: Delay + Clone,
+    {
+        let url = Url::parse(url)?;
+        let response_config = self.response_config;
+        let metrics = self.metrics;
+        let forward_proxy = self.forward_proxy(&url, None);
+        let (conn, rx_buf) = self.connect_with_timeouts(&url, timeouts).await?;
+        Ok(HttpRequestHandle {
+            conn,
+            request: Some(
+                Request::new(method, url.path())
+                    .host(url.authority())
+                    .forward_proxy(forward_proxy),
+            ),
+            response_config,
+            metrics,
+            rx_buf,
+        })
+    }
+
+    /// Create a connection to a server with the provided `resource_url`, where each phase of the requests is
+    /// bounded by the provided timeouts. The path in the url is considered the base path for subsequent requests.
+    ///
+    /// A phase that takes too long fails with [`ErrorKind::TimedOut`].
+    pub async fn resource_with_timeouts<'res, M>(
+        &'res mut self,
+        timeouts: Timeouts<M>,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, TimeoutConnection<W::Connection, M>>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'res>>,
+        M: Delay + Clone,
+    {
+        let resource_url = Url::parse(resource_url)?;
+        let response_config = self.response_config;
+        let metrics = self.metrics;
+        let forward_proxy = self.forward_proxy(&resource_url, None);
+        let (conn, _) = self.connect_with_timeouts(&resource_url, timeouts).await?;
+        Ok(HttpResource {
+            conn,
+            host: resource_url.authority(),
+            base_path: resource_url.path(),
+            response_config,
+            forward_proxy,
+            metrics,
+            auth: None,
+            credentials: None,
+        })
+    }
+
+    async fn connect_with_timeouts<'conn, M>(
+        &'conn mut self,
+        url: &Url<'_>,
+        timeouts: Timeouts<M>,
+    ) -> Result<
+        (
+            HttpConnection<'conn, TimeoutConnection<W::Connection, M>>,
+            &'conn mut [u8],
+        ),
+        Error,
+    >
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+        M: Delay + Clone,
+    {
+        let metrics = self.metrics;
+        let mut delay = timeouts.delay.clone();
+        let conn = with_timeout(&mut delay, timeouts.connect, self.connect_transport(url, None))
             .await
-            .map_err(|e| e.kind())?;
+            .unwrap_or(Err(Error::Network(ErrorKind::TimedOut)));
+        // The TLS handshake reads from the timeout connection, so its reads are bounded by the first byte and
+        // inactivity timeouts as well
+        let conn = TimeoutConnection::new(record_error(metrics, conn)?, &timeouts);
+        let conn = with_timeout(&mut delay, timeouts.tls_handshake, self.establish_connection(conn, url))
+            .await
+            .unwrap_or(Err(Error::Network(ErrorKind::TimedOut)));
+        record_handshake(metrics, url, conn)
+    }
+
+    /// Create a single https request, performing the TLS handshake with the provided [`TlsProvider`].
+    ///
+    /// The url must use the `https` scheme.
+    pub async fn request_with_tls<'conn, P>(
+        &'conn mut self,
+        provider: &'conn mut P,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, P::Connection<'conn>, ()>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+        P: TlsProvider<W::Connection>,
+    {
+        let url = Url::parse(url)?;
+        let conn = self.connect_with_tls(provider, &url).await?;
+        Ok(HttpRequestHandle {
+            conn,
+            request: Some(Request::new(method, url.path()).host(url.authority())),
+            response_config: self.response_config,
+            metrics: self.metrics,
+            rx_buf: &mut *self.rx_buf,
+        })
+    }
+
+    /// Create a connection to a server with the provided `resource_url`, performing the TLS handshake with
+    /// the provided [`TlsProvider`]. The path in the url is considered the base path for subsequent requests.
+    ///
+    /// The url must use the `https` scheme.
+    pub async fn resource_with_tls<'res, P>(
+        &'res mut self,
+        provider: &'res mut P,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, P::Connection<'res>>, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'res>>,
+        P: TlsProvider<W::Connection>,
+    {
+        let resource_url = Url::parse(resource_url)?;
+        let conn = self.connect_with_tls(provider, &resource_url).await?;
+        Ok(HttpResource {
+            conn,
+            host: resource_url.authority(),
+            base_path: resource_url.path(),
+            response_config: self.response_config,
+            forward_proxy: None,
+            metrics: self.metrics,
+            auth: None,
+            credentials: None,
+        })
+    }
+
+    async fn connect_with_tls<'conn, P>(
+        &self,
+        provider: &'conn mut P,
+        url: &Url<'_>,
+    ) -> Result<HttpConnection<'conn, P::Connection<'conn>>, Error>
+    where
+        'a: 'conn,
+        W: ConnectionDecorator<T::Connection<'conn>> + 'conn,
+        P: TlsProvider<W::Connection>,
+    {
+        if url.scheme() != UrlScheme::HTTPS {
+            return Err(Error::InvalidUrl(nourl::Error::UnsupportedScheme));
+        }
+        let conn = record_error(self.metrics, self.connect_transport(url, None).await)?;
+        let conn = record_error(self.metrics, provider.connect(url.host(), conn).await)?;
+        if let Some(metrics) = self.metrics {
+            metrics.record(Event::TlsHandshake);
+        }
+        Ok(HttpConnection::Provided(conn))
+    }
+}
 
+/// Send the request of `handle`, and read the response body into `rx_buf`.
+async fn send_once<'buf, C, B>(
+    mut handle: HttpRequestHandle<'_, C, B>,
+    rx_buf: &'buf mut [u8],
+) -> Result<(StatusCode, &'buf mut [u8]), Error>
+where
+    C: Read + Write,
+    B: RequestBody,
+{
+    let response = handle.send(rx_buf).await?;
+    let status = response.status;
+    Ok((status, response.body().read_to_end().await?))
+}
+
+/// Record the outcome of establishing a connection to `url`, which includes a TLS handshake for HTTPS.
+fn record_handshake<C>(metrics: Option<&dyn Recorder>, url: &Url<'_>, conn: Result<C, Error>) -> Result<C, Error> {
+    if let (Some(metrics), Ok(_)) = (metrics, &conn) {
         if url.scheme() == UrlScheme::HTTPS {
+            metrics.record(Event::TlsHandshake);
+        }
+    }
+    record_error(metrics, conn)
+}
+
+/// Resolve the host of `url` and establish a connection, performing the TLS handshake if required.
+pub(crate) async fn connect<'conn, T, D>(
+    client: &'conn T,
+    dns: &D,
+    url: &Url<'_>,
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))] tls: Option<TlsRef<'conn, '_>>,
+) -> Result<HttpConnection<'conn, T::Connection<'conn>>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let conn = connect_tcp(client, dns, url.host(), url.port_or_default()).await?;
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    return establish(conn, url, tls).await;
+    #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
+    return establish(conn, url).await;
+}
+
+/// Perform the TLS handshake on an established connection to the host of `url` if required.
+pub(crate) async fn establish<'conn, C>(
+    conn: C,
+    url: &Url<'_>,
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))] tls: Option<TlsRef<'conn, '_>>,
+) -> Result<HttpConnection<'conn, C>, Error>
+where
+    C: Read + Write,
+{
+    if url.scheme() == UrlScheme::HTTPS {
+        #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+        match tls {
             #[cfg(feature = "esp-mbedtls")]
-            if let Some(tls) = self.tls.as_mut() {
-                let mut servername = host.as_bytes().to_vec();
+            Some(TlsRef::MbedTls(tls)) => {
+                let mut servername = url.host().as_bytes().to_vec();
                 servername.push(0);
                 let mut session = esp_mbedtls::asynch::Session::new(
                     conn,
@@ -144,73 +931,198 @@ where
                 )?;
 
                 session.connect().await?;
-                Ok(HttpConnection::Tls(session))
-            } else {
-                Ok(HttpConnection::Plain(conn))
+                Ok(HttpConnection::MbedTls(session))
             }
-
             #[cfg(feature = "embedded-tls")]
-            if let Some(tls) = self.tls.as_mut() {
-                use embedded_tls::{TlsConfig, TlsContext};
+            Some(TlsRef::EmbeddedTls(tls)) => {
                 use rand_chacha::ChaCha8Rng;
                 use rand_core::{RngCore, SeedableRng};
-                let mut rng = ChaCha8Rng::seed_from_u64(tls.seed);
-                tls.seed = rng.next_u64();
-                let mut config = TlsConfig::new().with_server_name(url.host());
-                if let TlsVerify::Psk { identity, psk } = tls.verify {
-                    config = config.with_psk(psk, &[identity]);
-                }
-                let mut conn: embedded_tls::TlsConnection<'conn, T::Connection<'conn>, embedded_tls::Aes128GcmSha256> =
-                    embedded_tls::TlsConnection::new(conn, tls.read_buffer, tls.write_buffer);
-                conn.open::<_, embedded_tls::NoVerify>(TlsContext::new(&config, &mut rng))
-                    .await?;
+                let mut rng = match &mut tls.rng {
+                    TlsRng::Seed(seed) => {
+                        let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                        *seed = rng.next_u64();
+                        rng
+                    }
+                    TlsRng::Entropy(entropy) => {
+                        let mut seed = [0; 32];
+                        entropy.fill_bytes(&mut seed);
+                        ChaCha8Rng::from_seed(seed)
+                    }
+                };
+                let conn = match tls.cipher_suite {
+                    CipherSuite::Aes128GcmSha256 => {
+                        TlsConnection::Aes128GcmSha256(open_tls(conn, url.host(), tls, &mut rng).await?)
+                    }
+                    CipherSuite::Aes256GcmSha384 => {
+                        TlsConnection::Aes256GcmSha384(open_tls(conn, url.host(), tls, &mut rng).await?)
+                    }
+                };
                 Ok(HttpConnection::Tls(conn))
-            } else {
-                Ok(HttpConnection::Plain(conn))
             }
-            #[cfg(all(not(feature = "embedded-tls"), not(feature = "esp-mbedtls")))]
-            Err(Error::InvalidUrl(nourl::Error::UnsupportedScheme))
-        } else {
-            #[cfg(feature = "embedded-tls")]
-            match self.tls.as_mut() {
-                Some(tls) => Ok(HttpConnection::PlainBuffered(BufferedWrite::new(
-                    conn,
-                    tls.write_buffer,
-                ))),
-                None => Ok(HttpConnection::Plain(conn)),
+            None => Ok(HttpConnection::Plain(conn)),
+        }
+        #[cfg(all(not(feature = "embedded-tls"), not(feature = "esp-mbedtls")))]
+        Err(Error::InvalidUrl(nourl::Error::UnsupportedScheme))
+    } else {
+        #[cfg(feature = "embedded-tls")]
+        if let Some(TlsRef::EmbeddedTls(tls)) = tls {
+            return Ok(HttpConnection::PlainBuffered(BufferedWrite::new(
+                conn,
+                tls.write_buffer,
+            )));
+        }
+        Ok(HttpConnection::Plain(conn))
+    }
+}
+
+/// Resolve `host` and establish a TCP connection to `port`.
+///
+/// Hosts that are IP literals are connected to without resolving them. If connecting to the resolved address
+/// fails, the host is resolved again for the other address family, and the connection is attempted to that
+/// address before giving up.
+pub(crate) async fn connect_tcp<'conn, T, D>(
+    client: &'conn T,
+    dns: &D,
+    host: &str,
+    port: u16,
+) -> Result<T::Connection<'conn>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    if let Some(ip) = ip_literal(host) {
+        let conn = client.connect(SocketAddr::new(ip, port)).await.map_err(|e| e.kind())?;
+        return Ok(conn);
+    }
+
+    let remote = dns
+        .get_host_by_name(host, AddrType::Either)
+        .await
+        .map_err(|_| Error::Dns)?;
+
+    let kind = match client.connect(SocketAddr::new(remote, port)).await {
+        Ok(conn) => return Ok(conn),
+        Err(e) => e.kind(),
+    };
+
+    let other = match remote {
+        IpAddr::V4(_) => AddrType::IPv6,
+        IpAddr::V6(_) => AddrType::IPv4,
+    };
+    // Only the error of the first attempt is reported if the host has no address of the other family
+    let fallback = match dns.get_host_by_name(host, other).await {
+        Ok(fallback) if fallback != remote => fallback,
+        _ => return Err(Error::Network(kind)),
+    };
+
+    debug!("Connecting to {} failed, trying another address family", host);
+    let conn = client
+        .connect(SocketAddr::new(fallback, port))
+        .await
+        .map_err(|e| e.kind())?;
+    Ok(conn)
+}
+
+/// Perform the TLS handshake on `conn` using the cipher suite `S`.
+#[cfg(feature = "embedded-tls")]
+async fn open_tls<'conn, C, S>(
+    conn: C,
+    host: &str,
+    tls: &'conn mut TlsConfig<'_>,
+    rng: &mut rand_chacha::ChaCha8Rng,
+) -> Result<embedded_tls::TlsConnection<'conn, C, S>, Error>
+where
+    C: Read + Write,
+    S: embedded_tls::TlsCipherSuite + 'static,
+{
+    use embedded_tls::TlsContext;
+
+    let mut config = embedded_tls::TlsConfig::<S>::new().with_server_name(host);
+    match tls.verify {
+        TlsVerify::None => {}
+        TlsVerify::Psk { identity, psk } => config = config.with_psk(psk, &[identity]),
+        TlsVerify::PskPerHost(psks) => {
+            let Some(HostPsk { identity, psk, .. }) = find_host_psk(psks, host) else {
+                warn!("No pre-shared key configured for host {}", host);
+                return Err(Error::NoTlsConfig);
+            };
+            config = config.with_psk(psk, &[identity]);
+        }
+        #[cfg(feature = "webpki")]
+        TlsVerify::Ca { certificate, .. } => config = config.with_ca(embedded_tls::Certificate::X509(certificate)),
+    }
+    if let Some(protocols) = tls.alpn_protocols {
+        config = config.with_alpn_protocols(protocols);
+    }
+    if let Some(client) = tls.client_certificate {
+        config = config
+            .with_cert(embedded_tls::Certificate::X509(client.certificate))
+            .with_priv_key(client.private_key);
+    }
+
+    let mut conn = embedded_tls::TlsConnection::new(conn, tls.read_buffer, tls.write_buffer);
+    let context = TlsContext::new(&config, rng);
+    #[cfg(feature = "webpki")]
+    if let TlsVerify::Ca { time, .. } = tls.verify {
+        match time.map(|time| time.now()) {
+            Some(now) => {
+                let Some(now) = now else {
+                    warn!("The time is unknown, unable to verify the certificate validity");
+                    return Err(Error::UnknownTime);
+                };
+                VerificationClock::set(now);
+                conn.open::<_, embedded_tls::webpki::CertVerifier<S, VerificationClock, 4096>>(context)
+                    .await?;
+            }
+            None => {
+                conn.open::<_, embedded_tls::webpki::CertVerifier<S, NoClock, 4096>>(context)
+                    .await?
             }
-            #[cfg(not(feature = "embedded-tls"))]
-            Ok(HttpConnection::Plain(conn))
         }
+        return Ok(conn);
     }
+    conn.open::<_, embedded_tls::NoVerify>(context).await?;
+    Ok(conn)
+}
 
-    /// Create a single http request.
-    pub async fn request<'conn>(
-        &'conn mut self,
-        method: Method,
-        url: &'conn str,
-    ) -> Result<HttpRequestHandle<'conn, T::Connection<'conn>, ()>, Error> {
-        let url = Url::parse(url)?;
-        let conn = self.connect(&url).await?;
-        Ok(HttpRequestHandle {
-            conn,
-            request: Some(Request::new(method, url.path()).host(url.host())),
-        })
+/// Clock used when verifying certificates without a time source, which is unable to tell the time.
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+struct NoClock;
+
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+impl embedded_tls::TlsClock for NoClock {
+    fn now() -> Option<u64> {
+        None
     }
+}
 
-    /// Create a connection to a server with the provided `resource_url`.
-    /// The path in the url is considered the base path for subsequent requests.
-    pub async fn resource<'res>(
-        &'res mut self,
-        resource_url: &'res str,
-    ) -> Result<HttpResource<'res, T::Connection<'res>>, Error> {
-        let resource_url = Url::parse(resource_url)?;
-        let conn = self.connect(&resource_url).await?;
-        Ok(HttpResource {
-            conn,
-            host: resource_url.host(),
-            base_path: resource_url.path(),
-        })
+/// The time at which the last handshake verifying the validity of certificates was started, in seconds since the
+/// Unix epoch.
+///
+/// The clock of embedded-tls cannot hold state, so the time of the [`crate::date::TimeSource`] cannot be kept per
+/// connection and is passed on through this static, which is global to all clients. Concurrent handshakes may
+/// overwrite the time, but only with the current time from another source. Not all targets have 64-bit atomics, so the
+/// time is only accessed within a critical section, such that it is never read while it is partially written.
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+static VERIFICATION_TIME: critical_section::Mutex<core::cell::Cell<u64>> =
+    critical_section::Mutex::new(core::cell::Cell::new(0));
+
+/// Clock used when verifying certificates with a time source.
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+struct VerificationClock;
+
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+impl VerificationClock {
+    /// Set the time returned to all handshakes started afterwards.
+    fn set(now: u64) {
+        critical_section::with(|cs| VERIFICATION_TIME.borrow(cs).set(now));
+    }
+}
+
+#[cfg(all(feature = "embedded-tls", feature = "webpki"))]
+impl embedded_tls::TlsClock for VerificationClock {
+    fn now() -> Option<u64> {
+        Some(critical_section::with(|cs| VERIFICATION_TIME.borrow(cs).get()))
     }
 }
 
@@ -222,12 +1134,127 @@ where
 {
     Plain(C),
     PlainBuffered(BufferedWrite<'conn, C>),
-    #[cfg(feature = "esp-mbedtls")]
-    Tls(esp_mbedtls::asynch::Session<'conn, C>),
     #[cfg(feature = "embedded-tls")]
-    Tls(embedded_tls::TlsConnection<'conn, C, embedded_tls::Aes128GcmSha256>),
-    #[cfg(all(not(feature = "embedded-tls"), not(feature = "esp-mbedtls")))]
+    Tls(TlsConnection<'conn, C>),
+    #[cfg(not(feature = "embedded-tls"))]
     Tls((&'conn mut (), core::convert::Infallible)), // Variant is impossible to create, but we need it to avoid "unused lifetime" warning
+    #[cfg(feature = "esp-mbedtls")]
+    MbedTls(esp_mbedtls::asynch::Session<'conn, C>),
+    /// A connection secured by a [`TlsProvider`], such as TLS offloaded to a modem.
+    Provided(C),
+}
+
+/// A TLS connection established with embedded-tls, using the [`CipherSuite`] set in the [`TlsConfig`].
+#[cfg(feature = "embedded-tls")]
+pub enum TlsConnection<'conn, C>
+where
+    C: Read + Write,
+{
+    Aes128GcmSha256(embedded_tls::TlsConnection<'conn, C, embedded_tls::Aes128GcmSha256>),
+    Aes256GcmSha384(embedded_tls::TlsConnection<'conn, C, embedded_tls::Aes256GcmSha384>),
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<C> TlsConnection<'_, C>
+where
+    C: Read + Write,
+{
+    /// The cipher suite used by the connection.
+    pub fn cipher_suite(&self) -> CipherSuite {
+        match self {
+            Self::Aes128GcmSha256(_) => CipherSuite::Aes128GcmSha256,
+            Self::Aes256GcmSha384(_) => CipherSuite::Aes256GcmSha384,
+        }
+    }
+
+    /// The details negotiated during the handshake.
+    pub fn info(&self) -> TlsInfo {
+        TlsInfo {
+            version: TlsProtocolVersion::Tls1_3,
+            cipher_suite: self.cipher_suite(),
+        }
+    }
+}
+
+/// The details of an established TLS connection.
+///
+/// embedded-tls does not keep the certificate of the server after the handshake, so it is not included.
+#[cfg(feature = "embedded-tls")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TlsInfo {
+    pub version: TlsProtocolVersion,
+    pub cipher_suite: CipherSuite,
+}
+
+/// TLS protocol versions.
+#[cfg(feature = "embedded-tls")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TlsProtocolVersion {
+    /// TLS 1.3, the only version supported by embedded-tls
+    Tls1_3,
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<C> ErrorType for TlsConnection<'_, C>
+where
+    C: Read + Write,
+{
+    type Error = embedded_tls::TlsError;
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<C> Read for TlsConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self {
+            Self::Aes128GcmSha256(conn) => conn.read(buf).await,
+            Self::Aes256GcmSha384(conn) => conn.read(buf).await,
+        }
+    }
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<C> embedded_io_async::BufRead for TlsConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        match self {
+            Self::Aes128GcmSha256(conn) => conn.fill_buf().await,
+            Self::Aes256GcmSha384(conn) => conn.fill_buf().await,
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Aes128GcmSha256(conn) => conn.consume(amt),
+            Self::Aes256GcmSha384(conn) => conn.consume(amt),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-tls")]
+impl<C> Write for TlsConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        match self {
+            Self::Aes128GcmSha256(conn) => conn.write(buf).await,
+            Self::Aes256GcmSha384(conn) => conn.write(buf).await,
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        match self {
+            Self::Aes128GcmSha256(conn) => conn.flush().await,
+            Self::Aes256GcmSha384(conn) => conn.flush().await,
+        }
+    }
 }
 
 #[cfg(feature = "defmt")]
@@ -240,6 +1267,9 @@ where
             HttpConnection::Plain(_) => defmt::write!(fmt, "Plain"),
             HttpConnection::PlainBuffered(_) => defmt::write!(fmt, "PlainBuffered"),
             HttpConnection::Tls(_) => defmt::write!(fmt, "Tls"),
+            #[cfg(feature = "esp-mbedtls")]
+            HttpConnection::MbedTls(_) => defmt::write!(fmt, "MbedTls"),
+            HttpConnection::Provided(_) => defmt::write!(fmt, "Provided"),
         }
     }
 }
@@ -253,6 +1283,9 @@ where
             HttpConnection::Plain(_) => f.debug_tuple("Plain").finish(),
             HttpConnection::PlainBuffered(_) => f.debug_tuple("PlainBuffered").finish(),
             HttpConnection::Tls(_) => f.debug_tuple("Tls").finish(),
+            #[cfg(feature = "esp-mbedtls")]
+            HttpConnection::MbedTls(_) => f.debug_tuple("MbedTls").finish(),
+            HttpConnection::Provided(_) => f.debug_tuple("Provided").finish(),
         }
     }
 }
@@ -261,6 +1294,17 @@ impl<'conn, T> HttpConnection<'conn, T>
 where
     T: Read + Write,
 {
+    /// The details of the TLS connection, or `None` if the connection is not encrypted using embedded-tls.
+    ///
+    /// Connections secured by a [`TlsProvider`] have no details either, see [`HttpConnection::is_encrypted()`].
+    #[cfg(feature = "embedded-tls")]
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        match self {
+            HttpConnection::Tls(tls) => Some(tls.info()),
+            _ => None,
+        }
+    }
+
     /// Turn the request into a buffered request.
     ///
     /// This is only relevant if no TLS is used, as `embedded-tls` buffers internally and we reuse
@@ -273,9 +1317,17 @@ where
             HttpConnection::Plain(conn) => HttpConnection::PlainBuffered(BufferedWrite::new(conn, tx_buf)),
             HttpConnection::PlainBuffered(conn) => HttpConnection::PlainBuffered(conn),
             HttpConnection::Tls(tls) => HttpConnection::Tls(tls),
+            #[cfg(feature = "esp-mbedtls")]
+            HttpConnection::MbedTls(tls) => HttpConnection::MbedTls(tls),
+            HttpConnection::Provided(tls) => HttpConnection::Provided(tls),
         }
     }
 
+    /// Whether the connection is encrypted, using a built-in TLS backend or a [`TlsProvider`].
+    pub fn is_encrypted(&self) -> bool {
+        !matches!(self, HttpConnection::Plain(_) | HttpConnection::PlainBuffered(_))
+    }
+
     /// Send a request on an established connection.
     ///
     /// The request is sent in its raw form without any base path from the resource.
@@ -292,8 +1344,179 @@ where
         Response::read(self, request.method, rx_buf).await
     }
 
-    async fn write_request<'req, B: RequestBody>(&mut self, request: &Request<'req, B>) -> Result<(), Error> {
-        request.write_header(self).await?;
+    /// Send a request, answering the challenge of a `401 Unauthorized` response with the `credentials` once.
+    ///
+    /// The challenge is only answered if the request has no authentication of its own, the connection is kept open
+    /// and the body can be rewound, otherwise the `401 Unauthorized` response is returned.
+    pub(crate) async fn send_authenticated<'a, 'buf, B: RequestBody, const MAX_HEADERS: usize>(
+        &'a mut self,
+        request: Request<'_, B>,
+        rx_buf: &'buf mut [u8],
+        config: &ResponseConfig,
+        credentials: Option<Credentials<'_>>,
+    ) -> Result<Response<'a, 'buf, Self, MAX_HEADERS>, Error> {
+        self.write_request(&request).await?;
+        self.flush().await?;
+        let (header_len, pos) = <Response<Self, MAX_HEADERS>>::read_head(self, rx_buf, config, |_, _| {}).await?;
+
+        let mut authorization = None;
+        if let Some(credentials) = credentials.filter(|_| request.auth.is_none()) {
+            let response: Response<'_, '_, Self, MAX_HEADERS> =
+                Response::from_head(self, request.method, rx_buf, header_len, pos, config)?;
+            if response.status == Status::Unauthorized && response.is_persistent() {
+                let target = request.target();
+                let challenges = response.headers_matching("www-authenticate");
+                authorization = credentials
+                    .authorize(request.method, &target, challenges)
+                    .map(|authorization| (credentials, authorization));
+            }
+        }
+        let (credentials, authorization) = match authorization {
+            Some(authorization) if request.body.rewind().await => authorization,
+            _ => return Response::from_head(self, request.method, rx_buf, header_len, pos, config),
+        };
+
+        let response: Response<'_, '_, Self, MAX_HEADERS> =
+            Response::from_head(self, request.method, rx_buf, header_len, pos, config)?;
+        response.body().discard().await?;
+        debug!("Answering the authentication challenge");
+        let mut request = request;
+        request.auth = Some(authorization.auth(&credentials));
+        self.write_request(&request).await?;
+        self.flush().await?;
+        Response::read_with_config(self, request.method, rx_buf, config).await
+    }
+
+    /// Send a request with the bearer token of the `provider`, refreshing the token and sending the request once
+    /// more if the server responds with `401 Unauthorized` and the body can be rewound.
+    pub(crate) async fn send_with_token<'a, 'buf, B: RequestBody, P: TokenProvider, const MAX_HEADERS: usize>(
+        &'a mut self,
+        request: Request<'_, B>,
+        provider: &mut P,
+        rx_buf: &'buf mut [u8],
+        config: &ResponseConfig,
+    ) -> Result<Response<'a, 'buf, Self, MAX_HEADERS>, Error> {
+        let token = provider.token().await?;
+        self.write_request_with(&request, Some(&Auth::Bearer { token }), &[])
+            .await?;
+        self.flush().await?;
+        let (header_len, pos) = <Response<Self, MAX_HEADERS>>::read_head(self, rx_buf, config, |_, _| {}).await?;
+
+        let rejected = {
+            let response: Response<'_, '_, Self, MAX_HEADERS> =
+                Response::from_head(self, request.method, rx_buf, header_len, pos, config)?;
+            response.status == Status::Unauthorized && response.is_persistent()
+        };
+        if !rejected || !request.body.rewind().await {
+            return Response::from_head(self, request.method, rx_buf, header_len, pos, config);
+        }
+
+        let response: Response<'_, '_, Self, MAX_HEADERS> =
+            Response::from_head(self, request.method, rx_buf, header_len, pos, config)?;
+        response.body().discard().await?;
+        debug!("Refreshing the rejected token");
+        provider.refresh().await?;
+        let token = provider.token().await?;
+        self.write_request_with(&request, Some(&Auth::Bearer { token }), &[])
+            .await?;
+        self.flush().await?;
+        Response::read_with_config(self, request.method, rx_buf, config).await
+    }
+
+    /// Send a request on an established connection, watching for a response while the body is written.
+    ///
+    /// Servers may respond before the entire body is received, e.g. with `413 Content Too Large` or
+    /// `401 Unauthorized`. If a response arrives while the body is being written, the upload is aborted and
+    /// [`Upload::Interrupted`] is returned with that response. The connection should not be reused afterwards,
+    /// as the request body is incomplete.
+    ///
+    /// Early responses on TLS connections are only detected if writing the body fails.
+    pub async fn send_with_early_response<'req, 'buf, B: RequestBody>(
+        &'conn mut self,
+        request: Request<'req, B>,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Upload<'conn, 'buf, HttpConnection<'conn, T>>, Error>
+    where
+        T: ReadReady,
+    {
+        let completed = self.write_request_watched(&request).await?;
+        if completed {
+            self.flush().await?;
+        } else {
+            // The server has already responded, so whatever is left in the buffer is of no interest
+            self.flush().await.ok();
+        }
+
+        let response = Response::read(self, request.method, rx_buf).await?;
+        Ok(if completed {
+            Upload::Completed(response)
+        } else {
+            Upload::Interrupted(response)
+        })
+    }
+
+    /// Write a request, aborting the body if a response can be read.
+    ///
+    /// Returns whether the entire request was written.
+    async fn write_request_watched<'req, B: RequestBody>(&mut self, request: &Request<'req, B>) -> Result<bool, Error>
+    where
+        T: ReadReady,
+    {
+        request.write_header(self).await?;
+
+        let Some(body) = request.body.as_ref() else {
+            return Ok(true);
+        };
+
+        let mut watched = EarlyResponseWatch {
+            conn: self,
+            responded: false,
+        };
+        match write_body(&mut watched, body).await {
+            Ok(()) => Ok(true),
+            Err(_) if watched.responded => {
+                warn!("Server responded before the request body was written");
+                Ok(false)
+            }
+            // The server may have closed its side of the connection after responding
+            Err(Error::Network(_)) if watched.conn.read_ready().unwrap_or(false) => {
+                warn!("Writing the request body failed after the server responded");
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub(crate) async fn write_request<'req, B: RequestBody>(
+        &mut self,
+        request: &Request<'req, B>,
+    ) -> Result<(), Error> {
+        self.write_request_with(request, request.auth.as_ref(), &[]).await
+    }
+
+    /// Write a request with the signature header computed by the `signer`, including its body, without reading the
+    /// response.
+    ///
+    /// The body is written to the `signer` before the request is written, see [`RequestSigner`].
+    pub async fn write_signed_request<B: RequestBody, S: RequestSigner>(
+        &mut self,
+        request: &Request<'_, B>,
+        signer: &mut S,
+    ) -> Result<(), Error> {
+        let signature = signing::sign(request, signer).await?;
+        self.write_request_with(request, request.auth.as_ref(), &[signature])
+            .await
+    }
+
+    /// Write a request authenticated with `auth` rather than the authentication of the request, and with the
+    /// `headers` added by the client, such as the signature header.
+    pub(crate) async fn write_request_with<B: RequestBody>(
+        &mut self,
+        request: &Request<'_, B>,
+        auth: Option<&Auth<'_>>,
+        headers: &[(&str, &str)],
+    ) -> Result<(), Error> {
+        request.write_header_with(self, auth, headers).await?;
 
         if let Some(body) = request.body.as_ref() {
             match body.len() {
@@ -304,6 +1527,7 @@ where
                     trace!("Writing not-chunked body");
                     let mut writer = FixedBodyWriter::new(self);
                     body.write(&mut writer).await.map_err(|e| e.kind())?;
+                    check_body_error(body)?;
 
                     if writer.written() != len {
                         return Err(Error::IncorrectBodyWritten);
@@ -315,23 +1539,49 @@ where
                         HttpConnection::Plain(c) => {
                             let mut writer = ChunkedBodyWriter::new(c);
                             body.write(&mut writer).await?;
+                            check_body_error(body)?;
+                            writer.start_trailers().await.map_err(|e| e.kind())?;
+                            body.write_trailers(&mut writer).await?;
                             writer.terminate().await.map_err(|e| e.kind())?;
                         }
                         HttpConnection::PlainBuffered(buffered) => {
                             let (conn, buf, unwritten) = buffered.split();
                             let mut writer = BufferingChunkedBodyWriter::new_with_data(conn, buf, unwritten);
                             body.write(&mut writer).await?;
+                            check_body_error(body)?;
+                            writer.start_trailers().await.map_err(|e| e.kind())?;
+                            body.write_trailers(&mut writer).await?;
                             writer.terminate().await.map_err(|e| e.kind())?;
                             buffered.clear();
                         }
-                        #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+                        #[cfg(feature = "embedded-tls")]
                         HttpConnection::Tls(c) => {
                             let mut writer = ChunkedBodyWriter::new(c);
                             body.write(&mut writer).await?;
+                            check_body_error(body)?;
+                            writer.start_trailers().await.map_err(|e| e.kind())?;
+                            body.write_trailers(&mut writer).await?;
                             writer.terminate().await.map_err(|e| e.kind())?;
                         }
-                        #[cfg(all(not(feature = "embedded-tls"), not(feature = "esp-mbedtls")))]
+                        #[cfg(not(feature = "embedded-tls"))]
                         HttpConnection::Tls(_) => unreachable!(),
+                        #[cfg(feature = "esp-mbedtls")]
+                        HttpConnection::MbedTls(c) => {
+                            let mut writer = ChunkedBodyWriter::new(c);
+                            body.write(&mut writer).await?;
+                            check_body_error(body)?;
+                            writer.start_trailers().await.map_err(|e| e.kind())?;
+                            body.write_trailers(&mut writer).await?;
+                            writer.terminate().await.map_err(|e| e.kind())?;
+                        }
+                        HttpConnection::Provided(c) => {
+                            let mut writer = ChunkedBodyWriter::new(c);
+                            body.write(&mut writer).await?;
+                            check_body_error(body)?;
+                            writer.start_trailers().await.map_err(|e| e.kind())?;
+                            body.write_trailers(&mut writer).await?;
+                            writer.terminate().await.map_err(|e| e.kind())?;
+                        }
                     };
                 }
             }
@@ -340,6 +1590,63 @@ where
     }
 }
 
+/// A writer that refuses to write as soon as the server has sent any response data.
+struct EarlyResponseWatch<'a, C>
+where
+    C: Write + ReadReady,
+{
+    conn: &'a mut C,
+    /// Whether response data was available, aborting the write.
+    responded: bool,
+}
+
+impl<C> ErrorType for EarlyResponseWatch<'_, C>
+where
+    C: Write + ReadReady,
+{
+    type Error = ErrorKind;
+}
+
+impl<C> Write for EarlyResponseWatch<'_, C>
+where
+    C: Write + ReadReady,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.conn.read_ready().map_err(|e| e.kind())? {
+            self.responded = true;
+            return Err(ErrorKind::Interrupted);
+        }
+        self.conn.write(buf).await.map_err(|e| e.kind())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.conn.flush().await.map_err(|e| e.kind())
+    }
+}
+
+/// The outcome of a request sent with [`HttpConnection::send_with_early_response()`].
+pub enum Upload<'resp, 'buf, C>
+where
+    C: Read,
+{
+    /// The entire request was sent before the response was received.
+    Completed(Response<'resp, 'buf, C>),
+    /// The server responded before the entire request body was sent, and the upload was aborted.
+    Interrupted(Response<'resp, 'buf, C>),
+}
+
+impl<'resp, 'buf, C> Upload<'resp, 'buf, C>
+where
+    C: Read,
+{
+    /// Get the response, regardless of whether the upload was completed.
+    pub fn into_response(self) -> Response<'resp, 'buf, C> {
+        match self {
+            Upload::Completed(response) | Upload::Interrupted(response) => response,
+        }
+    }
+}
+
 impl<T> ErrorType for HttpConnection<'_, T>
 where
     T: Read + Write,
@@ -355,10 +1662,30 @@ where
         match self {
             Self::Plain(conn) => conn.read(buf).await.map_err(|e| e.kind()),
             Self::PlainBuffered(conn) => conn.read(buf).await.map_err(|e| e.kind()),
-            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            #[cfg(feature = "embedded-tls")]
             Self::Tls(conn) => conn.read(buf).await.map_err(|e| e.kind()),
-            #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
-            _ => unreachable!(),
+            #[cfg(not(feature = "embedded-tls"))]
+            Self::Tls(_) => unreachable!(),
+            #[cfg(feature = "esp-mbedtls")]
+            Self::MbedTls(conn) => conn.read(buf).await.map_err(|e| e.kind()),
+            Self::Provided(conn) => conn.read(buf).await.map_err(|e| e.kind()),
+        }
+    }
+}
+
+impl<T> ReadReady for HttpConnection<'_, T>
+where
+    T: Read + Write + ReadReady,
+{
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        match self {
+            Self::Plain(conn) => conn.read_ready().map_err(|e| e.kind()),
+            Self::PlainBuffered(conn) => conn.split().0.read_ready().map_err(|e| e.kind()),
+            // Records can only be decrypted by reading them, so it is unknown whether data is available
+            Self::Tls(_) => Ok(false),
+            #[cfg(feature = "esp-mbedtls")]
+            Self::MbedTls(_) => Ok(false),
+            Self::Provided(_) => Ok(false),
         }
     }
 }
@@ -371,10 +1698,13 @@ where
         match self {
             Self::Plain(conn) => conn.write(buf).await.map_err(|e| e.kind()),
             Self::PlainBuffered(conn) => conn.write(buf).await.map_err(|e| e.kind()),
-            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            #[cfg(feature = "embedded-tls")]
             Self::Tls(conn) => conn.write(buf).await.map_err(|e| e.kind()),
-            #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
-            _ => unreachable!(),
+            #[cfg(not(feature = "embedded-tls"))]
+            Self::Tls(_) => unreachable!(),
+            #[cfg(feature = "esp-mbedtls")]
+            Self::MbedTls(conn) => conn.write(buf).await.map_err(|e| e.kind()),
+            Self::Provided(conn) => conn.write(buf).await.map_err(|e| e.kind()),
         }
     }
 
@@ -382,10 +1712,13 @@ where
         match self {
             Self::Plain(conn) => conn.flush().await.map_err(|e| e.kind()),
             Self::PlainBuffered(conn) => conn.flush().await.map_err(|e| e.kind()),
-            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            #[cfg(feature = "embedded-tls")]
             Self::Tls(conn) => conn.flush().await.map_err(|e| e.kind()),
-            #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
-            _ => unreachable!(),
+            #[cfg(not(feature = "embedded-tls"))]
+            Self::Tls(_) => unreachable!(),
+            #[cfg(feature = "esp-mbedtls")]
+            Self::MbedTls(conn) => conn.flush().await.map_err(|e| e.kind()),
+            Self::Provided(conn) => conn.flush().await.map_err(|e| e.kind()),
         }
     }
 }
@@ -393,16 +1726,20 @@ where
 /// A HTTP request handle
 ///
 /// The underlying connection is closed when drop'ed.
-pub struct HttpRequestHandle<'conn, C, B>
+pub struct HttpRequestHandle<'conn, C, B, const MAX_HEADERS: usize = 64>
 where
     C: Read + Write,
     B: RequestBody,
 {
     pub conn: HttpConnection<'conn, C>,
-    request: Option<DefaultRequestBuilder<'conn, B>>,
+    pub(crate) request: Option<DefaultRequestBuilder<'conn, B>>,
+    pub(crate) response_config: ResponseConfig,
+    pub(crate) metrics: Option<&'conn dyn Recorder>,
+    /// The receive buffer of the client, used by [`HttpRequestHandle::fetch()`].
+    pub(crate) rx_buf: &'conn mut [u8],
 }
 
-impl<'conn, C, B> HttpRequestHandle<'conn, C, B>
+impl<'conn, C, B, const MAX_HEADERS: usize> HttpRequestHandle<'conn, C, B, MAX_HEADERS>
 where
     C: Read + Write,
     B: RequestBody,
@@ -411,13 +1748,28 @@ where
     ///
     /// This is only relevant if no TLS is used, as `embedded-tls` buffers internally and we reuse
     /// its buffer for non-TLS connections.
-    pub fn into_buffered<'buf>(self, tx_buf: &'buf mut [u8]) -> HttpRequestHandle<'buf, C, B>
+    pub fn into_buffered<'buf>(self, tx_buf: &'buf mut [u8]) -> HttpRequestHandle<'buf, C, B, MAX_HEADERS>
     where
         'conn: 'buf,
     {
         HttpRequestHandle {
             conn: self.conn.into_buffered(tx_buf),
             request: self.request,
+            response_config: self.response_config,
+            metrics: self.metrics,
+            rx_buf: self.rx_buf,
+        }
+    }
+
+    /// Set the maximum number of headers that can be parsed in the response, each of which takes up stack space
+    /// while parsing. Responses with more headers are rejected with [`Error::HeadersTooLarge`].
+    pub fn header_capacity<const N: usize>(self) -> HttpRequestHandle<'conn, C, B, N> {
+        HttpRequestHandle {
+            conn: self.conn,
+            request: self.request,
+            response_config: self.response_config,
+            metrics: self.metrics,
+            rx_buf: self.rx_buf,
         }
     }
 
@@ -429,20 +1781,42 @@ where
     pub async fn send<'req, 'buf>(
         &'req mut self,
         rx_buf: &'buf mut [u8],
-    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>>, Error> {
+    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>, MAX_HEADERS>, Error> {
+        let request = self.request.take().ok_or(Error::AlreadySent)?.build();
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.write_request(&request).await?;
+            conn.flush().await?;
+            Response::read_with_config(conn, request.method, rx_buf, &config).await
+        })
+        .await
+    }
+
+    /// Send the request, receiving the response in the buffer of the client.
+    ///
+    /// The client must be created with [`HttpClient::new_with_buffers()`] or configured with
+    /// [`HttpClient::rx_buffer()`], or else reading the response fails with [`Error::BufferTooSmall`].
+    pub async fn fetch(&mut self) -> Result<Response<'_, '_, HttpConnection<'conn, C>, MAX_HEADERS>, Error> {
         let request = self.request.take().ok_or(Error::AlreadySent)?.build();
-        self.conn.write_request(&request).await?;
-        self.conn.flush().await?;
-        Response::read(&mut self.conn, request.method, rx_buf).await
+        let conn = &mut self.conn;
+        let rx_buf = &mut *self.rx_buf;
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.write_request(&request).await?;
+            conn.flush().await?;
+            Response::read_with_config(conn, request.method, rx_buf, &config).await
+        })
+        .await
     }
 }
 
-impl<'m, C, B> RequestBuilder<'m, B> for HttpRequestHandle<'m, C, B>
+impl<'m, C, B, const MAX_HEADERS: usize> RequestBuilder<'m, B> for HttpRequestHandle<'m, C, B, MAX_HEADERS>
 where
     C: Read + Write,
     B: RequestBody,
 {
-    type WithBody<T: RequestBody> = HttpRequestHandle<'m, C, T>;
+    type WithBody<T: RequestBody> = HttpRequestHandle<'m, C, T, MAX_HEADERS>;
 
     fn headers(mut self, headers: &'m [(&'m str, &'m str)]) -> Self {
         self.request = Some(self.request.unwrap().headers(headers));
@@ -458,6 +1832,9 @@ where
         HttpRequestHandle {
             conn: self.conn,
             request: Some(self.request.unwrap().body(body)),
+            response_config: self.response_config,
+            metrics: self.metrics,
+            rx_buf: self.rx_buf,
         }
     }
 
@@ -481,6 +1858,26 @@ where
         self
     }
 
+    fn bearer_auth(mut self, token: &'m str) -> Self {
+        self.request = Some(self.request.unwrap().bearer_auth(token));
+        self
+    }
+
+    fn auth(mut self, auth: Auth<'m>) -> Self {
+        self.request = Some(self.request.unwrap().auth(auth));
+        self
+    }
+
+    fn range(mut self, start: u64, end: Option<u64>) -> Self {
+        self.request = Some(self.request.unwrap().range(start, end));
+        self
+    }
+
+    fn upgrade(mut self, protocol: &'m str) -> Self {
+        self.request = Some(self.request.unwrap().upgrade(protocol));
+        self
+    }
+
     fn build(self) -> Request<'m, B> {
         self.request.unwrap().build()
     }
@@ -489,16 +1886,25 @@ where
 /// A HTTP resource describing a scoped endpoint
 ///
 /// The underlying connection is closed when drop'ed.
-pub struct HttpResource<'res, C>
+pub struct HttpResource<'res, C, const MAX_HEADERS: usize = 64>
 where
     C: Read + Write,
 {
     pub conn: HttpConnection<'res, C>,
     pub host: &'res str,
     pub base_path: &'res str,
+    pub response_config: ResponseConfig,
+    /// The forward proxy that requests to this resource are sent to, if any.
+    pub(crate) forward_proxy: Option<ForwardProxy<'res>>,
+    /// The recorder of the requests sent to this resource, if any.
+    pub(crate) metrics: Option<&'res dyn Recorder>,
+    /// The authentication of requests to this resource that are not authenticated otherwise, if any.
+    pub(crate) auth: Option<Auth<'res>>,
+    /// The credentials answering the challenge of a `401 Unauthorized` response, if any.
+    pub(crate) credentials: Option<Credentials<'res>>,
 }
 
-impl<'res, C> HttpResource<'res, C>
+impl<'res, C, const MAX_HEADERS: usize> HttpResource<'res, C, MAX_HEADERS>
 where
     C: Read + Write,
 {
@@ -506,7 +1912,7 @@ where
     ///
     /// This is only relevant if no TLS is used, as `embedded-tls` buffers internally and we reuse
     /// its buffer for non-TLS connections.
-    pub fn into_buffered<'buf>(self, tx_buf: &'buf mut [u8]) -> HttpResource<'buf, C>
+    pub fn into_buffered<'buf>(self, tx_buf: &'buf mut [u8]) -> HttpResource<'buf, C, MAX_HEADERS>
     where
         'res: 'buf,
     {
@@ -514,46 +1920,97 @@ where
             conn: self.conn.into_buffered(tx_buf),
             host: self.host,
             base_path: self.base_path,
+            response_config: self.response_config,
+            forward_proxy: self.forward_proxy,
+            metrics: self.metrics,
+            auth: self.auth,
+            credentials: self.credentials,
+        }
+    }
+
+    /// Set the maximum number of headers that can be parsed in the responses, each of which takes up stack space
+    /// while parsing. Responses with more headers are rejected with [`Error::HeadersTooLarge`].
+    pub fn header_capacity<const N: usize>(self) -> HttpResource<'res, C, N> {
+        HttpResource {
+            conn: self.conn,
+            host: self.host,
+            base_path: self.base_path,
+            response_config: self.response_config,
+            forward_proxy: self.forward_proxy,
+            metrics: self.metrics,
+            auth: self.auth,
+            credentials: self.credentials,
         }
     }
 
+    /// Authenticate every request to this resource with `auth`, unless the request sets its own authentication.
+    pub fn auth(mut self, auth: Auth<'res>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Answer the challenge of a `401 Unauthorized` response with `credentials`, using the `Basic` or `Digest`
+    /// scheme, by sending the request once more.
+    ///
+    /// Requests that set their own authentication are not retried, and neither are requests whose
+    /// `401 Unauthorized` response closes the connection.
+    pub fn credentials(mut self, credentials: Credentials<'res>) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
     pub fn request<'req>(
         &'req mut self,
         method: Method,
         path: &'req str,
-    ) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    ) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         HttpResourceRequestBuilder {
             conn: &mut self.conn,
-            request: Request::new(method, path).host(self.host),
+            request: Request::new(method, path)
+                .host(self.host)
+                .forward_proxy(self.forward_proxy)
+                .default_auth(self.auth),
             base_path: self.base_path,
+            response_config: self.response_config,
+            metrics: self.metrics,
+            credentials: self.credentials,
         }
     }
 
     /// Create a new scoped GET http request.
-    pub fn get<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    pub fn get<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         self.request(Method::GET, path)
     }
 
     /// Create a new scoped POST http request.
-    pub fn post<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    pub fn post<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         self.request(Method::POST, path)
     }
 
     /// Create a new scoped PUT http request.
-    pub fn put<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    pub fn put<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         self.request(Method::PUT, path)
     }
 
     /// Create a new scoped DELETE http request.
-    pub fn delete<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    pub fn delete<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         self.request(Method::DELETE, path)
     }
 
     /// Create a new scoped HEAD http request.
-    pub fn head<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, ()> {
+    pub fn head<'req>(&'req mut self, path: &'req str) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
         self.request(Method::HEAD, path)
     }
 
+    /// Create a new scoped OPTIONS http request, e.g. to discover the methods supported by a resource using
+    /// [`Response::allow()`].
+    pub fn options<'req>(
+        &'req mut self,
+        path: &'req str,
+    ) -> HttpResourceRequestBuilder<'req, 'res, C, (), MAX_HEADERS> {
+        self.request(Method::OPTIONS, path)
+    }
+
     /// Send a request to a resource.
     ///
     /// The base path of the resource is prepended to the request path.
@@ -564,15 +2021,144 @@ where
         &'req mut self,
         mut request: Request<'req, B>,
         rx_buf: &'buf mut [u8],
-    ) -> Result<Response<'req, 'buf, HttpConnection<'res, C>>, Error> {
+    ) -> Result<Response<'req, 'buf, HttpConnection<'res, C>, MAX_HEADERS>, Error> {
+        request.base_path = Some(self.base_path);
+        request.forward_proxy = self.forward_proxy;
+        if request.auth.is_none() {
+            request.auth = self.auth;
+        }
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        let credentials = self.credentials;
+        record_request(self.metrics, async move {
+            conn.send_authenticated(request, rx_buf, &config, credentials).await
+        })
+        .await
+    }
+
+    /// Send a request to a resource, authenticated with the bearer token of the `provider`.
+    ///
+    /// If the server rejects the token with `401 Unauthorized`, the token is refreshed and the request is sent
+    /// once more. The token replaces any authentication of the request or the resource.
+    pub async fn send_with_token<'req, 'buf, B: RequestBody, P: TokenProvider>(
+        &'req mut self,
+        mut request: Request<'req, B>,
+        provider: &mut P,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Response<'req, 'buf, HttpConnection<'res, C>, MAX_HEADERS>, Error> {
+        request.base_path = Some(self.base_path);
+        request.forward_proxy = self.forward_proxy;
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.send_with_token(request, provider, rx_buf, &config).await
+        })
+        .await
+    }
+
+    /// Send a request to a resource, with the signature header computed by the `signer`.
+    ///
+    /// The body is written to the `signer` before the request is written, see [`RequestSigner`].
+    pub async fn send_signed<'req, 'buf, B: RequestBody, S: RequestSigner>(
+        &'req mut self,
+        mut request: Request<'req, B>,
+        signer: &mut S,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Response<'req, 'buf, HttpConnection<'res, C>, MAX_HEADERS>, Error> {
+        request.base_path = Some(self.base_path);
+        request.forward_proxy = self.forward_proxy;
+        if request.auth.is_none() {
+            request.auth = self.auth;
+        }
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.write_signed_request(&request, signer).await?;
+            conn.flush().await?;
+            Response::read_with_config(conn, request.method, rx_buf, &config).await
+        })
+        .await
+    }
+
+    /// Send a conditional GET request to a resource, using the validators stored for it.
+    ///
+    /// The stored `ETag` and `Last-Modified` values are sent as `If-None-Match` and `If-Modified-Since`.
+    /// If the server responds with `304 Not Modified`, [`Conditional::NotModified`] is returned without reading
+    /// any body. Otherwise the response is returned, and the validators of a `200 OK` response are stored.
+    pub async fn get_conditional<'req, 'buf, V: ValidatorStore>(
+        &'req mut self,
+        path: &str,
+        store: &mut V,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Conditional<'req, 'buf, HttpConnection<'res, C>, MAX_HEADERS>, Error> {
+        let key = resource_key(self.host, self.base_path, path)?;
+        let validators = validator::load(store, &key).await;
+
+        let mut request = Request::get(path).host(self.host).build();
+        request.base_path = Some(self.base_path);
+        request.forward_proxy = self.forward_proxy;
+        if request.auth.is_none() {
+            request.auth = self.auth;
+        }
+        validators.apply(&mut request);
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        let credentials = self.credentials;
+        let response = record_request(self.metrics, async move {
+            conn.send_authenticated(request, rx_buf, &config, credentials).await
+        })
+        .await?;
+        if response.status == Status::NotModified {
+            response.body().discard().await?;
+            return Ok(Conditional::NotModified);
+        }
+
+        validator::store(store, &key, &response).await;
+        Ok(Conditional::Modified(response))
+    }
+
+    /// Resume the download of a resource at `offset`, e.g. after the connection was lost while receiving it.
+    ///
+    /// The bytes from `offset` are requested using a `Range` request, which is conditional on the `etag` of the
+    /// partially downloaded resource, if any. See [`Resumed`] for how the body is received if the server does not
+    /// support ranges or the resource changed.
+    pub async fn resume<'req, 'buf>(
+        &'req mut self,
+        path: &'req str,
+        offset: u64,
+        etag: Option<&'req str>,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Resumed<BodyReader<BufferingReader<'req, 'buf, HttpConnection<'res, C>>>>, Error> {
+        let mut request = Request::get(path).host(self.host).range(offset, None).build();
         request.base_path = Some(self.base_path);
-        self.conn.write_request(&request).await?;
-        self.conn.flush().await?;
-        Response::read(&mut self.conn, request.method, rx_buf).await
+        request.forward_proxy = self.forward_proxy;
+        if request.auth.is_none() {
+            request.auth = self.auth;
+        }
+        request.if_range = etag;
+        let conn = &mut self.conn;
+        let config = self.response_config;
+        let credentials = self.credentials;
+        let response: Response<'_, '_, _, MAX_HEADERS> = record_request(self.metrics, async move {
+            conn.send_authenticated(request, rx_buf, &config, credentials).await
+        })
+        .await?;
+        Resumed::new(response, offset, etag)
+    }
+
+    /// Send a `HEAD` request to a resource, and get its metadata without downloading it.
+    ///
+    /// The response has no body, so the connection is ready for the next request to the resource. The buffer must
+    /// be large enough to contain the response headers.
+    pub async fn metadata(&mut self, path: &str, rx_buf: &mut [u8]) -> Result<Metadata, Error> {
+        let response = self.head(path).send(rx_buf).await?;
+        let metadata = Metadata::from_response(&response);
+        response.body().discard().await?;
+        Ok(metadata)
     }
 }
 
-pub struct HttpResourceRequestBuilder<'req, 'conn, C, B>
+pub struct HttpResourceRequestBuilder<'req, 'conn, C, B, const MAX_HEADERS: usize = 64>
 where
     C: Read + Write,
     B: RequestBody,
@@ -580,9 +2166,12 @@ where
     conn: &'req mut HttpConnection<'conn, C>,
     base_path: &'req str,
     request: DefaultRequestBuilder<'req, B>,
+    response_config: ResponseConfig,
+    metrics: Option<&'req dyn Recorder>,
+    credentials: Option<Credentials<'req>>,
 }
 
-impl<'req, 'conn, C, B> HttpResourceRequestBuilder<'req, 'conn, C, B>
+impl<'req, 'conn, C, B, const MAX_HEADERS: usize> HttpResourceRequestBuilder<'req, 'conn, C, B, MAX_HEADERS>
 where
     C: Read + Write,
     B: RequestBody,
@@ -596,22 +2185,64 @@ where
     pub async fn send<'buf>(
         self,
         rx_buf: &'buf mut [u8],
-    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>>, Error> {
+    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>, MAX_HEADERS>, Error> {
+        let conn = self.conn;
+        let mut request = self.request.build();
+        request.base_path = Some(self.base_path);
+        let config = self.response_config;
+        let credentials = self.credentials;
+        record_request(self.metrics, async move {
+            conn.send_authenticated(request, rx_buf, &config, credentials).await
+        })
+        .await
+    }
+
+    /// Send the request, with the signature header computed by the `signer`.
+    ///
+    /// See [`HttpResource::send_signed()`].
+    pub async fn send_signed<'buf, S: RequestSigner>(
+        self,
+        signer: &mut S,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>, MAX_HEADERS>, Error> {
+        let conn = self.conn;
+        let mut request = self.request.build();
+        request.base_path = Some(self.base_path);
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.write_signed_request(&request, signer).await?;
+            conn.flush().await?;
+            Response::read_with_config(conn, request.method, rx_buf, &config).await
+        })
+        .await
+    }
+
+    /// Send the request, authenticated with the bearer token of the `provider`.
+    ///
+    /// See [`HttpResource::send_with_token()`].
+    pub async fn send_with_token<'buf, P: TokenProvider>(
+        self,
+        provider: &mut P,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Response<'req, 'buf, HttpConnection<'conn, C>, MAX_HEADERS>, Error> {
         let conn = self.conn;
         let mut request = self.request.build();
         request.base_path = Some(self.base_path);
-        conn.write_request(&request).await?;
-        conn.flush().await?;
-        Response::read(conn, request.method, rx_buf).await
+        let config = self.response_config;
+        record_request(self.metrics, async move {
+            conn.send_with_token(request, provider, rx_buf, &config).await
+        })
+        .await
     }
 }
 
-impl<'req, 'conn, C, B> RequestBuilder<'req, B> for HttpResourceRequestBuilder<'req, 'conn, C, B>
+impl<'req, 'conn, C, B, const MAX_HEADERS: usize> RequestBuilder<'req, B>
+    for HttpResourceRequestBuilder<'req, 'conn, C, B, MAX_HEADERS>
 where
     C: Read + Write,
     B: RequestBody,
 {
-    type WithBody<T: RequestBody> = HttpResourceRequestBuilder<'req, 'conn, C, T>;
+    type WithBody<T: RequestBody> = HttpResourceRequestBuilder<'req, 'conn, C, T, MAX_HEADERS>;
 
     fn headers(mut self, headers: &'req [(&'req str, &'req str)]) -> Self {
         self.request = self.request.headers(headers);
@@ -628,6 +2259,9 @@ where
             conn: self.conn,
             base_path: self.base_path,
             request: self.request.body(body),
+            response_config: self.response_config,
+            metrics: self.metrics,
+            credentials: self.credentials,
         }
     }
 
@@ -651,6 +2285,26 @@ where
         self
     }
 
+    fn bearer_auth(mut self, token: &'req str) -> Self {
+        self.request = self.request.bearer_auth(token);
+        self
+    }
+
+    fn auth(mut self, auth: Auth<'req>) -> Self {
+        self.request = self.request.auth(auth);
+        self
+    }
+
+    fn range(mut self, start: u64, end: Option<u64>) -> Self {
+        self.request = self.request.range(start, end);
+        self
+    }
+
+    fn upgrade(mut self, protocol: &'req str) -> Self {
+        self.request = self.request.upgrade(protocol);
+        self
+    }
+
     fn build(self) -> Request<'req, B> {
         self.request.build()
     }
@@ -662,6 +2316,30 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    #[cfg(feature = "embedded-tls")]
+    fn can_find_host_psk() {
+        let psks = [
+            HostPsk {
+                host: "staging.example.com",
+                identity: b"staging",
+                psk: b"secret1",
+            },
+            HostPsk {
+                host: "example.com",
+                identity: b"production",
+                psk: b"secret2",
+            },
+        ];
+
+        assert_eq!(b"production", find_host_psk(&psks, "Example.COM").unwrap().identity);
+        assert_eq!(
+            b"staging",
+            find_host_psk(&psks, "staging.example.com").unwrap().identity
+        );
+        assert!(find_host_psk(&psks, "other.example.com").is_none());
+    }
+
     #[derive(Default)]
     struct VecBuffer(Vec<u8>);
 
@@ -704,6 +2382,48 @@ mod tests {
         assert_eq!(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nBODY", buffer.0.as_slice());
     }
 
+    /// Signs the method, target, date and body, in place of an HMAC.
+    #[derive(Default)]
+    struct ConcatSigner {
+        body: Vec<u8>,
+        signature: std::string::String,
+    }
+
+    impl RequestSigner for ConcatSigner {
+        fn update(&mut self, data: &[u8]) {
+            self.body.extend_from_slice(data);
+        }
+
+        fn sign<B: RequestBody>(&mut self, request: &Request<'_, B>) -> Result<(&str, &str), Error> {
+            self.signature = format!(
+                "{} {} {} {}",
+                request.method().as_str(),
+                request.target().concat(),
+                request.header("x-date").ok_or(Error::Codec)?,
+                core::str::from_utf8(&self.body).unwrap()
+            );
+            Ok(("X-Signature", &self.signature))
+        }
+    }
+
+    #[tokio::test]
+    async fn with_signature() {
+        let mut buffer = VecBuffer::default();
+        let mut conn = HttpConnection::Plain(&mut buffer);
+
+        let request = Request::new(Method::POST, "/hook")
+            .headers(&[("X-Date", "today")])
+            .body(b"BODY".as_slice())
+            .build();
+        let mut signer = ConcatSigner::default();
+        conn.write_signed_request(&request, &mut signer).await.unwrap();
+
+        assert_eq!(
+            b"POST /hook HTTP/1.1\r\nContent-Length: 4\r\nX-Date: today\r\nX-Signature: POST /hook today BODY\r\n\r\nBODY",
+            buffer.0.as_slice()
+        );
+    }
+
     struct ChunkedBody(&'static [&'static [u8]]);
 
     impl RequestBody for ChunkedBody {
@@ -724,7 +2444,7 @@ mod tests {
         let mut buffer = VecBuffer::default();
         let mut conn = HttpConnection::Plain(&mut buffer);
 
-        static CHUNKS: [&'static [u8]; 2] = [b"PART1", b"PART2"];
+        static CHUNKS: [&[u8]; 2] = [b"PART1", b"PART2"];
         let request = Request::new(Method::POST, "/").body(ChunkedBody(&CHUNKS)).build();
         conn.write_request(&request).await.unwrap();
 
@@ -740,7 +2460,7 @@ mod tests {
         let mut tx_buf = [0; 1024];
         let mut conn = HttpConnection::Plain(&mut buffer).into_buffered(&mut tx_buf);
 
-        static CHUNKS: [&'static [u8]; 2] = [b"PART1", b"PART2"];
+        static CHUNKS: [&[u8]; 2] = [b"PART1", b"PART2"];
         let request = Request::new(Method::POST, "/").body(ChunkedBody(&CHUNKS)).build();
         conn.write_request(&request).await.unwrap();
 
@@ -749,4 +2469,146 @@ mod tests {
             buffer.0.as_slice()
         );
     }
+
+    struct EarlyResponder {
+        written: Vec<u8>,
+        response: &'static [u8],
+        /// The response is available once this many bytes are written
+        ready_after: usize,
+    }
+
+    impl ErrorType for EarlyResponder {
+        type Error = Infallible;
+    }
+
+    impl Read for EarlyResponder {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let len = buf.len().min(self.response.len());
+            buf[..len].copy_from_slice(&self.response[..len]);
+            self.response = &self.response[len..];
+            Ok(len)
+        }
+    }
+
+    impl ReadReady for EarlyResponder {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.written.len() >= self.ready_after)
+        }
+    }
+
+    impl Write for EarlyResponder {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn aborts_upload_on_early_response() {
+        let mut responder = EarlyResponder {
+            written: Vec::new(),
+            response: b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 0\r\n\r\n",
+            ready_after: 1,
+        };
+        let mut conn = HttpConnection::Plain(&mut responder);
+
+        static CHUNKS: [&[u8]; 2] = [b"PART1", b"PART2"];
+        let request = Request::new(Method::POST, "/").body(ChunkedBody(&CHUNKS)).build();
+        let mut rx_buf = [0; 1024];
+        let upload = conn.send_with_early_response(request, &mut rx_buf).await.unwrap();
+
+        let Upload::Interrupted(response) = upload else {
+            panic!("Expected the upload to be interrupted");
+        };
+        assert_eq!(Status::ContentTooLarge, response.status);
+        drop(response);
+        assert_eq!(
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+            responder.written.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn completes_upload_without_early_response() {
+        let mut responder = EarlyResponder {
+            written: Vec::new(),
+            response: b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+            ready_after: usize::MAX,
+        };
+        let mut conn = HttpConnection::Plain(&mut responder);
+
+        let request = Request::new(Method::POST, "/").body(b"BODY".as_slice()).build();
+        let mut rx_buf = [0; 1024];
+        let upload = conn.send_with_early_response(request, &mut rx_buf).await.unwrap();
+
+        assert!(matches!(upload, Upload::Completed(_)));
+    }
+
+    #[tokio::test]
+    async fn resource_with_small_header_capacity() {
+        let mut rx_buf = [0; 1024];
+        for (response, fits) in [
+            (b"HTTP/1.1 200 OK\r\nA: 1\r\nContent-Length: 0\r\n\r\n".as_slice(), true),
+            (
+                b"HTTP/1.1 200 OK\r\nA: 1\r\nB: 2\r\nContent-Length: 0\r\n\r\n".as_slice(),
+                false,
+            ),
+        ] {
+            let mut responder = EarlyResponder {
+                written: Vec::new(),
+                response,
+                ready_after: 0,
+            };
+            let mut resource = HttpResource::<_> {
+                conn: HttpConnection::Plain(&mut responder),
+                host: "example.com",
+                base_path: "/",
+                response_config: ResponseConfig::new(),
+                forward_proxy: None,
+                metrics: None,
+                auth: None,
+                credentials: None,
+            }
+            .header_capacity::<2>();
+
+            let response = resource.get("/").send(&mut rx_buf).await;
+            if fits {
+                let response = response.unwrap();
+                assert_eq!(2, response.headers().count());
+                response.body().discard().await.unwrap();
+            } else {
+                assert!(matches!(response, Err(Error::HeadersTooLarge)));
+            }
+        }
+    }
+
+    struct ChecksumBody(&'static [u8]);
+
+    impl RequestBody for ChecksumBody {
+        async fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+            writer.write_all(self.0).await
+        }
+
+        async fn write_trailers<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+            let sum = self.0.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            writer.write_all(b"X-Checksum: ").await?;
+            writer.write_all(&[b'0' + sum % 10]).await?;
+            writer.write_all(b"\r\n").await
+        }
+    }
+
+    #[tokio::test]
+    async fn with_unknown_body_and_trailers() {
+        let mut buffer = VecBuffer::default();
+        let mut tx_buf = [0; 1024];
+        let mut conn = HttpConnection::Plain(&mut buffer).into_buffered(&mut tx_buf);
+
+        let request = Request::new(Method::POST, "/").body(ChecksumBody(b"AB")).build();
+        conn.write_request(&request).await.unwrap();
+
+        assert_eq!(
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nAB\r\n0\r\nX-Checksum: 1\r\n\r\n",
+            buffer.0.as_slice()
+        );
+    }
 }
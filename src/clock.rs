@@ -0,0 +1,62 @@
+/// Setting the clock of devices without NTP from the `Date` header of a server, which is accurate enough to validate
+/// the expiry of TLS certificates.
+///
+use embedded_nal_async::{Dns, TcpConnect};
+
+use crate::client::HttpClient;
+use crate::request::Method;
+use crate::Error;
+
+/// Get the current time from the `Date` header of the response to a `HEAD` request to `url`, as the number of
+/// seconds since the Unix epoch.
+///
+/// The `now_ms` function returns a monotonic timestamp in milliseconds, which is used to measure the round trip
+/// time of the request. The server generates the date halfway the round trip on average, so half of the round trip
+/// time is added to it. The returned time is the time at which the response was received.
+///
+/// The server is trusted to send the correct time, so the request should not be sent over TLS, which requires the
+/// time to validate the certificate of the server. Returns [`Error::Codec`] if the response has no valid date.
+pub async fn fetch_time<T, D, F>(
+    client: &mut HttpClient<'_, T, D>,
+    url: &str,
+    rx_buf: &mut [u8],
+    mut now_ms: F,
+) -> Result<u64, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+    F: FnMut() -> u64,
+{
+    let mut request = client.request(Method::HEAD, url).await?;
+    let sent = now_ms();
+    let response = request.send(rx_buf).await?;
+    let received = now_ms();
+    let Some(date) = response.date() else {
+        warn!("Response has no valid Date header");
+        return Err(Error::Codec);
+    };
+    response.body().discard().await?;
+
+    let round_trip = received.saturating_sub(sent);
+    Ok(estimate_time(date, round_trip))
+}
+
+/// Estimate the time at which the response was received, given its date and the round trip time in milliseconds.
+fn estimate_time(date: u64, round_trip: u64) -> u64 {
+    // The date is truncated to whole seconds, so it is half a second early on average
+    let ms = date.saturating_mul(1000) + 500 + round_trip / 2;
+    ms / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compensates_round_trip_time() {
+        assert_eq!(1000, estimate_time(1000, 0));
+        assert_eq!(1000, estimate_time(1000, 998));
+        assert_eq!(1001, estimate_time(1000, 1000));
+        assert_eq!(1005, estimate_time(1000, 10_000));
+    }
+}
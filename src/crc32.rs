@@ -0,0 +1,170 @@
+/// The CRC-32 checksum (IEEE 802.3), as used by device management protocols to check the integrity of transfers,
+/// and wrappers computing it over the bytes read from a response body or written to a request.
+///
+/// CRC-32 detects accidental corruption only, and must not be used where a cryptographic digest is required.
+use embedded_io::ErrorType;
+use embedded_io_async::{Read, Write};
+
+/// The CRC-32 of each value of a nibble, for the reflected polynomial `0xEDB88320`.
+const TABLE: [u32; 16] = [
+    0x00000000, 0x1db71064, 0x3b6e20c8, 0x26d930ac, 0x76dc4190, 0x6b6b51f4, 0x4db26158, 0x5005713c, 0xedb88320,
+    0xf00f9344, 0xd6d6a3e8, 0xcb61b38c, 0x9b64c2b0, 0x86d3d2d4, 0xa00ae278, 0xbdbdf21c,
+];
+
+/// A CRC-32 computed incrementally.
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self { state: 0xffffffff }
+    }
+
+    /// Update the checksum with the next part of the data.
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            let mut state = self.state ^ b as u32;
+            state = (state >> 4) ^ TABLE[(state & 0xf) as usize];
+            state = (state >> 4) ^ TABLE[(state & 0xf) as usize];
+            self.state = state;
+        }
+    }
+
+    /// The checksum of the data so far.
+    pub fn finalize(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// A reader computing the CRC-32 of the bytes read through it, e.g. from a response body.
+pub struct Crc32Reader<R> {
+    reader: R,
+    crc: Crc32,
+}
+
+impl<R> Crc32Reader<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            crc: Crc32::new(),
+        }
+    }
+
+    /// The checksum of the bytes read so far.
+    pub fn crc(&self) -> u32 {
+        self.crc.finalize()
+    }
+
+    /// Get the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> ErrorType for Crc32Reader<R>
+where
+    R: Read,
+{
+    type Error = R::Error;
+}
+
+impl<R> Read for Crc32Reader<R>
+where
+    R: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let read = self.reader.read(buf).await?;
+        self.crc.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// A writer computing the CRC-32 of the bytes written through it, e.g. of a request body.
+pub struct Crc32Writer<W> {
+    writer: W,
+    crc: Crc32,
+}
+
+impl<W> Crc32Writer<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            crc: Crc32::new(),
+        }
+    }
+
+    /// The checksum of the bytes written so far.
+    pub fn crc(&self) -> u32 {
+        self.crc.finalize()
+    }
+
+    /// Get the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> ErrorType for Crc32Writer<W>
+where
+    W: Write,
+{
+    type Error = W::Error;
+}
+
+impl<W> Write for Crc32Writer<W>
+where
+    W: Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let written = self.writer.write(buf).await?;
+        self.crc.update(&buf[..written]);
+        Ok(written)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.writer.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        let mut crc = Crc32::new();
+        assert_eq!(0, crc.finalize());
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(0xcbf43926, crc.finalize());
+    }
+
+    #[tokio::test]
+    async fn can_compute_crc_of_reader_and_writer() {
+        let mut reader = Crc32Reader::new(b"123456789".as_slice());
+        let mut buf = [0; 4];
+        while reader.read(&mut buf).await.unwrap() > 0 {}
+        assert_eq!(0xcbf43926, reader.crc());
+
+        let mut writer = Crc32Writer::new(Vec::new());
+        writer
+            .write_all(b"The quick brown fox jumps over the lazy dog")
+            .await
+            .unwrap();
+        assert_eq!(0x414fa339, writer.crc());
+    }
+}
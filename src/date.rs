@@ -0,0 +1,235 @@
+/// Parsing of HTTP-date values, as used in e.g. the `Date`, `Last-Modified` and `Expires` headers.
+///
+use crate::Error;
+
+const DAY_NAMES: [&[u8]; 7] = [b"Mon", b"Tue", b"Wed", b"Thu", b"Fri", b"Sat", b"Sun"];
+const LONG_DAY_NAMES: [&[u8]; 7] = [
+    b"Monday",
+    b"Tuesday",
+    b"Wednesday",
+    b"Thursday",
+    b"Friday",
+    b"Saturday",
+    b"Sunday",
+];
+const MONTH_NAMES: [&[u8]; 12] = [
+    b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun", b"Jul", b"Aug", b"Sep", b"Oct", b"Nov", b"Dec",
+];
+
+/// A source of wall-clock time, such as an RTC, NTP or the cellular network.
+pub trait TimeSource {
+    /// The number of seconds since the Unix epoch, or `None` if the time is not known (yet).
+    fn now(&self) -> Option<u64>;
+}
+
+/// Parse an HTTP-date into the number of seconds since the Unix epoch.
+///
+/// All formats specified in [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-5.6.7) are supported:
+///
+/// * IMF-fixdate: `Sun, 06 Nov 1994 08:49:37 GMT`
+/// * The obsolete RFC 850 format: `Sunday, 06-Nov-94 08:49:37 GMT`
+/// * The obsolete ANSI C `asctime()` format: `Sun Nov  6 08:49:37 1994`
+///
+/// The day name is not checked against the date. Dates before 1970 are rejected with [`Error::Codec`].
+pub fn parse_http_date(value: &[u8]) -> Result<u64, Error> {
+    let mut parser = Parser(value);
+
+    let date = if parser.skip_one_of(&DAY_NAMES).is_some() {
+        if parser.skip(b", ") {
+            // IMF-fixdate
+            let day = parser.number(2)?;
+            parser.expect(b" ")?;
+            let month = parser.month()?;
+            parser.expect(b" ")?;
+            let year = parser.number(4)?;
+            parser.expect(b" ")?;
+            let time = parser.time()?;
+            parser.expect(b" GMT")?;
+            (year, month, day, time)
+        } else {
+            // asctime
+            parser.expect(b" ")?;
+            let month = parser.month()?;
+            parser.expect(b" ")?;
+            let day = if parser.skip(b" ") {
+                parser.number(1)?
+            } else {
+                parser.number(2)?
+            };
+            parser.expect(b" ")?;
+            let time = parser.time()?;
+            parser.expect(b" ")?;
+            let year = parser.number(4)?;
+            (year, month, day, time)
+        }
+    } else if parser.skip_one_of(&LONG_DAY_NAMES).is_some() {
+        // RFC 850
+        parser.expect(b", ")?;
+        let day = parser.number(2)?;
+        parser.expect(b"-")?;
+        let month = parser.month()?;
+        parser.expect(b"-")?;
+        // Two digit years are assumed to be in the range 1970-2069
+        let year = match parser.number(2)? {
+            year @ 0..=69 => 2000 + year,
+            year => 1900 + year,
+        };
+        parser.expect(b" ")?;
+        let time = parser.time()?;
+        parser.expect(b" GMT")?;
+        (year, month, day, time)
+    } else {
+        return Err(Error::Codec);
+    };
+
+    if !parser.0.is_empty() {
+        return Err(Error::Codec);
+    }
+
+    let (year, month, day, (hour, minute, second)) = date;
+    if year < 1970 || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+        return Err(Error::Codec);
+    }
+
+    let days = days_since_epoch(year, month, day);
+    Ok(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// A minimal parser consuming the input from the front.
+///
+/// Each method only consumes the input if it succeeds.
+struct Parser<'a>(&'a [u8]);
+
+impl Parser<'_> {
+    fn skip(&mut self, expected: &[u8]) -> bool {
+        match self.0.strip_prefix(expected) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, expected: &[u8]) -> Result<(), Error> {
+        if self.skip(expected) {
+            Ok(())
+        } else {
+            Err(Error::Codec)
+        }
+    }
+
+    /// Skip one of the names, returning its index.
+    fn skip_one_of(&mut self, names: &[&[u8]]) -> Option<usize> {
+        let rest = self.0;
+        let index = names.iter().position(|name| self.skip(name))?;
+        // Names must not be followed by more letters, e.g. "Sun" is not "Sunday"
+        if self.0.first().is_some_and(|b| b.is_ascii_alphabetic()) {
+            self.0 = rest;
+            return None;
+        }
+        Some(index)
+    }
+
+    fn month(&mut self) -> Result<u64, Error> {
+        self.skip_one_of(&MONTH_NAMES)
+            .map(|index| index as u64 + 1)
+            .ok_or(Error::Codec)
+    }
+
+    /// Parse a number with exactly `digits` digits.
+    fn number(&mut self, digits: usize) -> Result<u64, Error> {
+        let number = self.0.get(..digits).ok_or(Error::Codec)?;
+        if !number.iter().all(|b| b.is_ascii_digit()) {
+            return Err(Error::Codec);
+        }
+        self.0 = &self.0[digits..];
+        Ok(number.iter().fold(0, |n, b| n * 10 + (b - b'0') as u64))
+    }
+
+    /// Parse a `HH:MM:SS` time of day.
+    fn time(&mut self) -> Result<(u64, u64, u64), Error> {
+        let hour = self.number(2)?;
+        self.expect(b":")?;
+        let minute = self.number(2)?;
+        self.expect(b":")?;
+        let second = self.number(2)?;
+        Ok((hour, minute, second))
+    }
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days from 1970-01-01 to the provided date, which must not be before 1970.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Count from March, such that the leap day is the last day of the year
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era_days = year * 365 + year / 4 - year / 100 + year / 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    // The number of days from 0000-03-01 to 1970-01-01
+    era_days + day_of_year - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOV_6_1994: u64 = 784111777;
+
+    #[test]
+    fn can_parse_imf_fixdate() {
+        assert_eq!(NOV_6_1994, parse_http_date(b"Sun, 06 Nov 1994 08:49:37 GMT").unwrap());
+        assert_eq!(0, parse_http_date(b"Thu, 01 Jan 1970 00:00:00 GMT").unwrap());
+        assert_eq!(951782400, parse_http_date(b"Tue, 29 Feb 2000 00:00:00 GMT").unwrap());
+        assert_eq!(4102444799, parse_http_date(b"Thu, 31 Dec 2099 23:59:59 GMT").unwrap());
+    }
+
+    #[test]
+    fn can_parse_rfc850_date() {
+        assert_eq!(NOV_6_1994, parse_http_date(b"Sunday, 06-Nov-94 08:49:37 GMT").unwrap());
+        assert_eq!(1262304000, parse_http_date(b"Friday, 01-Jan-10 00:00:00 GMT").unwrap());
+    }
+
+    #[test]
+    fn can_parse_asctime_date() {
+        assert_eq!(NOV_6_1994, parse_http_date(b"Sun Nov  6 08:49:37 1994").unwrap());
+        assert_eq!(
+            NOV_6_1994 + 4 * 86400,
+            parse_http_date(b"Thu Nov 10 08:49:37 1994").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for date in [
+            b"".as_slice(),
+            b"Sun, 06 Nov 1994 08:49:37 UTC".as_slice(),
+            b"Sun, 6 Nov 1994 08:49:37 GMT".as_slice(),
+            b"Sun, 31 Nov 1994 08:49:37 GMT".as_slice(),
+            b"Sun, 29 Feb 1900 08:49:37 GMT".as_slice(),
+            b"Sun, 06 Nov 1994 24:49:37 GMT".as_slice(),
+            b"Sun, 06 Foo 1994 08:49:37 GMT".as_slice(),
+            b"Sun, 06 Nov 1969 08:49:37 GMT".as_slice(),
+            b"Sun, 06 Nov 1994 08:49:37 GMT trailing".as_slice(),
+            b"Sunday, 06 Nov 1994 08:49:37 GMT".as_slice(),
+            b"1994-11-06T08:49:37Z".as_slice(),
+        ] {
+            assert!(parse_http_date(date).is_err());
+        }
+    }
+}
@@ -0,0 +1,260 @@
+/// Full-duplex exchanges, where the response is read while the request body is still being written.
+///
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::body_writer::ChunkedBodyWriter;
+use crate::client::HttpConnection;
+use crate::request::{Request, RequestBody};
+use crate::response::{Response, ResponseConfig};
+use crate::Error;
+
+/// A connection that can be split into halves that read and write independently.
+pub trait Split {
+    type Reader<'a>: Read
+    where
+        Self: 'a;
+    type Writer<'a>: Write
+    where
+        Self: 'a;
+
+    /// Split the connection into a reader and a writer.
+    fn split(&mut self) -> (Self::Reader<'_>, Self::Writer<'_>);
+}
+
+/// The body of a request whose body is written through a [`DuplexWriter`].
+pub struct StreamingBody {
+    len: Option<usize>,
+}
+
+impl StreamingBody {
+    /// A body of unknown length, which is sent using chunked encoding.
+    pub const fn chunked() -> Self {
+        Self { len: None }
+    }
+
+    /// A body of a known length, which is sent with a `Content-Length` header.
+    pub const fn with_len(len: usize) -> Self {
+        Self { len: Some(len) }
+    }
+}
+
+impl RequestBody for StreamingBody {
+    fn len(&self) -> Option<usize> {
+        self.len
+    }
+
+    async fn write<W: Write>(&self, _writer: &mut W) -> Result<(), W::Error> {
+        // The body is written through the DuplexWriter
+        Ok(())
+    }
+
+    async fn rewind(&self) -> bool {
+        false
+    }
+}
+
+/// Write the request header and start a full-duplex exchange on the provided connection halves.
+///
+/// The request body is written through the returned [`DuplexWriter`], while the response is read through the
+/// returned [`DuplexReader`]. Both can be driven concurrently, e.g. using `join`.
+pub async fn start<'req, R, W>(
+    request: &Request<'req, StreamingBody>,
+    reader: R,
+    mut writer: W,
+    config: &ResponseConfig,
+) -> Result<(DuplexWriter<W>, DuplexReader<R>), Error>
+where
+    R: Read,
+    W: Write,
+{
+    request.write_header(&mut writer).await?;
+    writer.flush().await.map_err(|e| e.kind())?;
+
+    let body = match request.body.as_ref().and_then(|body| body.len) {
+        Some(len) => DuplexBody::Fixed {
+            conn: writer,
+            remaining: len,
+        },
+        None => DuplexBody::Chunked(ChunkedBodyWriter::new(writer)),
+    };
+
+    Ok((
+        DuplexWriter { body },
+        DuplexReader {
+            conn: reader,
+            method: request.method,
+            config: *config,
+        },
+    ))
+}
+
+/// The writing half of a full-duplex exchange, writing the request body.
+pub struct DuplexWriter<W>
+where
+    W: Write,
+{
+    body: DuplexBody<W>,
+}
+
+enum DuplexBody<W>
+where
+    W: Write,
+{
+    Fixed { conn: W, remaining: usize },
+    Chunked(ChunkedBodyWriter<W>),
+}
+
+impl<W> DuplexWriter<W>
+where
+    W: Write,
+{
+    /// Finish the request body.
+    ///
+    /// A body of known length must be written entirely, otherwise [`Error::IncorrectBodyWritten`] is returned.
+    pub async fn finish(self) -> Result<(), Error> {
+        match self.body {
+            DuplexBody::Fixed { mut conn, remaining } => {
+                if remaining != 0 {
+                    return Err(Error::IncorrectBodyWritten);
+                }
+                conn.flush().await.map_err(|e| e.kind())?;
+            }
+            DuplexBody::Chunked(mut writer) => {
+                writer.terminate().await.map_err(|e| e.kind())?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W> ErrorType for DuplexWriter<W>
+where
+    W: Write,
+{
+    type Error = Error;
+}
+
+impl<W> Write for DuplexWriter<W>
+where
+    W: Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        match &mut self.body {
+            DuplexBody::Fixed { conn, remaining } => {
+                if buf.len() > *remaining {
+                    return Err(Error::IncorrectBodyWritten);
+                }
+                let written = conn.write(buf).await.map_err(|e| e.kind())?;
+                *remaining -= written;
+                Ok(written)
+            }
+            DuplexBody::Chunked(writer) => Ok(writer.write(buf).await?),
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        match &mut self.body {
+            DuplexBody::Fixed { conn, .. } => conn.flush().await.map_err(|e| e.kind().into()),
+            DuplexBody::Chunked(writer) => Ok(writer.flush().await?),
+        }
+    }
+}
+
+/// The reading half of a full-duplex exchange, reading the response.
+pub struct DuplexReader<R>
+where
+    R: Read,
+{
+    conn: R,
+    method: crate::request::Method,
+    config: ResponseConfig,
+}
+
+impl<R> DuplexReader<R>
+where
+    R: Read,
+{
+    /// Read the response headers, which may arrive before the request body is finished.
+    ///
+    /// The response headers are stored in the provided rx_buf, which should be sized to contain at least the
+    /// response headers.
+    pub async fn response<'r, 'buf>(&'r mut self, rx_buf: &'buf mut [u8]) -> Result<Response<'r, 'buf, R>, Error> {
+        Response::read_with_config(&mut self.conn, self.method, rx_buf, &self.config).await
+    }
+}
+
+impl<T> HttpConnection<'_, T>
+where
+    T: Read + Write + Split,
+{
+    /// Start a full-duplex exchange, see [`start()`].
+    ///
+    /// Only plain connections can be split, as TLS connections read and write through a single session.
+    pub async fn duplex<'a, 'req>(
+        &'a mut self,
+        request: &Request<'req, StreamingBody>,
+        config: &ResponseConfig,
+    ) -> Result<(DuplexWriter<T::Writer<'a>>, DuplexReader<T::Reader<'a>>), Error> {
+        let conn = match self {
+            HttpConnection::Plain(conn) => conn,
+            HttpConnection::PlainBuffered(buffered) => {
+                buffered.flush().await.map_err(|e| e.kind())?;
+                buffered.split().0
+            }
+            HttpConnection::Tls(_) => return Err(Error::Network(ErrorKind::Unsupported)),
+            #[cfg(feature = "esp-mbedtls")]
+            HttpConnection::MbedTls(_) => return Err(Error::Network(ErrorKind::Unsupported)),
+            HttpConnection::Provided(_) => return Err(Error::Network(ErrorKind::Unsupported)),
+        };
+        let (reader, writer) = conn.split();
+        start(request, reader, writer, config).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestBuilder;
+
+    #[tokio::test]
+    async fn can_read_response_before_finishing_chunked_body() {
+        let mut written = Vec::new();
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n0\r\n\r\n".as_slice();
+        let request = Request::post("/stream").body(StreamingBody::chunked()).build();
+        let (mut writer, mut reader) = start(&request, &mut response, &mut written, &ResponseConfig::new())
+            .await
+            .unwrap();
+
+        writer.write_all(b"PING").await.unwrap();
+
+        let mut rx_buf = [0; 256];
+        let response = reader.response(&mut rx_buf).await.unwrap();
+        let mut body = [0; 16];
+        let len = response.body().reader().read_all(&mut body).await.unwrap();
+        assert_eq!(b"HELLO", &body[..len]);
+
+        writer.finish().await.unwrap();
+        assert_eq!(
+            b"POST /stream HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nPING\r\n0\r\n\r\n",
+            written.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_incomplete_body_of_known_length() {
+        let mut written = Vec::new();
+        let request = Request::post("/").body(StreamingBody::with_len(4)).build();
+        let (mut writer, _) = start(&request, b"".as_slice(), &mut written, &ResponseConfig::new())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            writer.write(b"TOO LONG").await,
+            Err(Error::IncorrectBodyWritten)
+        ));
+        writer.write_all(b"PI").await.unwrap();
+        assert!(matches!(writer.finish().await, Err(Error::IncorrectBodyWritten)));
+    }
+}
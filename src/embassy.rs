@@ -0,0 +1,121 @@
+/// Native embassy-net integration, connecting a caller-provided [`TcpSocket`] without `embedded-nal-async`.
+///
+/// The socket and its buffers are owned by the caller, avoiding the state pool of `embassy_net::tcp::client::TcpClient`.
+/// A socket can be reused for a new connection once the previous resource or request handle has been dropped.
+use embassy_net::dns::DnsQueryType;
+use embassy_net::tcp::TcpSocket;
+use embassy_net::Stack;
+use embedded_io::ErrorKind;
+
+use crate::client::{establish, HttpConnection, HttpRequestHandle, HttpResource};
+#[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+use crate::client::{TlsConfig, TlsRef};
+use crate::request::{Method, Request, RequestBuilder};
+use crate::response::ResponseConfig;
+use crate::url::Url;
+use crate::Error;
+
+/// An async HTTP client that establishes connections on the sockets of an embassy-net [`Stack`].
+pub struct EmbassyHttpClient<'a> {
+    stack: Stack<'a>,
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    tls: Option<TlsConfig<'a>>,
+    response_config: ResponseConfig,
+}
+
+impl<'a> EmbassyHttpClient<'a> {
+    /// Create a new HTTP client using the provided network stack.
+    pub fn new(stack: Stack<'a>) -> Self {
+        Self {
+            stack,
+            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            tls: None,
+            response_config: ResponseConfig::new(),
+        }
+    }
+
+    /// Create a new HTTP client using the provided network stack and TLS configuration.
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    pub fn new_with_tls(stack: Stack<'a>, tls: TlsConfig<'a>) -> Self {
+        Self {
+            stack,
+            tls: Some(tls),
+            response_config: ResponseConfig::new(),
+        }
+    }
+
+    /// Set the configuration used to parse the responses of requests made with this client.
+    pub fn response_config(mut self, config: ResponseConfig) -> Self {
+        self.response_config = config;
+        self
+    }
+
+    async fn connect<'conn, 's>(
+        &'conn mut self,
+        socket: &'conn mut TcpSocket<'s>,
+        url: &Url<'_>,
+    ) -> Result<HttpConnection<'conn, &'conn mut TcpSocket<'s>>, Error> {
+        let address = match url.ip() {
+            Some(ip) => ip.into(),
+            None => {
+                let addresses = self
+                    .stack
+                    .dns_query(url.host(), DnsQueryType::A)
+                    .await
+                    .map_err(|_| Error::Dns)?;
+                *addresses.first().ok_or(Error::Dns)?
+            }
+        };
+
+        socket
+            .connect((address, url.port_or_default()))
+            .await
+            .map_err(|_| Error::Network(ErrorKind::ConnectionRefused))?;
+
+        #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+        return establish(socket, url, self.tls.as_mut().map(TlsRef::from)).await;
+        #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
+        return establish(socket, url).await;
+    }
+
+    /// Create a single http request on the provided socket.
+    pub async fn request<'conn, 's>(
+        &'conn mut self,
+        socket: &'conn mut TcpSocket<'s>,
+        method: Method,
+        url: &'conn str,
+    ) -> Result<HttpRequestHandle<'conn, &'conn mut TcpSocket<'s>, ()>, Error> {
+        let url = Url::parse(url)?;
+        let response_config = self.response_config;
+        let conn = self.connect(socket, &url).await?;
+        Ok(HttpRequestHandle {
+            conn,
+            request: Some(Request::new(method, url.path()).host(url.authority())),
+            response_config,
+            metrics: None,
+            rx_buf: &mut [],
+        })
+    }
+
+    /// Connect the provided socket to a server with the provided `resource_url`.
+    /// The path in the url is considered the base path for subsequent requests.
+    pub async fn resource<'res, 's>(
+        &'res mut self,
+        socket: &'res mut TcpSocket<'s>,
+        resource_url: &'res str,
+    ) -> Result<HttpResource<'res, &'res mut TcpSocket<'s>>, Error> {
+        let resource_url = Url::parse(resource_url)?;
+        let response_config = self.response_config;
+        let conn = self.connect(socket, &resource_url).await?;
+        Ok(HttpResource {
+            conn,
+            host: resource_url.authority(),
+            base_path: resource_url.path(),
+            response_config,
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+/// A fake connection for the tests, answering with a fixed response and recording what is written to it.
+///
+use core::convert::Infallible;
+use embedded_io::ErrorType;
+use embedded_io_async::{Read, Write};
+use std::vec::Vec;
+
+use crate::TryBufRead;
+
+pub(crate) struct FakeConnection {
+    /// The part of the response that has not been read yet
+    pub(crate) response: &'static [u8],
+    /// Everything written to the connection
+    pub(crate) request: Vec<u8>,
+}
+
+impl FakeConnection {
+    pub(crate) fn new(response: &'static [u8]) -> Self {
+        Self {
+            response,
+            request: Vec::new(),
+        }
+    }
+}
+
+impl ErrorType for FakeConnection {
+    type Error = Infallible;
+}
+
+impl Read for FakeConnection {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.response.read(buf).await
+    }
+}
+
+impl TryBufRead for FakeConnection {}
+
+impl Write for FakeConnection {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.request.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
@@ -0,0 +1,195 @@
+/// Writing response bodies to a NOR flash region using `embedded-storage-async` as they are received, so firmware
+/// images and assets can be persisted without buffering them in RAM.
+///
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+use embedded_storage_async::nor_flash::NorFlash;
+
+use crate::Error;
+
+/// A writer of a region of NOR flash, which erases the sectors of the region as they are reached.
+///
+/// Bytes are buffered in pages of `N` bytes, which must be a multiple of the write size of the flash, and the
+/// remaining bytes are written by [`FlashWriter::finish()`], padded with `0xFF` to the write size. Writing beyond
+/// the end of the region fails with [`Error::BodyTooLarge`], and flash errors are returned as [`Error::Storage`].
+///
+/// The body of a response is written using [`ResponseBody::copy_to()`](crate::response::ResponseBody::copy_to).
+pub struct FlashWriter<F, const N: usize = 256> {
+    flash: F,
+    start: u32,
+    end: u32,
+    /// The offset the buffered page is written to.
+    pos: u32,
+    /// The end of the erased part of the region.
+    erased: u32,
+    page: [u8; N],
+    len: usize,
+}
+
+impl<F, const N: usize> FlashWriter<F, N>
+where
+    F: NorFlash,
+{
+    /// Write the region of `flash` of `len` bytes starting at `offset`.
+    ///
+    /// Fails with [`Error::Storage`] if the region is not aligned to the erase size of the flash or exceeds its
+    /// capacity, or if `N` is not a multiple of the write size.
+    pub fn new(flash: F, offset: u32, len: u32) -> Result<Self, Error> {
+        let end = offset.checked_add(len).ok_or(Error::Storage)?;
+        let erase_size = F::ERASE_SIZE as u32;
+        if offset % erase_size != 0 || end % erase_size != 0 || end as usize > flash.capacity() {
+            return Err(Error::Storage);
+        }
+        if N == 0 || N % F::WRITE_SIZE != 0 {
+            return Err(Error::Storage);
+        }
+        Ok(Self {
+            flash,
+            start: offset,
+            end,
+            pos: offset,
+            erased: offset,
+            page: [0; N],
+            len: 0,
+        })
+    }
+
+    /// The number of bytes written so far, including the buffered bytes.
+    pub fn written(&self) -> u32 {
+        self.pos - self.start + self.len as u32
+    }
+
+    /// Write the buffered bytes, and return the flash.
+    pub async fn finish(mut self) -> Result<F, Error> {
+        if self.len > 0 {
+            let len = self.len.div_ceil(F::WRITE_SIZE) * F::WRITE_SIZE;
+            self.page[self.len..len].fill(0xFF);
+            self.write_page(len).await?;
+        }
+        Ok(self.flash)
+    }
+
+    async fn write_page(&mut self, len: usize) -> Result<(), Error> {
+        let end = self.pos + len as u32;
+        if end > self.erased {
+            let erase_size = F::ERASE_SIZE as u32;
+            let erase_end = end.div_ceil(erase_size) * erase_size;
+            trace!("Erasing flash from {} to {}", self.erased, erase_end);
+            self.flash
+                .erase(self.erased, erase_end)
+                .await
+                .map_err(|_| Error::Storage)?;
+            self.erased = erase_end;
+        }
+        self.flash
+            .write(self.pos, &self.page[..len])
+            .await
+            .map_err(|_| Error::Storage)?;
+        self.pos = end;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl<F, const N: usize> ErrorType for FlashWriter<F, N> {
+    type Error = Error;
+}
+
+impl<F, const N: usize> Write for FlashWriter<F, N>
+where
+    F: NorFlash,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let remaining = (self.end - self.pos) as usize - self.len;
+        if remaining == 0 && !buf.is_empty() {
+            warn!("Body exceeds the flash region");
+            return Err(Error::BodyTooLarge);
+        }
+        let len = buf.len().min(N - self.len).min(remaining);
+        self.page[self.len..self.len + len].copy_from_slice(&buf[..len]);
+        self.len += len;
+        if self.len == N {
+            self.write_page(N).await?;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_storage_async::nor_flash::{NorFlashErrorKind, ReadNorFlash};
+
+    use super::*;
+
+    /// A flash which checks that writes are aligned and only change erased bytes.
+    struct FakeFlash(Vec<u8>);
+
+    impl embedded_storage_async::nor_flash::ErrorType for FakeFlash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for FakeFlash {
+        const READ_SIZE: usize = 1;
+
+        async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl NorFlash for FakeFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 64;
+
+        async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            if from as usize % Self::ERASE_SIZE != 0 || to as usize % Self::ERASE_SIZE != 0 {
+                return Err(NorFlashErrorKind::NotAligned);
+            }
+            self.0[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            if offset % Self::WRITE_SIZE != 0 || bytes.len() % Self::WRITE_SIZE != 0 {
+                return Err(NorFlashErrorKind::NotAligned);
+            }
+            let target = &mut self.0[offset..offset + bytes.len()];
+            if target.iter().any(|&b| b != 0xFF) {
+                return Err(NorFlashErrorKind::Other);
+            }
+            target.copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn can_write_region() {
+        let data: Vec<u8> = (0..70).collect();
+        let mut writer: FlashWriter<_, 16> = FlashWriter::new(FakeFlash(vec![0; 256]), 64, 128).unwrap();
+        for part in data.chunks(7) {
+            writer.write_all(part).await.unwrap();
+        }
+        assert_eq!(70, writer.written());
+
+        let flash = writer.finish().await.unwrap().0;
+        assert_eq!(data.as_slice(), &flash[64..134]);
+        assert!(flash[134..192].iter().all(|&b| b == 0xFF));
+        assert!(flash[..64].iter().chain(&flash[192..]).all(|&b| b == 0));
+    }
+
+    #[tokio::test]
+    async fn rejects_body_larger_than_region() {
+        let mut writer: FlashWriter<_, 16> = FlashWriter::new(FakeFlash(vec![0; 256]), 0, 64).unwrap();
+        writer.write_all(&[1; 64]).await.unwrap();
+        assert!(matches!(writer.write(&[1]).await, Err(Error::BodyTooLarge)));
+
+        assert!(FlashWriter::<_, 16>::new(FakeFlash(vec![0; 256]), 32, 64).is_err());
+        assert!(FlashWriter::<_, 6>::new(FakeFlash(vec![0; 256]), 0, 64).is_err());
+    }
+}
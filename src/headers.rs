@@ -1,9 +1,15 @@
+use core::time::Duration;
+
+use crate::date::parse_http_date;
+use crate::request::Method;
+
 /// HTTP content types
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ContentType {
     TextHtml,
     TextPlain,
+    TextEventStream,
     ApplicationJson,
     ApplicationCbor,
     ApplicationOctetStream,
@@ -11,21 +17,49 @@ pub enum ContentType {
 
 impl<'a> From<&'a [u8]> for ContentType {
     fn from(from: &'a [u8]) -> ContentType {
-        match from {
-            b"application/json" => ContentType::ApplicationJson,
-            b"application/cbor" => ContentType::ApplicationCbor,
-            b"text/html" => ContentType::TextHtml,
-            b"text/plain" => ContentType::TextPlain,
-            _ => ContentType::ApplicationOctetStream,
-        }
+        // Parameters such as `charset` are ignored
+        let Ok(value) = core::str::from_utf8(from) else {
+            return ContentType::ApplicationOctetStream;
+        };
+        let media_type = value.split(';').next().unwrap_or_default().trim();
+        [
+            ContentType::ApplicationJson,
+            ContentType::ApplicationCbor,
+            ContentType::TextHtml,
+            ContentType::TextPlain,
+            ContentType::TextEventStream,
+        ]
+        .into_iter()
+        .find(|content_type| media_type.eq_ignore_ascii_case(content_type.as_str()))
+        .unwrap_or(ContentType::ApplicationOctetStream)
     }
 }
 
+/// Whether the `charset` parameter of a `Content-Type` header value is UTF-8 or a subset of it, which is assumed
+/// if there is no such parameter.
+pub(crate) fn is_utf8_charset(content_type: &[u8]) -> bool {
+    let Ok(value) = core::str::from_utf8(content_type) else {
+        return false;
+    };
+    value
+        .split(';')
+        .skip(1)
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map_or(true, |(_, charset)| {
+            let charset = charset.trim().trim_matches('"');
+            ["utf-8", "utf8", "us-ascii"]
+                .iter()
+                .any(|utf8| charset.eq_ignore_ascii_case(utf8))
+        })
+}
+
 impl ContentType {
     pub fn as_str(&self) -> &str {
         match self {
             ContentType::TextHtml => "text/html",
             ContentType::TextPlain => "text/plain",
+            ContentType::TextEventStream => "text/event-stream",
             ContentType::ApplicationJson => "application/json",
             ContentType::ApplicationCbor => "application/cbor",
             ContentType::ApplicationOctetStream => "application/octet-stream",
@@ -68,12 +102,57 @@ impl TransferEncoding {
     }
 }
 
+/// Content encoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ContentEncoding {
+    Identity,
+    Deflate,
+    Gzip,
+    Compress,
+    Brotli,
+    /// An unknown encoding, or several encodings applied one after the other, which cannot be decoded
+    Unsupported,
+}
+
+impl From<&[u8]> for ContentEncoding {
+    /// Parse a list of encodings, in which `identity` is ignored.
+    fn from(value: &[u8]) -> Self {
+        let Ok(value) = core::str::from_utf8(value) else {
+            return ContentEncoding::Unsupported;
+        };
+        value
+            .split(',')
+            .map(|encoding| match encoding.trim() {
+                e if e.eq_ignore_ascii_case("identity") => ContentEncoding::Identity,
+                e if e.eq_ignore_ascii_case("deflate") => ContentEncoding::Deflate,
+                e if e.eq_ignore_ascii_case("gzip") || e.eq_ignore_ascii_case("x-gzip") => ContentEncoding::Gzip,
+                e if e.eq_ignore_ascii_case("compress") || e.eq_ignore_ascii_case("x-compress") => {
+                    ContentEncoding::Compress
+                }
+                e if e.eq_ignore_ascii_case("br") => ContentEncoding::Brotli,
+                _ => ContentEncoding::Unsupported,
+            })
+            .fold(ContentEncoding::Identity, ContentEncoding::then)
+    }
+}
+
+impl ContentEncoding {
+    /// The encoding of a body that was encoded with `self` and then with `next`.
+    pub(crate) fn then(self, next: ContentEncoding) -> ContentEncoding {
+        match (self, next) {
+            (ContentEncoding::Identity, encoding) | (encoding, ContentEncoding::Identity) => encoding,
+            _ => ContentEncoding::Unsupported,
+        }
+    }
+}
+
 /// Keep-alive header
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct KeepAlive {
-    timeout: Option<u8>,
-    max: Option<u8>,
+    timeout: Option<u32>,
+    max: Option<u32>,
 }
 
 impl<'a> TryFrom<&'a [u8]> for KeepAlive {
@@ -97,3 +176,600 @@ impl<'a> TryFrom<&'a [u8]> for KeepAlive {
         Ok(keep_alive)
     }
 }
+
+impl KeepAlive {
+    /// The number of seconds the server will keep an idle connection open.
+    pub fn timeout(&self) -> Option<u32> {
+        self.timeout
+    }
+
+    /// The time the server will keep an idle connection open.
+    pub fn timeout_duration(&self) -> Option<Duration> {
+        self.timeout.map(|timeout| Duration::from_secs(timeout as u64))
+    }
+
+    /// The maximum number of requests the server will accept on the connection.
+    pub fn max(&self) -> Option<u32> {
+        self.max
+    }
+
+    /// The time until which an idle connection may be reused, given the time it became idle in seconds.
+    ///
+    /// Returns `None` if the server did not send a timeout.
+    pub fn reuse_deadline(&self, idle_since: u64) -> Option<u64> {
+        self.timeout.map(|timeout| idle_since.saturating_add(timeout as u64))
+    }
+}
+
+/// Connection header
+///
+/// Only the connection options relevant to the client are kept, other options are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Connection {
+    /// The server will close the connection after the response.
+    pub close: bool,
+    /// The server will keep the connection open after the response.
+    pub keep_alive: bool,
+    /// The server switches to the protocol of the `Upgrade` header.
+    pub upgrade: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for Connection {
+    type Error = core::str::Utf8Error;
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut connection = Connection::default();
+        for option in core::str::from_utf8(from)?.split(',') {
+            let option = option.trim();
+            match option {
+                _ if option.eq_ignore_ascii_case("close") => connection.close = true,
+                _ if option.eq_ignore_ascii_case("keep-alive") => connection.keep_alive = true,
+                _ if option.eq_ignore_ascii_case("upgrade") => connection.upgrade = true,
+                _ => (),
+            }
+        }
+        Ok(connection)
+    }
+}
+
+/// Cache-Control header
+///
+/// Only the response directives relevant to a private cache are kept, other directives are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CacheControl {
+    /// The number of seconds the response stays fresh.
+    pub max_age: Option<u64>,
+    /// The response must not be stored.
+    pub no_store: bool,
+    /// The response must be revalidated before every use.
+    pub no_cache: bool,
+    /// The response must be revalidated once it is stale.
+    pub must_revalidate: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for CacheControl {
+    type Error = core::str::Utf8Error;
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut cache_control = CacheControl::default();
+        for directive in core::str::from_utf8(from)?.split(',') {
+            let (key, value) = match directive.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match key {
+                _ if key.eq_ignore_ascii_case("max-age") => {
+                    // An invalid max-age makes the response stale
+                    cache_control.max_age = Some(value.and_then(|value| value.parse().ok()).unwrap_or(0))
+                }
+                _ if key.eq_ignore_ascii_case("no-store") => cache_control.no_store = true,
+                _ if key.eq_ignore_ascii_case("no-cache") => cache_control.no_cache = true,
+                _ if key.eq_ignore_ascii_case("must-revalidate") => cache_control.must_revalidate = true,
+                _ => (),
+            }
+        }
+        Ok(cache_control)
+    }
+}
+
+/// Retry-After header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RetryAfter {
+    /// The number of seconds to wait.
+    Delay(u64),
+    /// The time after which to retry, as seconds since the Unix epoch.
+    Date(u64),
+}
+
+impl<'a> TryFrom<&'a [u8]> for RetryAfter {
+    type Error = ();
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        if !from.is_empty() && from.iter().all(|b| b.is_ascii_digit()) {
+            let delay = core::str::from_utf8(from).map_err(|_| ())?;
+            return delay.parse().map(RetryAfter::Delay).map_err(|_| ());
+        }
+        parse_http_date(from).map(RetryAfter::Date).map_err(|_| ())
+    }
+}
+
+impl RetryAfter {
+    /// The number of seconds to wait, given the current time as seconds since the Unix epoch.
+    pub fn delay_secs(&self, now: u64) -> u64 {
+        match self {
+            RetryAfter::Delay(delay) => *delay,
+            RetryAfter::Date(date) => date.saturating_sub(now),
+        }
+    }
+}
+
+/// Content-Range header of a `206 Partial Content` response, such as `bytes 100-199/200`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ContentRange<'a> {
+    /// The unit of the range, which is `bytes` unless the server supports other units.
+    pub unit: &'a str,
+    /// The position of the first unit of the body in the resource.
+    pub start: u64,
+    /// The position of the last unit of the body in the resource, which is inclusive.
+    pub end: u64,
+    /// The total length of the resource, unless the server does not know it.
+    pub complete_length: Option<u64>,
+}
+
+impl<'a> TryFrom<&'a [u8]> for ContentRange<'a> {
+    type Error = ();
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        let from = core::str::from_utf8(from).map_err(|_| ())?;
+        let (unit, range) = from.trim().split_once(' ').ok_or(())?;
+        if unit.is_empty() {
+            return Err(());
+        }
+        let (range, complete_length) = range.trim().split_once('/').ok_or(())?;
+        let (start, end) = range.split_once('-').ok_or(())?;
+        let start: u64 = start.parse().map_err(|_| ())?;
+        let end: u64 = end.parse().map_err(|_| ())?;
+        let complete_length = match complete_length {
+            "*" => None,
+            complete_length => Some(complete_length.parse::<u64>().map_err(|_| ())?),
+        };
+        if end < start || complete_length.is_some_and(|complete_length| end >= complete_length) {
+            return Err(());
+        }
+        Ok(ContentRange {
+            unit,
+            start,
+            end,
+            complete_length,
+        })
+    }
+}
+
+impl ContentRange<'_> {
+    /// Whether the unit of the range is `bytes`.
+    pub fn is_bytes(&self) -> bool {
+        self.unit.eq_ignore_ascii_case("bytes")
+    }
+
+    /// The number of units in the range, which is the length of the body if the unit is `bytes`.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+/// Whether an `Accept-Ranges` header value advertises support for byte ranges.
+pub(crate) fn accepts_byte_ranges(value: &[u8]) -> bool {
+    core::str::from_utf8(value)
+        .is_ok_and(|value| value.split(',').any(|unit| unit.trim().eq_ignore_ascii_case("bytes")))
+}
+
+/// Allow header, listing the methods supported by a resource
+///
+/// Only the methods of [`Method`] are kept, other methods are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Allow {
+    methods: u8,
+}
+
+impl<'a> TryFrom<&'a [u8]> for Allow {
+    type Error = core::str::Utf8Error;
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut allow = Allow::default();
+        for name in core::str::from_utf8(from)?.split(',') {
+            // Method names are case-sensitive
+            let name = name.trim();
+            if let Some(method) = METHODS.iter().find(|method| method.as_str() == name) {
+                allow.methods |= 1 << *method as u8;
+            }
+        }
+        Ok(allow)
+    }
+}
+
+impl Allow {
+    /// Whether the resource supports `method`.
+    pub fn contains(&self, method: Method) -> bool {
+        self.methods & (1 << method as u8) != 0
+    }
+
+    /// The methods supported by the resource.
+    pub fn methods(&self) -> impl Iterator<Item = Method> + '_ {
+        METHODS.into_iter().filter(|method| self.contains(*method))
+    }
+}
+
+const METHODS: [Method; 6] = [
+    Method::GET,
+    Method::PUT,
+    Method::POST,
+    Method::DELETE,
+    Method::HEAD,
+    Method::OPTIONS,
+];
+
+/// Set-Cookie header
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetCookie<'a> {
+    /// The cookie name.
+    pub name: &'a str,
+    /// The cookie value, without any surrounding double quotes.
+    pub value: &'a str,
+    /// The `Path` attribute.
+    pub path: Option<&'a str>,
+    /// The `Domain` attribute, without any leading dot.
+    pub domain: Option<&'a str>,
+    /// The `Max-Age` attribute in seconds. Zero or negative values expire the cookie immediately.
+    pub max_age: Option<i64>,
+    /// The `Secure` attribute.
+    pub secure: bool,
+    /// The `HttpOnly` attribute.
+    pub http_only: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetCookie<'a> {
+    type Error = ();
+
+    fn try_from(from: &'a [u8]) -> Result<Self, Self::Error> {
+        let from = core::str::from_utf8(from).map_err(|_| ())?;
+        let mut parts = from.split(';');
+
+        let (name, value) = parts.next().and_then(|pair| pair.split_once('=')).ok_or(())?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(());
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+
+        let mut cookie = SetCookie {
+            name,
+            value,
+            path: None,
+            domain: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+        };
+
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (attribute.trim(), None),
+            };
+            match (key, value) {
+                _ if key.eq_ignore_ascii_case("secure") => cookie.secure = true,
+                _ if key.eq_ignore_ascii_case("httponly") => cookie.http_only = true,
+                (_, Some(value)) if key.eq_ignore_ascii_case("path") && value.starts_with('/') => {
+                    cookie.path = Some(value)
+                }
+                (_, Some(value)) if key.eq_ignore_ascii_case("domain") && !value.is_empty() => {
+                    cookie.domain = Some(value.trim_start_matches('.'))
+                }
+                (_, Some(value)) if key.eq_ignore_ascii_case("max-age") => {
+                    // Invalid values are ignored
+                    if let Ok(max_age) = value.parse() {
+                        cookie.max_age = Some(max_age);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(cookie)
+    }
+}
+
+/// The headers whose values are masked when requests and responses are formatted using `Debug` or `defmt`.
+pub const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// A request or response formatted using `Debug` or `defmt` with the values of sensitive headers masked.
+///
+/// Created with e.g. [`Request::redacted()`](crate::request::Request::redacted) to mask other headers than
+/// the [`SENSITIVE_HEADERS`].
+pub struct Redacted<'a, T> {
+    pub(crate) value: &'a T,
+    pub(crate) sensitive: &'a [&'a str],
+}
+
+impl<T> Redacted<'_, T> {
+    /// Get the value of a header as formatted, which is masked if the header is sensitive.
+    pub(crate) fn header<'v>(&self, name: &str, value: &'v [u8]) -> HeaderValue<'v> {
+        if self.sensitive.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+            HeaderValue(None)
+        } else {
+            HeaderValue(Some(value))
+        }
+    }
+}
+
+/// A header value as formatted, where `None` is a masked value.
+pub(crate) struct HeaderValue<'a>(Option<&'a [u8]>);
+
+impl HeaderValue<'_> {
+    pub(crate) const MASKED: Self = HeaderValue(None);
+}
+
+impl core::fmt::Debug for HeaderValue<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            None => f.write_str("***"),
+            Some(value) => match core::str::from_utf8(value) {
+                Ok(value) => value.fmt(f),
+                Err(_) => value.fmt(f),
+            },
+        }
+    }
+}
+
+/// Formats the headers returned by the function as a list.
+pub(crate) struct HeaderList<F>(pub(crate) F);
+
+impl<'v, F, I> core::fmt::Debug for HeaderList<F>
+where
+    F: Fn() -> I,
+    I: Iterator<Item = (&'v str, HeaderValue<'v>)>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.0()).finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<'v, F, I> defmt::Format for HeaderList<F>
+where
+    F: Fn() -> I,
+    I: Iterator<Item = (&'v str, HeaderValue<'v>)>,
+{
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "[");
+        for (i, (name, value)) in self.0().enumerate() {
+            if i > 0 {
+                defmt::write!(fmt, ", ");
+            }
+            defmt::write!(fmt, "({=str}, {})", name, value);
+        }
+        defmt::write!(fmt, "]");
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for HeaderValue<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self.0 {
+            None => defmt::write!(fmt, "***"),
+            Some(value) => defmt::write!(fmt, "{=[u8]:a}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_content_type() {
+        assert!(matches!(
+            ContentType::from(b"application/json".as_slice()),
+            ContentType::ApplicationJson
+        ));
+        assert!(matches!(
+            ContentType::from(b"Application/JSON; charset=utf-8".as_slice()),
+            ContentType::ApplicationJson
+        ));
+        assert!(matches!(
+            ContentType::from(b"image/png".as_slice()),
+            ContentType::ApplicationOctetStream
+        ));
+    }
+
+    #[test]
+    fn can_check_charset() {
+        assert!(is_utf8_charset(b"text/plain"));
+        assert!(is_utf8_charset(b"text/plain; charset=UTF-8"));
+        assert!(is_utf8_charset(b"text/html;format=flowed; charset=\"us-ascii\""));
+        assert!(!is_utf8_charset(b"text/plain; charset=iso-8859-1"));
+    }
+
+    #[test]
+    fn can_parse_cache_control() {
+        assert_eq!(
+            Ok(CacheControl {
+                max_age: Some(3600),
+                no_store: false,
+                no_cache: true,
+                must_revalidate: true,
+            }),
+            CacheControl::try_from(b"Max-Age=3600, no-cache, must-revalidate, private".as_slice())
+        );
+        assert_eq!(
+            Ok(CacheControl {
+                max_age: Some(60),
+                no_store: true,
+                ..Default::default()
+            }),
+            CacheControl::try_from(b"max-age=\"60\",no-store".as_slice())
+        );
+        assert_eq!(
+            Some(0),
+            CacheControl::try_from(b"max-age=-1".as_slice()).unwrap().max_age
+        );
+    }
+
+    #[test]
+    fn can_parse_keep_alive() {
+        let keep_alive = KeepAlive::try_from(b"timeout=300, max=1000".as_slice()).unwrap();
+
+        assert_eq!(Some(300), keep_alive.timeout());
+        assert_eq!(Some(1000), keep_alive.max());
+        assert_eq!(Some(Duration::from_secs(300)), keep_alive.timeout_duration());
+        assert_eq!(Some(1300), keep_alive.reuse_deadline(1000));
+
+        let keep_alive = KeepAlive::try_from(b"max=5".as_slice()).unwrap();
+
+        assert_eq!(None, keep_alive.timeout());
+        assert_eq!(None, keep_alive.reuse_deadline(1000));
+    }
+
+    #[test]
+    fn can_parse_connection() {
+        assert_eq!(
+            Ok(Connection {
+                close: true,
+                ..Default::default()
+            }),
+            Connection::try_from(b"Close".as_slice())
+        );
+        assert_eq!(
+            Ok(Connection {
+                keep_alive: true,
+                upgrade: true,
+                ..Default::default()
+            }),
+            Connection::try_from(b"keep-alive, TE,Upgrade".as_slice())
+        );
+        assert_eq!(Ok(Connection::default()), Connection::try_from(b"".as_slice()));
+    }
+
+    #[test]
+    fn can_parse_retry_after() {
+        assert_eq!(Ok(RetryAfter::Delay(120)), RetryAfter::try_from(b"120".as_slice()));
+        assert_eq!(
+            Ok(RetryAfter::Date(784111777)),
+            RetryAfter::try_from(b"Sun, 06 Nov 1994 08:49:37 GMT".as_slice())
+        );
+        assert_eq!(Err(()), RetryAfter::try_from(b"-1".as_slice()));
+        assert_eq!(Err(()), RetryAfter::try_from(b"".as_slice()));
+
+        assert_eq!(120, RetryAfter::Delay(120).delay_secs(1000));
+        assert_eq!(30, RetryAfter::Date(1030).delay_secs(1000));
+        assert_eq!(0, RetryAfter::Date(970).delay_secs(1000));
+    }
+
+    #[test]
+    fn can_parse_content_range() {
+        assert_eq!(
+            Ok(ContentRange {
+                unit: "bytes",
+                start: 100,
+                end: 199,
+                complete_length: Some(200)
+            }),
+            ContentRange::try_from(b"bytes 100-199/200".as_slice())
+        );
+        assert_eq!(
+            Ok(ContentRange {
+                unit: "bytes",
+                start: 0,
+                end: 0,
+                complete_length: None
+            }),
+            ContentRange::try_from(b"bytes 0-0/*".as_slice())
+        );
+        assert_eq!(Err(()), ContentRange::try_from(b"bytes */200".as_slice()));
+        assert_eq!(Err(()), ContentRange::try_from(b"bytes 100-99/200".as_slice()));
+        assert_eq!(Err(()), ContentRange::try_from(b"bytes 100-200/200".as_slice()));
+        assert_eq!(Err(()), ContentRange::try_from(b"0-9/10".as_slice()));
+
+        let range = ContentRange::try_from(b"items 0-9/10".as_slice()).unwrap();
+        assert!(!range.is_bytes());
+        assert_eq!(10, range.length());
+
+        assert!(accepts_byte_ranges(b"bytes"));
+        assert!(!accepts_byte_ranges(b"none"));
+    }
+
+    #[test]
+    fn can_parse_allow() {
+        let allow = Allow::try_from(b"GET, HEAD,OPTIONS, PATCH, post".as_slice()).unwrap();
+        assert!(allow.contains(Method::GET));
+        assert!(!allow.contains(Method::POST));
+        assert_eq!(
+            [Method::GET, Method::HEAD, Method::OPTIONS].as_slice(),
+            allow.methods().collect::<Vec<_>>().as_slice()
+        );
+        assert_eq!(Ok(Allow::default()), Allow::try_from(b"".as_slice()));
+    }
+
+    #[test]
+    fn can_parse_set_cookie() {
+        let cookie = SetCookie::try_from(
+            b"session=abc123; Path=/; Domain=.example.com; Max-Age=3600; Secure; HttpOnly".as_slice(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            SetCookie {
+                name: "session",
+                value: "abc123",
+                path: Some("/"),
+                domain: Some("example.com"),
+                max_age: Some(3600),
+                secure: true,
+                http_only: true,
+            },
+            cookie
+        );
+    }
+
+    #[test]
+    fn can_parse_set_cookie_without_attributes() {
+        let cookie = SetCookie::try_from(b"id=\"quoted\"".as_slice()).unwrap();
+
+        assert_eq!("id", cookie.name);
+        assert_eq!("quoted", cookie.value);
+        assert_eq!(None, cookie.path);
+        assert_eq!(None, cookie.max_age);
+        assert!(!cookie.secure);
+        assert!(!cookie.http_only);
+    }
+
+    #[test]
+    fn ignores_unknown_and_invalid_attributes() {
+        let cookie = SetCookie::try_from(b"id=1; max-age=soon; SameSite=Lax; path=relative".as_slice()).unwrap();
+
+        assert_eq!(None, cookie.max_age);
+        assert_eq!(None, cookie.path);
+    }
+
+    #[test]
+    fn cannot_parse_set_cookie_without_name() {
+        assert!(SetCookie::try_from(b"=value".as_slice()).is_err());
+        assert!(SetCookie::try_from(b"novalue".as_slice()).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+/// Verifying the integrity of response bodies against the `sha-256` or `md5` digest of the `Content-Digest` header
+/// (RFC 9530), or the MD5 digest of the `Content-MD5` header, so corrupted transfers are detected.
+///
+/// The digests are computed over the body as received, i.e. with any `Content-Encoding` still applied. MD5 only
+/// detects corruption, and does not protect against a malicious server.
+///
+/// Verification is opt-in: if a response has neither header, or only lists digests of unsupported algorithms, there
+/// is no digest to verify against and the body is not checked.
+use base64::engine::{general_purpose, Engine as _};
+use embedded_io::ErrorType;
+use embedded_io_async::Read;
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+
+use crate::Error;
+
+/// The digest of a body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Digest {
+    /// The SHA-256 digest
+    Sha256([u8; 32]),
+    /// The MD5 digest
+    Md5([u8; 16]),
+}
+
+impl Digest {
+    /// Verify that the digest of `body` matches, returning [`Error::DigestMismatch`] otherwise.
+    pub fn verify(&self, body: &[u8]) -> Result<(), Error> {
+        let mut hasher = Hasher::new(self);
+        hasher.update(body);
+        if !hasher.matches(self) {
+            warn!("Body does not match its digest");
+            return Err(Error::DigestMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+enum Hasher {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl Hasher {
+    fn new(digest: &Digest) -> Self {
+        match digest {
+            Digest::Sha256(_) => Hasher::Sha256(Sha256::new()),
+            Digest::Md5(_) => Hasher::Md5(Md5::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(sha256) => sha256.update(data),
+            Hasher::Md5(md5) => md5.update(data),
+        }
+    }
+
+    fn matches(self, digest: &Digest) -> bool {
+        match (self, digest) {
+            (Hasher::Sha256(sha256), Digest::Sha256(expected)) => sha256.finalize()[..] == expected[..],
+            (Hasher::Md5(md5), Digest::Md5(expected)) => md5.finalize()[..] == expected[..],
+            _ => false,
+        }
+    }
+}
+
+/// Decode a base64 encoded digest of `N` bytes.
+fn decode<const N: usize>(value: &[u8]) -> Option<[u8; N]> {
+    // The decoded length is overestimated for the padding
+    let mut digest = [0; 34];
+    let len = general_purpose::STANDARD.decode_slice(value, &mut digest).ok()?;
+    digest[..len].try_into().ok()
+}
+
+/// Parse a `Content-MD5` header value, which is the base64 encoded digest.
+pub(crate) fn parse_content_md5(value: &[u8]) -> Option<Digest> {
+    decode(value).map(Digest::Md5)
+}
+
+/// Parse a `Content-Digest` header value, such as `sha-256=:...:, md5=:...:`, preferring the `sha-256` digest over
+/// the `md5` digest. Digests of other algorithms are ignored.
+pub(crate) fn parse_content_digest(value: &[u8]) -> Option<Digest> {
+    let value = core::str::from_utf8(value).ok()?;
+    let member = |algorithm: &str| {
+        value
+            .split(',')
+            .filter_map(|member| member.split_once('='))
+            .find(|(name, _)| name.trim() == algorithm)
+            .and_then(|(_, digest)| digest.trim().strip_prefix(':')?.strip_suffix(':'))
+    };
+    member("sha-256")
+        .and_then(|digest| decode(digest.as_bytes()))
+        .map(Digest::Sha256)
+        .or_else(|| {
+            member("md5")
+                .and_then(|digest| decode(digest.as_bytes()))
+                .map(Digest::Md5)
+        })
+}
+
+/// A reader of a body verifying its digest, e.g. the reader of
+/// [`ResponseBody::reader()`](crate::response::ResponseBody::reader) with the digest of
+/// [`Response::content_digest()`](crate::response::Response::content_digest).
+///
+/// Reaching the end of the body fails with [`Error::DigestMismatch`] if the digest does not match, so the body must
+/// be read until the end before it is used.
+pub struct DigestReader<R> {
+    reader: R,
+    hasher: Hasher,
+    expected: Digest,
+}
+
+impl<R> DigestReader<R>
+where
+    R: Read<Error = Error>,
+{
+    /// Verify the body read from `reader` against the `expected` digest.
+    pub fn new(reader: R, expected: Digest) -> Self {
+        Self {
+            reader,
+            hasher: Hasher::new(&expected),
+            expected,
+        }
+    }
+}
+
+impl<R> ErrorType for DigestReader<R> {
+    type Error = Error;
+}
+
+impl<R> Read for DigestReader<R>
+where
+    R: Read<Error = Error>,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let read = self.reader.read(buf).await?;
+        if read == 0 && !buf.is_empty() && !self.hasher.clone().matches(&self.expected) {
+            warn!("Body does not match its digest");
+            return Err(Error::DigestMismatch);
+        }
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The digests of `HELLO WORLD`.
+    const MD5: &[u8] = b"Nh+t8ccS6BLRmMTKtXEqeQ==";
+    const SHA256: &[u8] = b"eH7Hbcr9IMGQjrCTahL5Ht0QWrXNfswrGuIDJkg0Xf8=";
+
+    #[test]
+    fn can_parse_digest_headers() {
+        let md5 = parse_content_md5(MD5).unwrap();
+        md5.verify(b"HELLO WORLD").unwrap();
+        assert!(matches!(md5.verify(b"HELLO"), Err(Error::DigestMismatch)));
+
+        let sha256 = parse_content_digest(
+            b"md5=:Nh+t8ccS6BLRmMTKtXEqeQ==:, sha-256=:eH7Hbcr9IMGQjrCTahL5Ht0QWrXNfswrGuIDJkg0Xf8=:",
+        )
+        .unwrap();
+        assert!(matches!(sha256, Digest::Sha256(_)));
+        sha256.verify(b"HELLO WORLD").unwrap();
+        assert!(matches!(sha256.verify(b"HELLO"), Err(Error::DigestMismatch)));
+
+        assert_eq!(
+            Some(md5),
+            parse_content_digest(b"sha-512=:AAAA:, md5=:Nh+t8ccS6BLRmMTKtXEqeQ==:")
+        );
+        assert_eq!(None, parse_content_digest(b"sha-512=:AAAA:"));
+        assert_eq!(None, parse_content_digest(b"sha-256=:Nh+t8ccS6BLRmMTKtXEqeQ==:"));
+        assert_eq!(None, parse_content_md5(SHA256));
+        assert_eq!(None, parse_content_md5(b"AAAA"));
+    }
+
+    #[tokio::test]
+    async fn verifies_body_at_end() {
+        let mut buf = [0; 16];
+        for expected in [parse_content_md5(MD5).unwrap(), Digest::Sha256(decode(SHA256).unwrap())] {
+            let mut reader = DigestReader::new(Body(b"HELLO WORLD"), expected);
+            assert_eq!(11, reader.read(&mut buf).await.unwrap());
+            assert_eq!(0, reader.read(&mut buf).await.unwrap());
+
+            let mut reader = DigestReader::new(Body(b"HELLO W0RLD"), expected);
+            assert_eq!(11, reader.read(&mut buf).await.unwrap());
+            assert!(matches!(reader.read(&mut buf).await, Err(Error::DigestMismatch)));
+        }
+    }
+
+    struct Body(&'static [u8]);
+
+    impl ErrorType for Body {
+        type Error = Error;
+    }
+
+    impl Read for Body {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            let len = buf.len().min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+}
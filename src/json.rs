@@ -0,0 +1,81 @@
+/// Decoding JSON response bodies using `serde-json-core`, for the common case of fetching a JSON document.
+///
+use embedded_nal_async::{Dns, TcpConnect};
+use serde::Deserialize;
+
+use crate::client::HttpClient;
+use crate::headers::ContentType;
+use crate::request::{Method, RequestBuilder};
+use crate::transport::ConnectionDecorator;
+use crate::Error;
+
+impl<'a, T, D, W> HttpClient<'a, T, D, W>
+where
+    T: TcpConnect + 'a,
+    D: Dns + 'a,
+{
+    /// Send a `GET` request to `url` accepting JSON, and deserialize the response body into a `V`.
+    ///
+    /// The body is read into `rx_buf`, which must be large enough to contain both the response headers and
+    /// the body, and which borrowed fields of the value refer to. A response that is not successful or not
+    /// JSON fails with [`Error::UnexpectedResponse`], and a body that cannot be deserialized with
+    /// [`Error::Codec`].
+    pub async fn fetch_json<'conn, 'buf, V>(
+        &'conn mut self,
+        url: &'conn str,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<V, Error>
+    where
+        W: ConnectionDecorator<T::Connection<'conn>>,
+        V: Deserialize<'buf>,
+    {
+        let mut request = self
+            .request(Method::GET, url)
+            .await?
+            .accept(ContentType::ApplicationJson);
+        let mut response = request.send(rx_buf).await?;
+        let content_type = response.content_type.take();
+        if !response.status.is_successful() || !matches!(content_type, Some(ContentType::ApplicationJson)) {
+            warn!("Unexpected response to JSON request");
+            return Err(Error::UnexpectedResponse {
+                status: response.status,
+                content_type,
+            });
+        }
+        let body = response.body().read_to_end().await?;
+        from_slice(body)
+    }
+}
+
+/// Deserialize a JSON value from `body`, failing with [`Error::Codec`] if it is invalid.
+pub fn from_slice<'a, V>(body: &'a [u8]) -> Result<V, Error>
+where
+    V: Deserialize<'a>,
+{
+    let (value, _) = serde_json_core::from_slice(body).map_err(|_| Error::Codec)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Status<'a> {
+        name: &'a str,
+        uptime: u32,
+    }
+
+    #[test]
+    fn can_deserialize_borrowed_fields() {
+        let body = br#"{"name":"device","uptime":42}"#;
+        assert_eq!(
+            Status {
+                name: "device",
+                uptime: 42
+            },
+            from_slice(body).unwrap()
+        );
+        assert!(matches!(from_slice::<Status>(b"{\"name\":1}"), Err(Error::Codec)));
+    }
+}
@@ -5,14 +5,71 @@ use core::{num::ParseIntError, str::Utf8Error};
 
 use embedded_io_async::ReadExactError;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 mod fmt;
 
+pub mod auth;
 mod body_writer;
+pub mod breaker;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod cancel;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "cert-storage")]
+pub mod certificates;
 pub mod client;
+pub mod clock;
+pub mod crc32;
+pub mod date;
+pub mod duplex;
+#[cfg(feature = "embassy-net")]
+pub mod embassy;
+#[cfg(test)]
+mod fake;
+#[cfg(feature = "flash")]
+pub mod flash;
 pub mod headers;
+#[cfg(feature = "integrity")]
+pub mod integrity;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod metrics;
+pub mod ota;
+#[cfg(feature = "std")]
+pub mod pinning;
+pub mod pipeline;
+pub mod pool;
+pub mod progress;
+pub mod proxy;
 mod reader;
 pub mod request;
 pub mod response;
+pub mod resume;
+pub mod retry;
+pub mod segmented;
+pub mod signing;
+pub mod sse;
+#[cfg(feature = "std")]
+pub mod std_tls;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod tap;
+pub mod throttle;
+pub mod timeout;
+pub mod tls;
+#[cfg(feature = "tokio")]
+pub mod tokio_net;
+pub mod trace;
+pub mod transport;
+pub mod url;
+pub mod validator;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 /// Errors that can be returned by this library.
 #[derive(Debug)]
@@ -29,9 +86,9 @@ pub enum Error {
     /// Tls Error
     #[cfg(feature = "embedded-tls")]
     Tls(embedded_tls::TlsError),
-    /// Tls Error
+    /// Tls Error from esp-mbedtls
     #[cfg(feature = "esp-mbedtls")]
-    Tls(esp_mbedtls::TlsError),
+    MbedTls(esp_mbedtls::TlsError),
     /// The provided buffer is too small
     BufferTooSmall,
     /// The request is already sent
@@ -39,7 +96,43 @@ pub enum Error {
     /// An invalid number of bytes were written to request body
     IncorrectBodyWritten,
     /// The underlying connection was closed while being used
+    ///
+    /// The number of body bytes received before is available from
+    /// [`BodyReader::consumed()`](crate::response::BodyReader::consumed).
     ConnectionAborted,
+    /// The response has an invalid, conflicting or too large `Content-Length`
+    InvalidContentLength,
+    /// The response body exceeds the configured maximum size
+    BodyTooLarge,
+    /// The response header section exceeds the configured size or number of headers
+    HeadersTooLarge,
+    /// An error reading from storage
+    Storage,
+    /// The proxy responded to the request to open a tunnel with a status other than 2xx
+    ProxyRejected(response::StatusCode),
+    /// The circuit of the host is open after too many consecutive failures
+    CircuitOpen,
+    /// The server responded to a request to upgrade the connection with a status other than `101 Switching Protocols`
+    UpgradeRejected(response::StatusCode),
+    /// The request body cannot be rewound to write it again
+    BodyNotRewindable,
+    /// The response has a status or content type other than expected
+    UnexpectedResponse {
+        status: response::StatusCode,
+        content_type: Option<headers::ContentType>,
+    },
+    /// The charset of the response is not supported
+    UnsupportedCharset,
+    /// The digest of the received content does not match the expected digest, so it is corrupted
+    DigestMismatch,
+    /// The response body ended before the requested number of bytes were read
+    UnexpectedEndOfBody,
+    /// No TLS configuration, or no pre-shared key for the host, is available to establish a connection to a `https` url
+    NoTlsConfig,
+    /// The time source is unable to tell the current time, so the validity of certificates cannot be verified
+    UnknownTime,
+    /// The resource changed while it was downloaded in parts, so the parts received before do not belong to it
+    ResourceChanged,
 }
 
 impl embedded_io::Error for Error {
@@ -81,7 +174,7 @@ pub use esp_mbedtls::{Certificates, TlsVersion, X509, TlsReference};
 #[cfg(feature = "esp-mbedtls")]
 impl From<esp_mbedtls::TlsError> for Error {
     fn from(e: esp_mbedtls::TlsError) -> Error {
-        Error::Tls(e)
+        Error::MbedTls(e)
     }
 }
 
@@ -112,6 +205,8 @@ pub trait TryBufRead: embedded_io_async::Read {
     fn try_consume(&mut self, _amt: usize) {}
 }
 
+impl TryBufRead for &[u8] {}
+
 impl<C> TryBufRead for crate::client::HttpConnection<'_, C>
 where
     C: embedded_io_async::Read + embedded_io_async::Write,
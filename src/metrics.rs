@@ -0,0 +1,270 @@
+/// Metrics of the requests made by a client, for reporting the HTTP health of a device in its telemetry.
+///
+use core::cell::Cell;
+use core::future::Future;
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::transport::ConnectionDecorator;
+use crate::Error;
+
+/// An event recorded by the client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// A request is sent.
+    Request,
+    /// Bytes were written to a connection, including any TLS framing.
+    BytesWritten(usize),
+    /// Bytes were read from a connection, including any TLS framing.
+    BytesRead(usize),
+    /// A TLS handshake completed.
+    TlsHandshake,
+    /// Connecting or sending a request failed.
+    Error(ErrorClass),
+}
+
+/// The class of an error, as reported in [`Event::Error`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorClass {
+    /// Resolving the host failed.
+    Dns,
+    /// An operation on the connection timed out.
+    Timeout,
+    /// Any other error on the connection, e.g. when it is reset or closed.
+    Network,
+    /// The TLS handshake or record layer failed.
+    Tls,
+    /// The response was malformed or exceeded a limit.
+    Protocol,
+    /// Any other error, such as an invalid url or a too small buffer.
+    Other,
+}
+
+impl ErrorClass {
+    /// Classify the error.
+    pub fn of(error: &Error) -> Self {
+        match error {
+            Error::Dns => ErrorClass::Dns,
+            Error::Network(ErrorKind::TimedOut) => ErrorClass::Timeout,
+            Error::Network(_) | Error::ConnectionAborted => ErrorClass::Network,
+            #[cfg(feature = "embedded-tls")]
+            Error::Tls(_) => ErrorClass::Tls,
+            #[cfg(feature = "esp-mbedtls")]
+            Error::MbedTls(_) => ErrorClass::Tls,
+            Error::Codec | Error::InvalidContentLength | Error::HeadersTooLarge | Error::BodyTooLarge => {
+                ErrorClass::Protocol
+            }
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
+/// A recorder of the events of a client, such as [`Counters`] or a closure forwarding them to the telemetry.
+pub trait Recorder {
+    /// Record the event.
+    fn record(&self, event: Event);
+}
+
+impl<F> Recorder for F
+where
+    F: Fn(Event),
+{
+    fn record(&self, event: Event) {
+        self(event)
+    }
+}
+
+/// Record that a request is sent by `send`, and the outcome of sending it if it is an error.
+pub(crate) async fn record_request<T, F>(metrics: Option<&dyn Recorder>, send: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    if let Some(metrics) = metrics {
+        metrics.record(Event::Request);
+    }
+    record_error(metrics, send.await)
+}
+
+/// Record the outcome of `result` if it is an error.
+pub(crate) fn record_error<T>(metrics: Option<&dyn Recorder>, result: Result<T, Error>) -> Result<T, Error> {
+    if let (Some(metrics), Err(e)) = (metrics, &result) {
+        metrics.record(Event::Error(ErrorClass::of(e)));
+    }
+    result
+}
+
+/// The values of [`Counters`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Counts {
+    /// The number of requests sent.
+    pub requests: u32,
+    /// The number of bytes written to connections.
+    pub bytes_written: u64,
+    /// The number of bytes read from connections.
+    pub bytes_read: u64,
+    /// The number of completed TLS handshakes.
+    pub tls_handshakes: u32,
+    /// The number of errors of each [`ErrorClass`].
+    pub dns_errors: u32,
+    pub timeouts: u32,
+    pub network_errors: u32,
+    pub tls_errors: u32,
+    pub protocol_errors: u32,
+    pub other_errors: u32,
+}
+
+/// A [`Recorder`] that counts the events.
+///
+/// The counters are not synchronized, so they must be used by the clients of a single executor.
+#[derive(Debug, Default)]
+pub struct Counters(Cell<Counts>);
+
+impl Counters {
+    /// Create counters that start at zero.
+    pub const fn new() -> Self {
+        Self(Cell::new(Counts {
+            requests: 0,
+            bytes_written: 0,
+            bytes_read: 0,
+            tls_handshakes: 0,
+            dns_errors: 0,
+            timeouts: 0,
+            network_errors: 0,
+            tls_errors: 0,
+            protocol_errors: 0,
+            other_errors: 0,
+        }))
+    }
+
+    /// Get the current values of the counters.
+    pub fn counts(&self) -> Counts {
+        self.0.get()
+    }
+
+    /// Get the current values of the counters, and reset them to zero, e.g. after reporting them.
+    pub fn take(&self) -> Counts {
+        self.0.take()
+    }
+}
+
+impl Recorder for Counters {
+    fn record(&self, event: Event) {
+        let mut counts = self.0.get();
+        match event {
+            Event::Request => counts.requests = counts.requests.wrapping_add(1),
+            Event::BytesWritten(len) => counts.bytes_written = counts.bytes_written.wrapping_add(len as u64),
+            Event::BytesRead(len) => counts.bytes_read = counts.bytes_read.wrapping_add(len as u64),
+            Event::TlsHandshake => counts.tls_handshakes = counts.tls_handshakes.wrapping_add(1),
+            Event::Error(class) => {
+                let counter = match class {
+                    ErrorClass::Dns => &mut counts.dns_errors,
+                    ErrorClass::Timeout => &mut counts.timeouts,
+                    ErrorClass::Network => &mut counts.network_errors,
+                    ErrorClass::Tls => &mut counts.tls_errors,
+                    ErrorClass::Protocol => &mut counts.protocol_errors,
+                    ErrorClass::Other => &mut counts.other_errors,
+                };
+                *counter = counter.wrapping_add(1);
+            }
+        }
+        self.0.set(counts);
+    }
+}
+
+/// A [`ConnectionDecorator`] that records the bytes written to and read from connections.
+///
+/// Requests, TLS handshakes and errors are recorded by the client itself, see
+/// [`HttpClient::metrics()`](crate::client::HttpClient::metrics).
+#[derive(Clone, Copy)]
+pub struct Metered<'a>(pub &'a dyn Recorder);
+
+impl<'a, C> ConnectionDecorator<C> for Metered<'a>
+where
+    C: Read + Write,
+{
+    type Connection = MeteredConnection<'a, C>;
+
+    fn decorate(&self, conn: C) -> Self::Connection {
+        MeteredConnection { conn, metrics: self.0 }
+    }
+}
+
+/// A connection whose reads and writes are recorded.
+pub struct MeteredConnection<'a, C>
+where
+    C: Read + Write,
+{
+    conn: C,
+    metrics: &'a dyn Recorder,
+}
+
+impl<C> ErrorType for MeteredConnection<'_, C>
+where
+    C: Read + Write,
+{
+    type Error = ErrorKind;
+}
+
+impl<C> Read for MeteredConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.conn.read(buf).await.map_err(|e| e.kind())?;
+        self.metrics.record(Event::BytesRead(len));
+        Ok(len)
+    }
+}
+
+impl<C> Write for MeteredConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = self.conn.write(buf).await.map_err(|e| e.kind())?;
+        self.metrics.record(Event::BytesWritten(len));
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.conn.flush().await.map_err(|e| e.kind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeConnection;
+
+    #[tokio::test]
+    async fn counts_bytes_of_connection() {
+        let counters = Counters::new();
+        let mut conn = Metered(&counters).decorate(FakeConnection::new(b"HTTP/1.1 200 OK\r\n\r\n"));
+
+        conn.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut buf = [0; 64];
+        let len = conn.read(&mut buf).await.unwrap();
+
+        let counts = counters.take();
+        assert_eq!(18, counts.bytes_written);
+        assert_eq!(len as u64, counts.bytes_read);
+        assert_eq!(Counts::default(), counters.counts());
+    }
+
+    #[test]
+    fn counts_errors_by_class() {
+        let counters = Counters::new();
+        let recorder: &dyn Recorder = &counters;
+        record_error::<()>(Some(recorder), Err(Error::Network(ErrorKind::TimedOut))).ok();
+        record_error::<()>(Some(recorder), Err(Error::Codec)).ok();
+        record_error::<()>(Some(recorder), Err(Error::Dns)).ok();
+        record_error(Some(recorder), Ok(())).unwrap();
+
+        let counts = counters.counts();
+        assert_eq!((1, 1, 1), (counts.timeouts, counts.protocol_errors, counts.dns_errors));
+        assert_eq!(0, counts.network_errors);
+    }
+}
@@ -0,0 +1,326 @@
+/// Firmware over-the-air updates, downloading an image to storage using ranged requests, such that a download
+/// interrupted e.g. by a power loss is resumed where it stopped, and verifying its digest once it is complete.
+///
+use embedded_io_async::{BufRead, Read, Write};
+use heapless::String;
+
+use crate::client::HttpResource;
+use crate::Error;
+
+/// The maximum length of an `ETag`, longer tags are ignored.
+pub const MAX_ETAG_LEN: usize = 64;
+
+/// The storage an image is downloaded to, such as the inactive partition of a bootloader.
+///
+/// The storage must persist the `ETag` of the image along with the number of bytes written, so a download that
+/// was interrupted by a reset can be resumed rather than started over.
+pub trait ImageStorage {
+    /// Prepare to store the image of `len` bytes identified by `etag`, returning the number of bytes of the image
+    /// already written by an interrupted download, or 0 after preparing to write the image from the start.
+    async fn begin(&mut self, etag: Option<&str>, len: u64) -> Result<u64, Error>;
+
+    /// Write the next part of the image.
+    async fn write(&mut self, data: &[u8]) -> Result<(), Error>;
+
+    /// Read the part of the written image at `offset` into `buf`, to compute its digest.
+    async fn read(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), Error>;
+
+    /// Complete the download of the verified image, e.g. by marking it to be booted.
+    async fn finish(&mut self) -> Result<(), Error>;
+}
+
+/// The digest of an image, which is verified against the hash provided by the server, e.g. in an update manifest,
+/// using the crypto of the application.
+pub trait ImageDigest {
+    /// Update the digest with the next part of the image.
+    fn update(&mut self, data: &[u8]);
+
+    /// Whether the digest of the whole image matches the expected hash.
+    fn verify(&mut self) -> bool;
+}
+
+/// Download the image at `path` of `resource` to `storage`, resuming an interrupted download of the same image,
+/// and verify it using `digest`.
+///
+/// A `HEAD` request discovers the length and `ETag` of the image, after which the missing part is requested
+/// using a `Range` request, conditional on the `ETag` being unchanged. The `progress` is invoked with the number of
+/// bytes stored and the length of the image as the image is received. The responses are read into `rx_buf`,
+/// which is also used to read back the image to compute its digest.
+///
+/// Errors that occur while receiving the image leave the written part in storage, so the download is resumed by
+/// calling this again. If the image changed since the `HEAD` request, [`Error::ResourceChanged`] is returned without
+/// writing to storage, and calling this again downloads the new image. If the digest does not match,
+/// [`Error::DigestMismatch`] is returned, and the storage should be reset before retrying. Returns the length of the
+/// image.
+pub async fn download<C, S, D, F>(
+    resource: &mut HttpResource<'_, C>,
+    path: &str,
+    storage: &mut S,
+    digest: &mut D,
+    rx_buf: &mut [u8],
+    mut progress: F,
+) -> Result<u64, Error>
+where
+    C: Read + Write,
+    S: ImageStorage,
+    D: ImageDigest,
+    F: FnMut(u64, u64),
+{
+    if rx_buf.is_empty() {
+        return Err(Error::BufferTooSmall);
+    }
+
+    let (len, etag) = {
+        let response = resource.head(path).send(rx_buf).await?;
+        if !response.status.is_successful() {
+            warn!("Unexpected response to HEAD request of image");
+            return Err(Error::UnexpectedResponse {
+                status: response.status,
+                content_type: response.content_type,
+            });
+        }
+        let len = response.content_length.ok_or(Error::InvalidContentLength)?;
+        let etag: Option<String<MAX_ETAG_LEN>> = response
+            .header("etag")
+            .and_then(|etag| core::str::from_utf8(etag).ok())
+            .and_then(|etag| etag.try_into().ok());
+        (len, etag)
+    };
+
+    let mut offset = storage.begin(etag.as_deref(), len).await?;
+    if offset < len {
+        debug!("Downloading image from offset {} of {}", offset, len);
+        let mut reader = resource.resume(path, offset, etag.as_deref(), rx_buf).await?;
+        if reader.offset() != offset {
+            // The image changed since the HEAD request
+            warn!("Image changed during download");
+            return Err(Error::ResourceChanged);
+        }
+
+        loop {
+            let data = reader.fill_buf().await?;
+            if data.is_empty() {
+                break;
+            }
+            let received = data.len();
+            if offset + received as u64 > len {
+                warn!("Image exceeds its length");
+                return Err(Error::BodyTooLarge);
+            }
+            storage.write(data).await?;
+            offset += received as u64;
+            progress(offset, len);
+            reader.consume(received);
+        }
+        if offset < len {
+            return Err(Error::ConnectionAborted);
+        }
+    }
+
+    let mut pos = 0;
+    while pos < len {
+        let n = (len - pos).min(rx_buf.len() as u64) as usize;
+        storage.read(pos, &mut rx_buf[..n]).await?;
+        digest.update(&rx_buf[..n]);
+        pos += n as u64;
+    }
+    if !digest.verify() {
+        warn!("Image digest mismatch");
+        return Err(Error::DigestMismatch);
+    }
+    storage.finish().await?;
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+    use embedded_io::ErrorType;
+
+    use super::*;
+    use crate::client::HttpConnection;
+    use crate::fake::FakeConnection;
+    use crate::response::ResponseConfig;
+
+    /// A server answering each request with the next response.
+    struct FakeServer {
+        responses: std::vec::Vec<&'static [u8]>,
+        conn: FakeConnection,
+    }
+
+    impl ErrorType for FakeServer {
+        type Error = Infallible;
+    }
+
+    impl Read for FakeServer {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.conn.response.is_empty() && self.conn.request.ends_with(b"\r\n\r\n") {
+                self.conn.response = self.responses.remove(0);
+                self.conn.request.push(b'|');
+            }
+            self.conn.read(buf).await
+        }
+    }
+
+    impl Write for FakeServer {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.conn.write(buf).await
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeStorage {
+        etag: std::string::String,
+        image: std::vec::Vec<u8>,
+        finished: bool,
+    }
+
+    impl ImageStorage for FakeStorage {
+        async fn begin(&mut self, etag: Option<&str>, _len: u64) -> Result<u64, Error> {
+            if etag != Some(self.etag.as_str()) {
+                self.etag = etag.unwrap_or_default().into();
+                self.image.clear();
+            }
+            Ok(self.image.len() as u64)
+        }
+
+        async fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+            self.image.extend_from_slice(data);
+            Ok(())
+        }
+
+        async fn read(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), Error> {
+            let offset = offset as usize;
+            buf.copy_from_slice(&self.image[offset..offset + buf.len()]);
+            Ok(())
+        }
+
+        async fn finish(&mut self) -> Result<(), Error> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    /// A digest comparing the image itself.
+    struct FakeDigest {
+        expected: &'static [u8],
+        image: std::vec::Vec<u8>,
+    }
+
+    impl ImageDigest for FakeDigest {
+        fn update(&mut self, data: &[u8]) {
+            self.image.extend_from_slice(data);
+        }
+
+        fn verify(&mut self) -> bool {
+            self.image == self.expected
+        }
+    }
+
+    fn resource(responses: std::vec::Vec<&'static [u8]>) -> HttpResource<'static, FakeServer> {
+        HttpResource {
+            conn: HttpConnection::Plain(FakeServer {
+                responses,
+                conn: FakeConnection::new(&[]),
+            }),
+            host: "example.com",
+            base_path: "",
+            response_config: ResponseConfig::new(),
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn can_resume_download() {
+        let mut resource = resource(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v1\"\r\n\r\n",
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 6\r\n\r\n456789",
+        ]);
+        let mut storage = FakeStorage {
+            etag: "\"v1\"".into(),
+            image: b"0123".to_vec(),
+            finished: false,
+        };
+        let mut digest = FakeDigest {
+            expected: b"0123456789",
+            image: std::vec::Vec::new(),
+        };
+        let mut rx_buf = [0; 256];
+        let mut reported = 0;
+        let len = download(
+            &mut resource,
+            "/image.bin",
+            &mut storage,
+            &mut digest,
+            &mut rx_buf,
+            |offset, len| {
+                assert_eq!(10, len);
+                reported = offset;
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(10, len);
+        assert_eq!(10, reported);
+        assert!(storage.finished);
+        let HttpConnection::Plain(server) = &resource.conn else {
+            unreachable!()
+        };
+        let requests = std::str::from_utf8(&server.conn.request).unwrap();
+        assert!(requests.starts_with("HEAD /image.bin HTTP/1.1\r\n"));
+        assert!(requests.contains("|GET /image.bin HTTP/1.1\r\n"));
+        assert!(requests.contains("\r\nRange: bytes=4-\r\nIf-Range: \"v1\"\r\n"));
+    }
+
+    #[tokio::test]
+    async fn restarts_download_of_changed_image() {
+        let mut resource = resource(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nETag: \"v2\"\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nabcd",
+        ]);
+        let mut storage = FakeStorage {
+            etag: "\"v1\"".into(),
+            image: b"0123".to_vec(),
+            finished: false,
+        };
+        let mut digest = FakeDigest {
+            expected: b"abce",
+            image: std::vec::Vec::new(),
+        };
+        let mut rx_buf = [0; 256];
+        let result = download(&mut resource, "/", &mut storage, &mut digest, &mut rx_buf, |_, _| {}).await;
+
+        assert!(matches!(result, Err(Error::DigestMismatch)));
+        assert_eq!(b"abcd", storage.image.as_slice());
+        assert!(!storage.finished);
+    }
+
+    #[tokio::test]
+    async fn rejects_image_changed_after_head_request() {
+        let mut resource = resource(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v1\"\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nETag: \"v2\"\r\n\r\nabcd",
+        ]);
+        let mut storage = FakeStorage {
+            etag: "\"v1\"".into(),
+            image: b"0123".to_vec(),
+            finished: false,
+        };
+        let mut digest = FakeDigest {
+            expected: b"abcd",
+            image: std::vec::Vec::new(),
+        };
+        let mut rx_buf = [0; 256];
+        let result = download(&mut resource, "/", &mut storage, &mut digest, &mut rx_buf, |_, _| {}).await;
+
+        assert!(matches!(result, Err(Error::ResourceChanged)));
+        assert_eq!(b"0123", storage.image.as_slice());
+
+        let result = download(&mut resource, "/", &mut storage, &mut digest, &mut [], |_, _| {}).await;
+        assert!(matches!(result, Err(Error::BufferTooSmall)));
+    }
+}
@@ -0,0 +1,162 @@
+/// Pinning of server certificates, accepting a server only if the SHA-256 hash of its certificate or of its public
+/// key matches one of a configured set of pins, instead of verifying the certificate chain against a CA bundle.
+///
+/// Pinning the public key (the DER-encoded `SubjectPublicKeyInfo`) keeps working when the certificate is renewed
+/// with the same key, while pinning the certificate requires updating the pin with every renewal.
+///
+/// The pins are enforced by [`RustlsProvider::pinned()`](crate::std_tls::RustlsProvider::pinned), as the verifiers
+/// of embedded-tls cannot be configured per connection.
+use sha2::{Digest as _, Sha256};
+
+/// A pin of a server certificate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Pin {
+    /// The SHA-256 hash of the DER-encoded certificate
+    Certificate([u8; 32]),
+    /// The SHA-256 hash of the DER-encoded `SubjectPublicKeyInfo` of the certificate
+    PublicKey([u8; 32]),
+}
+
+impl Pin {
+    /// Create a pin of the provided DER-encoded certificate.
+    pub fn of_certificate(certificate: &[u8]) -> Self {
+        Pin::Certificate(sha256(certificate))
+    }
+
+    /// Create a pin of the public key of the provided DER-encoded certificate.
+    ///
+    /// [`Error::Codec`](crate::Error::Codec) is returned if the certificate is malformed.
+    pub fn of_public_key(certificate: &[u8]) -> Result<Self, crate::Error> {
+        let spki = subject_public_key_info(certificate).ok_or(crate::Error::Codec)?;
+        Ok(Pin::PublicKey(sha256(spki)))
+    }
+
+    /// Check whether the provided DER-encoded certificate matches this pin.
+    pub fn matches(&self, certificate: &[u8]) -> bool {
+        match self {
+            Pin::Certificate(hash) => sha256(certificate) == *hash,
+            Pin::PublicKey(hash) => subject_public_key_info(certificate).is_some_and(|spki| sha256(spki) == *hash),
+        }
+    }
+}
+
+/// Check whether the provided DER-encoded certificate matches any of the pins.
+pub fn is_pinned(pins: &[Pin], certificate: &[u8]) -> bool {
+    pins.iter().any(|pin| pin.matches(certificate))
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_VERSION: u8 = 0xA0;
+
+/// Find the encoded `subjectPublicKeyInfo` field of a DER-encoded X.509 certificate.
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let (tag, certificate, _) = next_element(certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+    let (tag, mut tbs_certificate, _) = next_element(certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+
+    // The version is optional, followed by the serial number, signature, issuer, validity and subject
+    if tbs_certificate.first() == Some(&TAG_VERSION) {
+        tbs_certificate = next_element(tbs_certificate)?.2;
+    }
+    for _ in 0..5 {
+        tbs_certificate = next_element(tbs_certificate)?.2;
+    }
+
+    let (tag, _, rest) = next_element(tbs_certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+    Some(&tbs_certificate[..tbs_certificate.len() - rest.len()])
+}
+
+/// Split the next DER element off `data`, returning its tag, its value and the remaining data.
+fn next_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&len, mut data) = data.split_first()?;
+    let len = if len & 0x80 == 0 {
+        len as usize
+    } else {
+        let octets = (len & 0x7F) as usize;
+        if octets == 0 || octets > 4 || data.len() < octets {
+            return None;
+        }
+        let (octets, rest) = data.split_at(octets);
+        data = rest;
+        octets.iter().fold(0, |len, &octet| (len << 8) | octet as usize)
+    };
+    if data.len() < len {
+        return None;
+    }
+    let (value, rest) = data.split_at(len);
+    Some((tag, value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::{general_purpose, Engine as _};
+
+    fn certificate(buf: &mut [u8]) -> &[u8] {
+        let pem: String = include_str!("../tests/certs/cert.pem")
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let len = general_purpose::STANDARD.decode_slice(pem, buf).unwrap();
+        &buf[..len]
+    }
+
+    // Computed with `openssl x509 -outform der | sha256sum`
+    const CERTIFICATE_HASH: [u8; 32] = [
+        0x55, 0x69, 0x8c, 0xfe, 0x0e, 0x41, 0x29, 0x71, 0xee, 0xae, 0xd1, 0x05, 0x6b, 0x0d, 0x61, 0x53, 0x01, 0x4f,
+        0x94, 0x81, 0xb5, 0xee, 0x33, 0xaf, 0xfc, 0x6a, 0x9f, 0x6c, 0x6a, 0xd1, 0xb7, 0x36,
+    ];
+
+    // Computed with `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | sha256sum`
+    const PUBLIC_KEY_HASH: [u8; 32] = [
+        0xa5, 0xf0, 0xa9, 0xd2, 0x6c, 0x3e, 0x06, 0x75, 0x91, 0x7a, 0x73, 0x8f, 0x3e, 0x4f, 0x9c, 0xab, 0x20, 0xcd,
+        0x6d, 0x88, 0x89, 0x67, 0xa6, 0xd4, 0x81, 0xb0, 0x14, 0xed, 0xe8, 0xc3, 0xe3, 0xf9,
+    ];
+
+    #[test]
+    fn can_match_pins() {
+        let mut buf = [0; 1024];
+        let certificate = certificate(&mut buf);
+
+        assert_eq!(Pin::Certificate(CERTIFICATE_HASH), Pin::of_certificate(certificate));
+        assert_eq!(
+            Pin::PublicKey(PUBLIC_KEY_HASH),
+            Pin::of_public_key(certificate).unwrap()
+        );
+        assert!(is_pinned(&[Pin::Certificate(CERTIFICATE_HASH)], certificate));
+        assert!(is_pinned(
+            &[Pin::Certificate([0; 32]), Pin::PublicKey(PUBLIC_KEY_HASH)],
+            certificate
+        ));
+        assert!(!is_pinned(
+            &[Pin::Certificate([0; 32]), Pin::PublicKey([0; 32])],
+            certificate
+        ));
+        assert!(!is_pinned(&[], certificate));
+    }
+
+    #[test]
+    fn rejects_malformed_certificate() {
+        let mut buf = [0; 1024];
+        let certificate = certificate(&mut buf);
+        let truncated = &certificate[..200];
+
+        assert!(Pin::of_public_key(truncated).is_err());
+        assert!(!Pin::PublicKey(PUBLIC_KEY_HASH).matches(truncated));
+        assert!(!Pin::PublicKey(PUBLIC_KEY_HASH).matches(&[]));
+    }
+}
@@ -0,0 +1,288 @@
+/// HTTP/1.1 request pipelining, writing multiple requests before reading their responses in order.
+///
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+use heapless::Deque;
+
+use crate::client::{HttpConnection, HttpResource};
+use crate::metrics::{record_error, record_request, Recorder};
+use crate::proxy::ForwardProxy;
+use crate::request::{Auth, Method, Request, RequestBody};
+use crate::response::{Response, ResponseConfig};
+use crate::{Error, TryBufRead};
+
+impl<'res, C> HttpResource<'res, C>
+where
+    C: Read + Write,
+{
+    /// Start pipelining requests to this resource, with at most `N` requests awaiting their response.
+    ///
+    /// The `read_buf` is used to read the responses without consuming any bytes of the next response.
+    pub fn pipeline<'p, const N: usize>(&'p mut self, read_buf: &'p mut [u8]) -> Pipeline<'p, 'res, C, N> {
+        Pipeline {
+            reader: PipelineReader {
+                conn: &mut self.conn,
+                buf: read_buf,
+                start: 0,
+                end: 0,
+                previous: [0; 2],
+            },
+            host: self.host,
+            base_path: self.base_path,
+            response_config: self.response_config,
+            forward_proxy: self.forward_proxy,
+            metrics: self.metrics,
+            auth: self.auth,
+            pending: Deque::new(),
+        }
+    }
+}
+
+/// Requests pipelined on the connection of a [`HttpResource`].
+///
+/// Requests are written back-to-back with [`Pipeline::send()`], and their responses are read in the same order
+/// with [`Pipeline::next_response()`]. Only idempotent methods can be pipelined, as the server may close the
+/// connection before responding to all requests. Each response body must be read or discarded completely
+/// before the next response is read, and responses must have a `Content-Length` or be chunked.
+pub struct Pipeline<'p, 'res, C, const N: usize>
+where
+    C: Read + Write,
+{
+    reader: PipelineReader<'p, HttpConnection<'res, C>>,
+    host: &'p str,
+    base_path: &'p str,
+    response_config: ResponseConfig,
+    forward_proxy: Option<ForwardProxy<'p>>,
+    metrics: Option<&'p dyn Recorder>,
+    auth: Option<Auth<'p>>,
+    /// The methods of the requests that are awaiting their response.
+    pending: Deque<Method, N>,
+}
+
+impl<'p, 'res, C, const N: usize> Pipeline<'p, 'res, C, N>
+where
+    C: Read + Write,
+{
+    /// Write a request without waiting for the responses of previous requests.
+    ///
+    /// Requests with a method that is not idempotent are rejected with [`ErrorKind::InvalidInput`],
+    /// and [`Error::BufferTooSmall`] is returned if `N` requests are already awaiting their response.
+    pub async fn send<B: RequestBody>(&mut self, mut request: Request<'_, B>) -> Result<(), Error> {
+        if !request.method.is_idempotent() {
+            warn!("Only requests with idempotent methods can be pipelined");
+            return Err(Error::Network(ErrorKind::InvalidInput));
+        }
+        if self.pending.is_full() {
+            return Err(Error::BufferTooSmall);
+        }
+
+        request.base_path = Some(self.base_path);
+        request.forward_proxy = self.forward_proxy;
+        if request.auth.is_none() {
+            request.auth = self.auth;
+        }
+        if request.host.is_none() {
+            request.host = Some(self.host);
+        }
+        let conn = &mut self.reader.conn;
+        record_request(self.metrics, async { conn.write_request(&request).await }).await?;
+        self.pending.push_back(request.method).unwrap();
+        Ok(())
+    }
+
+    /// The number of requests that are awaiting their response.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Read the response to the oldest request that is awaiting its response, if any.
+    ///
+    /// The response headers are stored in the provided rx_buf, which should be sized to contain at least the response headers.
+    pub async fn next_response<'r, 'buf>(
+        &'r mut self,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Option<Response<'r, 'buf, PipelineReader<'p, HttpConnection<'res, C>>>>, Error> {
+        let Some(method) = self.pending.pop_front() else {
+            return Ok(None);
+        };
+
+        let reader = &mut self.reader;
+        let config = self.response_config;
+        let read = async move {
+            reader.conn.flush().await?;
+            Response::read_with_config(reader, method, rx_buf, &config).await
+        };
+        let response = record_error(self.metrics, read.await)?;
+        if !response.is_persistent() && !self.pending.is_empty() {
+            warn!(
+                "Connection will not be kept open, {} pipelined requests are lost",
+                self.pending.len()
+            );
+            self.pending.clear();
+        }
+        Ok(Some(response))
+    }
+}
+
+/// A reader of pipelined responses that never reads beyond the end of the current response.
+///
+/// Reads stop after the first empty line, which ends every header section and chunked body, while
+/// bodies with a `Content-Length` are never read beyond their length.
+pub struct PipelineReader<'a, C>
+where
+    C: Read,
+{
+    conn: &'a mut C,
+    buf: &'a mut [u8],
+    start: usize,
+    end: usize,
+    /// The last two consumed bytes, to detect an empty line spanning multiple reads.
+    previous: [u8; 2],
+}
+
+impl<C> PipelineReader<'_, C>
+where
+    C: Read,
+{
+    async fn fill_buf(&mut self) -> Result<&[u8], ErrorKind> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = self.conn.read(self.buf).await.map_err(|e| e.kind())?;
+        }
+        Ok(&self.buf[self.start..self.end])
+    }
+
+    fn advance(&mut self, amt: usize) {
+        let amt = amt.min(self.end - self.start);
+        let consumed = &self.buf[self.start..self.start + amt];
+        match consumed {
+            [] => {}
+            [last] => self.previous = [self.previous[1], *last],
+            [.., second_last, last] => self.previous = [*second_last, *last],
+        }
+        self.start += amt;
+    }
+}
+
+/// Get the number of bytes up to and including the first empty line, or all bytes if there is none.
+fn until_empty_line(previous: [u8; 2], data: &[u8]) -> usize {
+    for (i, byte) in data.iter().enumerate() {
+        if *byte != b'\n' {
+            continue;
+        }
+        let before = |n: usize| {
+            if n <= i {
+                data[i - n]
+            } else {
+                previous[2 + i - n]
+            }
+        };
+        if before(1) == b'\n' || (before(1) == b'\r' && before(2) == b'\n') {
+            return i + 1;
+        }
+    }
+    data.len()
+}
+
+impl<C> ErrorType for PipelineReader<'_, C>
+where
+    C: Read,
+{
+    type Error = ErrorKind;
+}
+
+impl<C> Read for PipelineReader<'_, C>
+where
+    C: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let previous = self.previous;
+        let available = self.fill_buf().await?;
+        // Header sections and chunked bodies are read without knowing their length, but both end with an empty line
+        let len = until_empty_line(previous, available).min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.advance(len);
+        Ok(len)
+    }
+}
+
+impl<C> TryBufRead for PipelineReader<'_, C> where C: Read {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeConnection;
+    use crate::request::RequestBuilder;
+    use crate::response::Status;
+
+    #[test]
+    fn can_find_empty_line() {
+        assert_eq!(19, until_empty_line([0; 2], b"HTTP/1.1 200 OK\r\n\r\nBODY"));
+        assert_eq!(17, until_empty_line([0; 2], b"HTTP/1.1 200 OK\n\nBODY"));
+        assert_eq!(1, until_empty_line(*b"\r\n", b"\nBODY"));
+        assert_eq!(2, until_empty_line([0, b'\n'], b"\r\nBODY"));
+        assert_eq!(4, until_empty_line([0; 2], b"BODY"));
+    }
+
+    #[tokio::test]
+    async fn can_read_pipelined_responses() {
+        let mut conn = FakeConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nFIRSTHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nSECOND\r\n0\r\n\r\nHTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        let mut resource = HttpResource {
+            conn: HttpConnection::Plain(&mut conn),
+            host: "example.com",
+            base_path: "/api",
+            response_config: ResponseConfig::new(),
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        };
+        let mut read_buf = [0; 256];
+        let mut pipeline = resource.pipeline::<4>(&mut read_buf);
+
+        pipeline.send(Request::get("/first").build()).await.unwrap();
+        pipeline.send(Request::get("/second").build()).await.unwrap();
+        pipeline.send(Request::get("/third").build()).await.unwrap();
+        assert_eq!(3, pipeline.pending());
+
+        let mut rx_buf = [0; 256];
+        let response = pipeline.next_response(&mut rx_buf).await.unwrap().unwrap();
+        assert_eq!(b"FIRST", response.body().read_to_end().await.unwrap());
+
+        let response = pipeline.next_response(&mut rx_buf).await.unwrap().unwrap();
+        assert_eq!(b"SECOND", response.body().read_to_end().await.unwrap());
+
+        let response = pipeline.next_response(&mut rx_buf).await.unwrap().unwrap();
+        assert_eq!(Status::NotFound, response.status);
+        assert_eq!(0, response.body().discard().await.unwrap());
+
+        assert!(pipeline.next_response(&mut rx_buf).await.unwrap().is_none());
+        assert!(conn
+            .request
+            .starts_with(b"GET /api/first HTTP/1.1\r\nHost: example.com\r\n\r\nGET /api/second"));
+    }
+
+    #[tokio::test]
+    async fn rejects_non_idempotent_requests() {
+        let mut conn = FakeConnection::new(b"");
+        let mut resource = HttpResource {
+            conn: HttpConnection::Plain(&mut conn),
+            host: "example.com",
+            base_path: "/",
+            response_config: ResponseConfig::new(),
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        };
+        let mut read_buf = [0; 256];
+        let mut pipeline = resource.pipeline::<4>(&mut read_buf);
+
+        let request = Request::post("/").body(b"BODY".as_slice()).build();
+        assert!(matches!(
+            pipeline.send(request).await,
+            Err(Error::Network(ErrorKind::InvalidInput))
+        ));
+        assert_eq!(0, pipeline.pending());
+    }
+}
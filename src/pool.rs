@@ -0,0 +1,323 @@
+/// Connection pool keeping established connections open between requests.
+///
+#[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
+use crate::client::connect;
+use crate::client::HttpConnection;
+#[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+use crate::client::{connect_tcp, establish, TlsConfig, TlsRef};
+use crate::request::*;
+use crate::response::*;
+use crate::url::Url;
+use crate::Error;
+use embedded_io_async::{Read, Write};
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::String;
+use nourl::UrlScheme;
+
+/// The maximum length of a host name that can be kept in the pool.
+pub const MAX_HOST_LEN: usize = 128;
+
+/// The number of seconds an idle connection is kept when the server did not send a `Keep-Alive` timeout.
+pub const DEFAULT_IDLE_TIMEOUT: u64 = 5;
+
+/// An async HTTP client that keeps up to `N` connections open, one per host, port and scheme.
+///
+/// Requests to a host that already has an open connection reuse it, avoiding the cost of
+/// the TCP and TLS handshakes. Idle connections are evicted when their keep-alive timeout
+/// has expired, and the least recently used connection is evicted when all slots are taken.
+///
+/// The pool has no notion of time, so all operations take a monotonic `now` timestamp in seconds.
+pub struct HttpClientPool<'a, T, D, const N: usize>
+where
+    T: TcpConnect + 'a,
+    D: Dns + 'a,
+{
+    client: &'a T,
+    dns: &'a D,
+    slots: [PoolSlot<'a, T::Connection<'a>>; N],
+    idle_timeout: u64,
+    response_config: ResponseConfig,
+}
+
+struct PoolSlot<'a, C>
+where
+    C: Read + Write,
+{
+    conn: Option<HttpConnection<'a, C>>,
+    /// The TLS configuration of the slot, until it is moved into a TLS connection.
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    tls: Option<&'a mut TlsConfig<'a>>,
+    scheme: UrlScheme,
+    host: String<MAX_HOST_LEN>,
+    port: u16,
+    /// The timestamp at which the connection was last handed out.
+    last_used: u64,
+    /// The number of seconds the connection may stay idle before it is evicted.
+    idle_timeout: u64,
+    /// Whether the connection is in a state where it can be used for another request.
+    reusable: bool,
+    /// Whether the server keeps the connection open after the last response.
+    persistent: bool,
+}
+
+impl<C> PoolSlot<'_, C>
+where
+    C: Read + Write,
+{
+    fn empty() -> Self {
+        Self {
+            conn: None,
+            #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+            tls: None,
+            scheme: UrlScheme::HTTP,
+            host: String::new(),
+            port: 0,
+            last_used: 0,
+            idle_timeout: 0,
+            reusable: false,
+            persistent: false,
+        }
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        !self.reusable || now >= self.last_used.saturating_add(self.idle_timeout)
+    }
+
+    fn matches(&self, url: &Url<'_>) -> bool {
+        self.conn.is_some()
+            && self.scheme == url.scheme()
+            && self.port == url.port_or_default()
+            && self.host.eq_ignore_ascii_case(url.host())
+    }
+}
+
+impl<'a, T, D, const N: usize> HttpClientPool<'a, T, D, N>
+where
+    T: TcpConnect + 'a,
+    D: Dns + 'a,
+{
+    /// A pool without slots cannot hold a connection, so creating one fails to compile.
+    const HAS_SLOTS: () = assert!(N > 0, "A connection pool must have at least one slot");
+
+    /// Create a new connection pool for a given connection handle and dns resolver.
+    pub fn new(client: &'a T, dns: &'a D) -> Self {
+        let () = Self::HAS_SLOTS;
+        Self {
+            client,
+            dns,
+            slots: core::array::from_fn(|_| PoolSlot::empty()),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            response_config: ResponseConfig::new(),
+        }
+    }
+
+    /// Create a new connection pool with one TLS configuration per slot for connections to `https` urls.
+    ///
+    /// A TLS connection borrows the buffers of its configuration for the lifetime of the pool, so each configuration
+    /// is moved into a single TLS handshake. A slot without a configuration of its own takes an unused one from
+    /// another slot, and once all configurations are used, connecting to a `https` url returns
+    /// [`Error::NoTlsConfig`]. Plain connections do not use a configuration.
+    #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+    pub fn new_with_tls(client: &'a T, dns: &'a D, tls: &'a mut [TlsConfig<'a>; N]) -> Self {
+        let () = Self::HAS_SLOTS;
+        let mut tls = tls.iter_mut();
+        Self {
+            client,
+            dns,
+            slots: core::array::from_fn(|_| PoolSlot {
+                tls: tls.next(),
+                ..PoolSlot::empty()
+            }),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            response_config: ResponseConfig::new(),
+        }
+    }
+
+    /// Set the number of seconds a connection is kept idle when the server does not specify a keep-alive timeout.
+    pub fn idle_timeout(mut self, seconds: u64) -> Self {
+        self.idle_timeout = seconds;
+        self
+    }
+
+    /// Set the configuration used to parse the responses of requests made with this pool.
+    pub fn response_config(mut self, config: ResponseConfig) -> Self {
+        self.response_config = config;
+        self
+    }
+
+    /// The number of currently open connections.
+    pub fn open_connections(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.conn.is_some()).count()
+    }
+
+    /// Close all connections that have been idle for longer than their keep-alive timeout.
+    pub fn evict_idle(&mut self, now: u64) {
+        for slot in self.slots.iter_mut() {
+            if slot.conn.is_some() && slot.is_expired(now) {
+                debug!("Evicting idle connection to {}", slot.host.as_str());
+                slot.conn = None;
+            }
+        }
+    }
+
+    /// Close all open connections.
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.conn = None;
+        }
+    }
+
+    /// Get a connection to the server with the provided `resource_url`, reusing an open connection if possible.
+    /// The path in the url is considered the base path for subsequent requests.
+    ///
+    /// A connection is only reused once the body of the previous response has been read to its end, which is
+    /// signalled with [`PooledResource::release()`] unless the body was received together with the headers.
+    pub async fn resource<'p>(
+        &'p mut self,
+        resource_url: &'p str,
+        now: u64,
+    ) -> Result<PooledResource<'p, 'a, T::Connection<'a>>, Error> {
+        let resource_url = Url::parse(resource_url)?;
+        self.evict_idle(now);
+
+        let index = match self.slots.iter().position(|slot| slot.matches(&resource_url)) {
+            Some(index) => index,
+            None => {
+                let index = self.vacant_slot();
+                let host = String::try_from(resource_url.host()).map_err(|_| Error::BufferTooSmall)?;
+
+                #[cfg(any(feature = "embedded-tls", feature = "esp-mbedtls"))]
+                let conn = {
+                    // Prefer the configuration of the slot, and only take one of another slot if it has none
+                    let tls_slot = match resource_url.scheme() {
+                        UrlScheme::HTTPS if self.slots[index].tls.is_some() => Some(index),
+                        UrlScheme::HTTPS => match self.slots.iter().position(|slot| slot.tls.is_some()) {
+                            Some(other) => Some(other),
+                            None => {
+                                warn!("No TLS configuration left to connect to {}", resource_url.host());
+                                return Err(Error::NoTlsConfig);
+                            }
+                        },
+                        _ => None,
+                    };
+                    let conn = connect_tcp(
+                        self.client,
+                        self.dns,
+                        resource_url.host(),
+                        resource_url.port_or_default(),
+                    )
+                    .await?;
+                    let tls = tls_slot.and_then(|tls_slot| self.slots[tls_slot].tls.take());
+                    establish(conn, &resource_url, tls.map(TlsRef::from)).await?
+                };
+                #[cfg(not(any(feature = "embedded-tls", feature = "esp-mbedtls")))]
+                let conn = connect(self.client, self.dns, &resource_url).await?;
+
+                let slot = &mut self.slots[index];
+                slot.conn = Some(conn);
+                slot.scheme = resource_url.scheme();
+                slot.host = host;
+                slot.port = resource_url.port_or_default();
+                slot.idle_timeout = self.idle_timeout;
+                slot.reusable = true;
+                slot.persistent = true;
+                index
+            }
+        };
+
+        let slot = &mut self.slots[index];
+        slot.last_used = now;
+
+        Ok(PooledResource {
+            slot,
+            host: resource_url.authority(),
+            base_path: resource_url.path(),
+            response_config: self.response_config,
+        })
+    }
+
+    /// Find a slot without a connection, or evict the least recently used connection.
+    fn vacant_slot(&mut self) -> usize {
+        if let Some(index) = self.slots.iter().position(|slot| slot.conn.is_none()) {
+            return index;
+        }
+
+        // The pool has at least one slot, see `HAS_SLOTS`
+        let (index, slot) = self
+            .slots
+            .iter_mut()
+            .enumerate()
+            .min_by_key(|(_, slot)| slot.last_used)
+            .unwrap();
+        debug!("Evicting least recently used connection to {}", slot.host.as_str());
+        slot.conn = None;
+        index
+    }
+}
+
+/// A HTTP resource backed by a pooled connection
+///
+/// The connection is returned to the pool when drop'ed.
+pub struct PooledResource<'p, 'a, C>
+where
+    C: Read + Write,
+{
+    slot: &'p mut PoolSlot<'a, C>,
+    pub host: &'p str,
+    pub base_path: &'p str,
+    pub response_config: ResponseConfig,
+}
+
+impl<'p, 'a, C> PooledResource<'p, 'a, C>
+where
+    C: Read + Write,
+{
+    /// Send a request to a resource.
+    ///
+    /// The base path of the resource is prepended to the request path.
+    /// The response headers are stored in the provided rx_buf, which should be sized to contain at least the response headers.
+    ///
+    /// The response is returned.
+    pub async fn send<'req, 'buf, B: RequestBody>(
+        &'req mut self,
+        mut request: Request<'req, B>,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<Response<'req, 'buf, HttpConnection<'a, C>>, Error> {
+        request.base_path = Some(self.base_path);
+        if request.host.is_none() {
+            request.host = Some(self.host);
+        }
+
+        let PoolSlot {
+            conn,
+            idle_timeout,
+            reusable,
+            persistent,
+            ..
+        } = &mut *self.slot;
+        let conn = conn.as_mut().ok_or(Error::ConnectionAborted)?;
+
+        // Assume the connection is broken until the response headers are read successfully.
+        *reusable = false;
+        *persistent = false;
+        conn.write_request(&request).await?;
+        conn.flush().await?;
+        let response = Response::read_with_config(conn, request.method, rx_buf, &self.response_config).await?;
+        *persistent = response.is_persistent();
+        *reusable = *persistent && response.is_body_received();
+
+        if let Some(timeout) = response.keep_alive.as_ref().and_then(|keep_alive| keep_alive.timeout()) {
+            *idle_timeout = timeout.into();
+        }
+
+        Ok(response)
+    }
+
+    /// Return the connection to the pool for reuse after the body of the last response has been read to its end.
+    ///
+    /// Until then, the connection is closed when another resource is requested from the pool, as it may still
+    /// hold unread bytes of the body. The connection is not reused if the server is closing it.
+    pub fn release(&mut self) {
+        self.slot.reusable = self.slot.persistent;
+    }
+}
@@ -0,0 +1,140 @@
+/// Reporting the progress of writing request bodies, e.g. to show the progress of an upload on the device.
+///
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+
+use crate::request::RequestBody;
+
+/// A writer that reports the number of bytes written through it.
+///
+/// The callback is invoked after every write with the number of bytes written so far and the `total`, if known.
+pub struct ProgressWriter<W, F> {
+    writer: W,
+    on_progress: F,
+    written: usize,
+    total: Option<usize>,
+}
+
+impl<W, F> ProgressWriter<W, F>
+where
+    W: Write,
+    F: Fn(usize, Option<usize>),
+{
+    /// Report the progress of writing `total` bytes, if known, to `writer`.
+    pub fn new(writer: W, total: Option<usize>, on_progress: F) -> Self {
+        Self {
+            writer,
+            on_progress,
+            written: 0,
+            total,
+        }
+    }
+
+    /// Get the number of bytes written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Get the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W, F> ErrorType for ProgressWriter<W, F>
+where
+    W: Write,
+{
+    type Error = W::Error;
+}
+
+impl<W, F> Write for ProgressWriter<W, F>
+where
+    W: Write,
+    F: Fn(usize, Option<usize>),
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let written = self.writer.write(buf).await?;
+        if written > 0 {
+            self.written += written;
+            (self.on_progress)(self.written, self.total);
+        }
+        Ok(written)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.writer.flush().await
+    }
+}
+
+/// A request body whose progress is reported using a [`ProgressWriter`].
+///
+/// The total is the length of the body, which is unknown for bodies sent using chunked encoding. The progress is
+/// reported from the start every time the body is written, and the trailer fields of the body are not included.
+pub struct ProgressBody<B, F> {
+    body: B,
+    on_progress: F,
+}
+
+impl<B, F> ProgressBody<B, F>
+where
+    B: RequestBody,
+    F: Fn(usize, Option<usize>),
+{
+    /// Report the progress of writing `body` to `on_progress`.
+    pub fn new(body: B, on_progress: F) -> Self {
+        Self { body, on_progress }
+    }
+}
+
+impl<B, F> RequestBody for ProgressBody<B, F>
+where
+    B: RequestBody,
+    F: Fn(usize, Option<usize>),
+{
+    fn len(&self) -> Option<usize> {
+        self.body.len()
+    }
+
+    async fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        let mut writer = ProgressWriter::new(writer, self.body.len(), &self.on_progress);
+        self.body.write(&mut writer).await
+    }
+
+    async fn write_trailers<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.body.write_trailers(writer).await
+    }
+
+    fn error(&self) -> Option<embedded_io::ErrorKind> {
+        self.body.error()
+    }
+
+    async fn rewind(&self) -> bool {
+        self.body.rewind().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_progress() {
+        let reports = RefCell::new(Vec::new());
+        let body = ProgressBody::new(b"0123456789".as_slice(), |written, total| {
+            reports.borrow_mut().push((written, total))
+        });
+        let mut buf = Vec::new();
+        let mut writer = ProgressWriter::new(&mut buf, None, |written, total| {
+            reports.borrow_mut().push((written, total))
+        });
+        writer.write_all(b"abc").await.unwrap();
+        assert_eq!(3, writer.written());
+
+        body.write(&mut buf).await.unwrap();
+        assert_eq!(b"abc0123456789", buf.as_slice());
+        assert_eq!(vec![(3, None), (10, Some(10))], *reports.borrow());
+    }
+}
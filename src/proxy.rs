@@ -0,0 +1,149 @@
+/// Tunneling connections through an HTTP proxy using `CONNECT`.
+///
+use core::fmt::Write as _;
+use embedded_io::Error as _;
+use embedded_io_async::{Read, Write};
+use heapless::String;
+
+use crate::request::{write_basic_credentials, write_str};
+use crate::response::StatusCode;
+use crate::url::Url;
+use crate::Error;
+
+/// The maximum length of the `host:port` target of a `CONNECT` request.
+const MAX_TARGET_LEN: usize = 256;
+
+/// The maximum size of the header section of the proxy response to a `CONNECT` request.
+const MAX_RESPONSE_LEN: usize = 512;
+
+/// The maximum number of headers in the proxy response to a `CONNECT` request.
+const MAX_RESPONSE_HEADERS: usize = 16;
+
+/// An HTTP proxy through which HTTPS requests are tunneled, and to which plain HTTP requests are sent.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Proxy<'a> {
+    pub(crate) url: Url<'a>,
+    credentials: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> Proxy<'a> {
+    /// Create a proxy configuration for the proxy at the provided url, e.g. `http://proxy.local:3128`.
+    pub fn new(url: &'a str) -> Result<Self, Error> {
+        Ok(Self {
+            url: Url::parse(url)?,
+            credentials: None,
+        })
+    }
+
+    /// Authenticate to the proxy with basic authentication in the `Proxy-Authorization` header.
+    pub fn basic_auth(mut self, username: &'a str, password: &'a str) -> Self {
+        self.credentials = Some((username, password));
+        self
+    }
+
+    /// The forwarding of plain HTTP requests to `url` through this proxy.
+    pub(crate) fn forward<'b>(&self, url: &Url<'b>) -> ForwardProxy<'b>
+    where
+        'a: 'b,
+    {
+        ForwardProxy {
+            origin: url.origin(),
+            credentials: self.credentials,
+        }
+    }
+
+    /// Ask the proxy on the connection `conn` to open a tunnel to `host` and `port`.
+    ///
+    /// The response is read byte by byte, so that no bytes of the tunneled connection are consumed.
+    /// [`Error::ProxyRejected`] is returned if the proxy responds with a status other than 2xx.
+    pub(crate) async fn tunnel<C>(&self, conn: &mut C, host: &str, port: u16) -> Result<(), Error>
+    where
+        C: Read + Write,
+    {
+        let mut target: String<MAX_TARGET_LEN> = String::new();
+        write!(target, "{}:{}", host, port).map_err(|_| Error::BufferTooSmall)?;
+
+        write_str(conn, "CONNECT ").await?;
+        write_str(conn, &target).await?;
+        write_str(conn, " HTTP/1.1\r\nHost: ").await?;
+        write_str(conn, &target).await?;
+        write_str(conn, "\r\n").await?;
+        if let Some((username, password)) = self.credentials {
+            write_str(conn, "Proxy-Authorization: Basic ").await?;
+            write_basic_credentials(conn, username, password).await?;
+            write_str(conn, "\r\n").await?;
+        }
+        write_str(conn, "\r\n").await?;
+        conn.flush().await.map_err(|e| e.kind())?;
+
+        let mut buf = [0; MAX_RESPONSE_LEN];
+        let mut len = 0;
+        while !buf[..len].ends_with(b"\r\n\r\n") {
+            if len == buf.len() {
+                return Err(Error::HeadersTooLarge);
+            }
+            if conn.read(&mut buf[len..len + 1]).await.map_err(|e| e.kind())? == 0 {
+                return Err(Error::ConnectionAborted);
+            }
+            len += 1;
+        }
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_RESPONSE_HEADERS];
+        let mut response = httparse::Response::new(&mut headers);
+        response.parse(&buf[..len]).map_err(|_| Error::Codec)?;
+        let status = StatusCode(response.code.ok_or(Error::Codec)?);
+        if !status.is_successful() {
+            warn!(
+                "Proxy rejected the tunnel to {} with status {}",
+                target.as_str(),
+                status.0
+            );
+            return Err(Error::ProxyRejected(status));
+        }
+        debug!("Proxy opened a tunnel to {}", target.as_str());
+        Ok(())
+    }
+}
+
+/// A request sent to a forward proxy, which uses the absolute-form request target.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct ForwardProxy<'a> {
+    /// The scheme and authority of the target, prepended to the request path.
+    pub(crate) origin: &'a str,
+    /// The basic authentication credentials, sent in the `Proxy-Authorization` header.
+    pub(crate) credentials: Option<(&'a str, &'a str)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeConnection;
+
+    #[tokio::test]
+    async fn can_open_tunnel() {
+        let mut conn = FakeConnection::new(b"HTTP/1.1 200 Connection established\r\n\r\nTLS");
+        let proxy = Proxy::new("http://proxy.local:3128")
+            .unwrap()
+            .basic_auth("user", "secret");
+        proxy.tunnel(&mut conn, "example.com", 443).await.unwrap();
+        assert_eq!(
+            b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\nProxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n\r\n",
+            conn.request.as_slice()
+        );
+        // The bytes after the proxy response belong to the tunneled connection
+        assert_eq!(b"TLS", conn.response);
+    }
+
+    #[tokio::test]
+    async fn rejected_tunnel() {
+        let mut conn = FakeConnection::new(b"HTTP/1.1 407 Proxy Authentication Required\r\nContent-Length: 0\r\n\r\n");
+        let proxy = Proxy::new("http://proxy.local:3128").unwrap();
+        assert!(matches!(
+            proxy.tunnel(&mut conn, "[2001:db8::1]", 8443).await,
+            Err(Error::ProxyRejected(StatusCode(407)))
+        ));
+        assert!(conn.request.starts_with(b"CONNECT [2001:db8::1]:8443 HTTP/1.1\r\n"));
+    }
+}
@@ -62,6 +62,37 @@ where
     }
 }
 
+impl<C> BufferingReader<'_, '_, C>
+where
+    C: TryBufRead,
+{
+    /// Fill the buffer until it contains at least `n` bytes, or until the stream ends, without consuming them.
+    ///
+    /// Bytes buffered by the stream, such as a decrypted TLS record, are moved into the buffer. At most the size of
+    /// the buffer is filled.
+    pub(crate) async fn fill_at_least(&mut self, n: usize) -> Result<&[u8], ErrorKind> {
+        let n = n.min(self.buffer.buffer.len());
+        while self.buffer.loaded < n {
+            let free = &mut self.buffer.buffer[self.buffer.loaded..];
+            let read = match self.stream.try_fill_buf().await {
+                Some(result) => {
+                    let data = result.map_err(|e| e.kind())?;
+                    let len = data.len().min(free.len());
+                    free[..len].copy_from_slice(&data[..len]);
+                    self.stream.try_consume(len);
+                    len
+                }
+                None => self.stream.read(free).await.map_err(|e| e.kind())?,
+            };
+            if read == 0 {
+                break;
+            }
+            self.buffer.loaded += read;
+        }
+        self.buffer.fill_buf()
+    }
+}
+
 impl<C> ErrorType for BufferingReader<'_, '_, C>
 where
     C: Read,
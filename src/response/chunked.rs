@@ -5,17 +5,27 @@ use crate::{
     Error, TryBufRead,
 };
 
+/// The maximum number of bytes of trailer fields kept after the last chunk.
+///
+/// Trailer fields that do not fit are discarded.
+pub const MAX_TRAILERS_LEN: usize = 128;
+
+/// The maximum number of bytes of chunk extensions accepted after a chunk size.
+///
+/// Chunk extensions are ignored, but longer extensions are rejected.
+const MAX_CHUNK_EXTENSIONS_LEN: usize = 256;
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum ChunkState {
     NoChunk,
-    NotEmpty(u32),
+    NotEmpty(u64),
     Empty,
 }
 
 impl ChunkState {
     fn consume(&mut self, amt: usize) -> usize {
         if let ChunkState::NotEmpty(remaining) = self {
-            let consumed = (amt as u32).min(*remaining);
+            let consumed = (amt as u64).min(*remaining);
             *remaining -= consumed;
             consumed as usize
         } else {
@@ -23,9 +33,10 @@ impl ChunkState {
         }
     }
 
+    /// The number of remaining bytes in the chunk, saturated to what can be addressed in a buffer.
     fn len(self) -> usize {
         if let ChunkState::NotEmpty(len) = self {
-            len as usize
+            usize::try_from(len).unwrap_or(usize::MAX)
         } else {
             0
         }
@@ -36,6 +47,14 @@ impl ChunkState {
 pub struct ChunkedBodyReader<B> {
     pub raw_body: B,
     chunk_remaining: ChunkState,
+    /// The trailer fields received after the last chunk, one `name: value` per line.
+    trailers: heapless::Vec<u8, MAX_TRAILERS_LEN>,
+    /// Whether lines may be terminated by a bare `LF` instead of `CRLF`.
+    allow_bare_lf: bool,
+    /// The number of bytes that later chunks may contain before the body exceeds the maximum size.
+    body_remaining: u64,
+    /// The number of body bytes read or consumed.
+    consumed: u64,
 }
 
 impl<C> ChunkedBodyReader<C>
@@ -46,52 +65,106 @@ where
         Self {
             raw_body,
             chunk_remaining: ChunkState::NoChunk,
+            trailers: heapless::Vec::new(),
+            allow_bare_lf: false,
+            body_remaining: u64::MAX,
+            consumed: 0,
         }
     }
 
+    /// Accept chunk size lines, chunk terminations and trailer fields ending with a bare `LF` instead of `CRLF`.
+    pub fn allow_bare_lf(mut self, allow: bool) -> Self {
+        self.allow_bare_lf = allow;
+        self
+    }
+
+    /// Fail reads with [`Error::BodyTooLarge`] once a chunk makes the body exceed `max` bytes.
+    pub fn max_body_size(mut self, max: u64) -> Self {
+        self.body_remaining = max;
+        self
+    }
+
     pub fn is_done(&self) -> bool {
         self.chunk_remaining == ChunkState::Empty
     }
 
-    async fn read_next_chunk_length(&mut self) -> Result<(), Error> {
-        let mut header_buf = [0; 8 + 2]; // 32 bit hex + \r + \n
-        let mut total_read = 0;
+    /// Get the number of body bytes read or consumed so far, excluding the chunk framing.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
 
-        'read_size: loop {
+    /// Get the trailer fields sent after the last chunk.
+    ///
+    /// The trailers are only available once the entire body is read.
+    pub fn trailers(&self) -> TrailerIterator<'_> {
+        TrailerIterator(self.trailers.split(is_newline as fn(&u8) -> bool))
+    }
+
+    async fn read_next_chunk_length(&mut self) -> Result<(), Error> {
+        let mut chunk_length: u64 = 0;
+        let mut hex_digits = 0;
+        // Set when the chunk size is followed by whitespace or chunk extensions
+        let mut size_done = false;
+        let mut extensions_len = 0;
+        let mut previous = 0;
+
+        loop {
             let mut byte = 0;
             self.raw_body
                 .read_exact(core::slice::from_mut(&mut byte))
                 .await
                 .map_err(|e| Error::from(e).kind())?;
 
-            if byte != b'\n' {
-                header_buf[total_read] = byte;
-                total_read += 1;
-
-                if total_read == header_buf.len() {
+            if byte == b'\n' {
+                if previous != b'\r' && !self.allow_bare_lf {
                     return Err(Error::Codec);
                 }
-            } else {
-                if total_read == 0 || header_buf[total_read - 1] != b'\r' {
+                break;
+            }
+            if previous == b'\r' {
+                // Bare carriage return within the line
+                return Err(Error::Codec);
+            }
+            previous = byte;
+
+            if extensions_len > 0 {
+                // Chunk extensions are ignored
+                extensions_len += 1;
+                if extensions_len > MAX_CHUNK_EXTENSIONS_LEN {
+                    warn!("Chunk extensions are too long");
                     return Err(Error::Codec);
                 }
-                break 'read_size;
+                continue;
             }
-        }
 
-        let hex_digits = total_read - 1;
-
-        // Prepend hex with zeros
-        let mut hex = [b'0'; 8];
-        hex[8 - hex_digits..].copy_from_slice(&header_buf[..hex_digits]);
-
-        let mut bytes = [0; 4];
-        hex::decode_to_slice(hex, &mut bytes).map_err(|_| Error::Codec)?;
+            match byte {
+                b'\r' => {}
+                b';' if hex_digits > 0 => extensions_len = 1,
+                b' ' | b'\t' if hex_digits > 0 => size_done = true,
+                _ if !size_done => {
+                    let digit = (byte as char).to_digit(16).ok_or(Error::Codec)?;
+                    chunk_length = chunk_length
+                        .checked_mul(16)
+                        .and_then(|len| len.checked_add(u64::from(digit)))
+                        .ok_or(Error::Codec)?;
+                    hex_digits += 1;
+                }
+                _ => return Err(Error::Codec),
+            }
+        }
 
-        let chunk_length = u32::from_be_bytes(bytes);
+        if hex_digits == 0 {
+            return Err(Error::Codec);
+        }
 
         debug!("Chunk length: {}", chunk_length);
 
+        if chunk_length > self.body_remaining {
+            warn!("Chunked: Response body exceeds the maximum size");
+            return Err(Error::BodyTooLarge);
+        }
+        self.body_remaining -= chunk_length;
+
         self.chunk_remaining = match chunk_length {
             0 => ChunkState::Empty,
             other => ChunkState::NotEmpty(other),
@@ -101,16 +174,70 @@ where
     }
 
     async fn read_chunk_end(&mut self) -> Result<(), Error> {
-        // All chunks are terminated with a \r\n
-        let mut newline_buf = [0; 2];
-        self.raw_body.read_exact(&mut newline_buf).await?;
+        // All chunks are terminated with a \r\n, or a bare \n if allowed
+        let mut byte = 0;
+        self.raw_body.read_exact(core::slice::from_mut(&mut byte)).await?;
+
+        if byte == b'\r' {
+            self.raw_body.read_exact(core::slice::from_mut(&mut byte)).await?;
+        } else if !self.allow_bare_lf {
+            return Err(Error::Codec);
+        }
 
-        if newline_buf != [b'\r', b'\n'] {
+        if byte != b'\n' {
             return Err(Error::Codec);
         }
         Ok(())
     }
 
+    /// Read the trailer section after the last chunk, which is terminated by an empty line.
+    async fn read_trailers(&mut self) -> Result<(), Error> {
+        loop {
+            let start = self.trailers.len();
+            let mut fits = true;
+            let mut previous = 0;
+
+            loop {
+                let mut byte = 0;
+                self.raw_body.read_exact(core::slice::from_mut(&mut byte)).await?;
+                if byte == b'\n' {
+                    break;
+                }
+                if previous == b'\r' {
+                    // Bare carriage return within the line
+                    return Err(Error::Codec);
+                }
+                if byte != b'\r' && fits {
+                    fits = self.trailers.push(byte).is_ok();
+                }
+                previous = byte;
+            }
+
+            if previous != b'\r' && !self.allow_bare_lf {
+                return Err(Error::Codec);
+            }
+
+            let line = &self.trailers[start..];
+            if line.is_empty() && fits {
+                // The empty line terminates the trailer section
+                return Ok(());
+            }
+
+            if fits {
+                let (name, _) = core::str::from_utf8(line)?.split_once(':').ok_or(Error::Codec)?;
+                if name.trim().is_empty() {
+                    return Err(Error::Codec);
+                }
+                fits = self.trailers.push(b'\n').is_ok();
+            }
+
+            if !fits {
+                warn!("Discarding trailer field that does not fit");
+                self.trailers.truncate(start);
+            }
+        }
+    }
+
     /// Handles chunk boundary and returns the number of bytes in the current (or new) chunk.
     async fn handle_chunk_boundary(&mut self) -> Result<usize, Error> {
         match self.chunk_remaining {
@@ -128,8 +255,8 @@ where
         }
 
         if self.chunk_remaining == ChunkState::Empty {
-            // Read final chunk termination
-            self.read_chunk_end().await?;
+            // Read any trailer fields and the final termination
+            self.read_trailers().await?;
         }
 
         Ok(self.chunk_remaining.len())
@@ -153,6 +280,10 @@ where
                 stream: self.raw_body.stream,
             },
             chunk_remaining: self.chunk_remaining,
+            trailers: self.trailers,
+            allow_bare_lf: self.allow_bare_lf,
+            body_remaining: self.body_remaining,
+            consumed: self.consumed,
         };
 
         let mut len = 0;
@@ -188,6 +319,51 @@ where
 
         Ok(&mut buffer[..len])
     }
+
+    /// Fill the buffer until it contains at least `n` bytes of the current chunk, without consuming them.
+    pub(crate) async fn fill_at_least(&mut self, n: usize) -> Result<&[u8], Error> {
+        let remaining = self.handle_chunk_boundary().await?;
+        if remaining == 0 {
+            return Ok(&[]);
+        }
+        let buf = self.raw_body.fill_at_least(n.min(remaining)).await?;
+        let len = buf.len().min(remaining);
+        Ok(&buf[..len])
+    }
+}
+
+/// Iterator over the trailer fields of a chunked body
+pub struct TrailerIterator<'a>(core::slice::Split<'a, u8, fn(&u8) -> bool>);
+
+impl<'a> Iterator for TrailerIterator<'a> {
+    type Item = (&'a str, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.0.find(|line| !line.is_empty())?;
+        let separator = line.iter().position(|b| *b == b':')?;
+        let name = core::str::from_utf8(&line[..separator]).ok()?;
+        Some((name.trim(), trim_whitespace(&line[separator + 1..])))
+    }
+}
+
+fn is_newline(b: &u8) -> bool {
+    *b == b'\n'
+}
+
+fn trim_whitespace(mut value: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = value {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        value = rest;
+    }
+    while let [rest @ .., last] = value {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        value = rest;
+    }
+    value
 }
 
 impl<C> ErrorType for ChunkedBodyReader<C> {
@@ -209,6 +385,7 @@ where
             .map_err(|e| Error::Network(e.kind()))?;
 
         self.chunk_remaining.consume(len);
+        self.consumed += len as u64;
 
         Ok(len)
     }
@@ -231,6 +408,7 @@ where
 
     fn consume(&mut self, amt: usize) {
         let consumed = self.chunk_remaining.consume(amt);
+        self.consumed += consumed as u64;
         self.raw_body.consume(consumed);
     }
 }
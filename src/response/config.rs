@@ -0,0 +1,261 @@
+use crate::Error;
+
+/// Configuration of how responses are parsed
+///
+/// The default configuration accepts bare `LF` line endings and status lines without a reason phrase,
+/// as many embedded servers send those, but rejects any other deviation from the specification.
+/// Use [`ResponseConfig::strict()`] for strict conformance to
+/// [RFC 9112](https://www.rfc-editor.org/rfc/rfc9112), e.g. in security sensitive deployments,
+/// or [`ResponseConfig::lenient()`] to tolerate as many non-conforming servers as possible.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResponseConfig {
+    pub(crate) allow_bare_lf: bool,
+    pub(crate) allow_missing_reason_phrase: bool,
+    pub(crate) allow_extra_whitespace: bool,
+    pub(crate) allow_obsolete_line_folding: bool,
+    pub(crate) reject_conflicting_length: bool,
+    pub(crate) max_content_length: u64,
+    pub(crate) max_body_size: u64,
+    pub(crate) max_header_size: usize,
+    pub(crate) max_headers: usize,
+}
+
+impl Default for ResponseConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseConfig {
+    /// Create a new configuration with the default parsing behavior.
+    pub const fn new() -> Self {
+        Self {
+            allow_bare_lf: true,
+            allow_missing_reason_phrase: true,
+            allow_extra_whitespace: false,
+            allow_obsolete_line_folding: false,
+            reject_conflicting_length: false,
+            max_content_length: u64::MAX,
+            max_body_size: u64::MAX,
+            max_header_size: usize::MAX,
+            max_headers: usize::MAX,
+        }
+    }
+
+    /// Create a configuration that only accepts responses conforming to the specification.
+    pub const fn strict() -> Self {
+        Self {
+            allow_bare_lf: false,
+            allow_missing_reason_phrase: false,
+            allow_extra_whitespace: false,
+            allow_obsolete_line_folding: false,
+            reject_conflicting_length: true,
+            max_content_length: u64::MAX,
+            max_body_size: u64::MAX,
+            max_header_size: usize::MAX,
+            max_headers: usize::MAX,
+        }
+    }
+
+    /// Create a configuration that tolerates common deviations from the specification.
+    pub const fn lenient() -> Self {
+        Self {
+            allow_bare_lf: true,
+            allow_missing_reason_phrase: true,
+            allow_extra_whitespace: true,
+            allow_obsolete_line_folding: true,
+            reject_conflicting_length: false,
+            max_content_length: u64::MAX,
+            max_body_size: u64::MAX,
+            max_header_size: usize::MAX,
+            max_headers: usize::MAX,
+        }
+    }
+
+    /// Accept lines terminated by a bare `LF` instead of `CRLF`, both in the header section and in chunked bodies.
+    pub const fn allow_bare_lf(mut self, allow: bool) -> Self {
+        self.allow_bare_lf = allow;
+        self
+    }
+
+    /// Accept status lines without the space separating the status code from the reason phrase, e.g. `HTTP/1.1 200`.
+    pub const fn allow_missing_reason_phrase(mut self, allow: bool) -> Self {
+        self.allow_missing_reason_phrase = allow;
+        self
+    }
+
+    /// Accept multiple spaces in the status line, whitespace between a header name and the colon,
+    /// and whitespace before the first header.
+    pub const fn allow_extra_whitespace(mut self, allow: bool) -> Self {
+        self.allow_extra_whitespace = allow;
+        self
+    }
+
+    /// Accept header values continued on the next line using obsolete line folding.
+    ///
+    /// The line folding is replaced with spaces, so header values appear as a single line.
+    pub const fn allow_obsolete_line_folding(mut self, allow: bool) -> Self {
+        self.allow_obsolete_line_folding = allow;
+        self
+    }
+
+    /// Set the maximum size of the header section in bytes, including the status line.
+    ///
+    /// Responses with a larger header section are rejected with [`Error::HeadersTooLarge`].
+    /// The header section is always limited by the size of the buffer it is read into.
+    pub const fn max_header_size(mut self, max: usize) -> Self {
+        self.max_header_size = max;
+        self
+    }
+
+    /// Set the maximum number of headers.
+    ///
+    /// Responses with more headers are rejected with [`Error::HeadersTooLarge`].
+    /// The number of headers is always limited by the `MAX_HEADERS` capacity of the [`Response`](crate::response::Response).
+    pub const fn max_headers(mut self, max: usize) -> Self {
+        self.max_headers = max;
+        self
+    }
+
+    /// Set the maximum accepted `Content-Length`.
+    ///
+    /// Responses announcing a larger body are rejected with [`Error::InvalidContentLength`].
+    pub const fn max_content_length(mut self, max: u64) -> Self {
+        self.max_content_length = max;
+        self
+    }
+
+    /// Set the maximum size of the response body in bytes.
+    ///
+    /// Responses announcing a larger body in `Content-Length` are rejected with [`Error::BodyTooLarge`],
+    /// and reading a chunked body or a body that ends when the connection is closed fails with
+    /// [`Error::BodyTooLarge`] once it exceeds the maximum size.
+    pub const fn max_body_size(mut self, max: u64) -> Self {
+        self.max_body_size = max;
+        self
+    }
+
+    /// Reject responses that have both a `Content-Length` and a `Transfer-Encoding` header with [`Error::InvalidContentLength`].
+    ///
+    /// Such responses may be an attempt at response smuggling. If they are not rejected,
+    /// the `Transfer-Encoding` takes precedence and the `Content-Length` is ignored.
+    pub const fn reject_conflicting_length(mut self, reject: bool) -> Self {
+        self.reject_conflicting_length = reject;
+        self
+    }
+
+    /// Limit the headers that can be parsed to the maximum number of headers.
+    pub(crate) fn limit_headers<'h, 'b>(
+        &self,
+        headers: &'h mut [httparse::Header<'b>],
+    ) -> &'h mut [httparse::Header<'b>] {
+        let max = headers.len().min(self.max_headers);
+        &mut headers[..max]
+    }
+
+    /// Parse the response header section using this configuration.
+    pub(crate) fn parse<'h, 'b>(
+        &self,
+        response: &mut httparse::Response<'h, 'b>,
+        buf: &'b [u8],
+    ) -> Result<httparse::Status<usize>, Error> {
+        let mut parser = httparse::ParserConfig::default();
+        parser
+            .allow_multiple_spaces_in_response_status_delimiters(self.allow_extra_whitespace)
+            .allow_spaces_after_header_name_in_responses(self.allow_extra_whitespace)
+            .allow_space_before_first_header_name(self.allow_extra_whitespace)
+            .allow_obsolete_multiline_headers_in_responses(self.allow_obsolete_line_folding);
+        parser.parse_response(response, buf).map_err(|e| match e {
+            httparse::Error::TooManyHeaders => {
+                warn!("Response has too many headers");
+                Error::HeadersTooLarge
+            }
+            _ => Error::Codec,
+        })
+    }
+
+    /// Replace any obsolete line folding in a complete header section with spaces.
+    ///
+    /// As specified in [RFC 9112](https://www.rfc-editor.org/rfc/rfc9112#section-5.2), each line break
+    /// followed by whitespace is replaced in-place, keeping the length of the header section.
+    pub(crate) fn unfold(&self, header: &mut [u8]) {
+        if !self.allow_obsolete_line_folding {
+            return;
+        }
+
+        // Whitespace after the status line is not line folding
+        let Some(status_line_end) = header.iter().position(|b| *b == b'\n') else {
+            return;
+        };
+        for i in status_line_end + 1..header.len() {
+            if header[i] == b'\n' && matches!(header.get(i + 1), Some(b' ' | b'\t')) {
+                header[i] = b' ';
+                if header[i - 1] == b'\r' {
+                    header[i - 1] = b' ';
+                }
+            }
+        }
+    }
+
+    /// Check the parts of a complete header section that the parser accepts regardless of the configuration.
+    pub(crate) fn validate(&self, header: &[u8]) -> Result<(), Error> {
+        if !self.allow_bare_lf && has_bare_lf(header) {
+            warn!("Response has a bare LF line ending");
+            return Err(Error::Codec);
+        }
+
+        if !self.allow_missing_reason_phrase && !has_reason_phrase(header) {
+            warn!("Response status line has no reason phrase");
+            return Err(Error::Codec);
+        }
+
+        Ok(())
+    }
+}
+
+fn has_bare_lf(buf: &[u8]) -> bool {
+    buf.iter()
+        .enumerate()
+        .any(|(i, b)| *b == b'\n' && (i == 0 || buf[i - 1] != b'\r'))
+}
+
+/// Check whether the status code is followed by a space, i.e. that there is a (possibly empty) reason phrase.
+fn has_reason_phrase(header: &[u8]) -> bool {
+    // The status line starts with the 8 byte HTTP version followed by a space
+    let Some(status) = header.get(8..) else {
+        return false;
+    };
+    let code = status.iter().position(|b| *b != b' ').unwrap_or(status.len());
+    status.get(code + 3) == Some(&b' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_detect_bare_lf() {
+        assert!(!has_bare_lf(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"));
+        assert!(has_bare_lf(b"HTTP/1.1 200 OK\nContent-Length: 0\r\n\r\n"));
+        assert!(has_bare_lf(b"\n"));
+    }
+
+    #[test]
+    fn can_unfold_obsolete_line_folding() {
+        let mut header = *b"HTTP/1.1 200 OK\r\nX-Folded: a\r\n b\n\tc\r\nX-Other: d\r\n\r\n";
+        ResponseConfig::new()
+            .allow_obsolete_line_folding(true)
+            .unfold(&mut header);
+
+        assert_eq!(b"HTTP/1.1 200 OK\r\nX-Folded: a   b \tc\r\nX-Other: d\r\n\r\n", &header);
+    }
+
+    #[test]
+    fn can_detect_reason_phrase() {
+        assert!(has_reason_phrase(b"HTTP/1.1 200 OK\r\n\r\n"));
+        assert!(has_reason_phrase(b"HTTP/1.1 200 \r\n\r\n"));
+        assert!(has_reason_phrase(b"HTTP/1.1   200 OK\r\n\r\n"));
+        assert!(!has_reason_phrase(b"HTTP/1.1 200\r\n\r\n"));
+    }
+}
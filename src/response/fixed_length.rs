@@ -5,7 +5,29 @@ use crate::Error;
 /// Fixed length response body reader
 pub struct FixedLengthBodyReader<B> {
     pub raw_body: B,
-    pub remaining: usize,
+    pub remaining: u64,
+    consumed: u64,
+}
+
+impl<B> FixedLengthBodyReader<B> {
+    /// Read a body of `len` bytes.
+    pub fn new(raw_body: B, len: u64) -> Self {
+        Self {
+            raw_body,
+            remaining: len,
+            consumed: 0,
+        }
+    }
+
+    /// Get the number of body bytes read or consumed so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// The number of remaining bytes, saturated to what can be addressed in a buffer.
+    pub(crate) fn remaining_len(&self) -> usize {
+        usize::try_from(self.remaining).unwrap_or(usize::MAX)
+    }
 }
 
 impl<C> ErrorType for FixedLengthBodyReader<C> {
@@ -21,8 +43,14 @@ where
             return Ok(0);
         }
 
-        let read = self.raw_body.read(buf).await.map_err(|e| Error::Network(e.kind()))?;
-        self.remaining -= read;
+        let len = buf.len().min(self.remaining_len());
+        let read = self
+            .raw_body
+            .read(&mut buf[..len])
+            .await
+            .map_err(|e| Error::Network(e.kind()))?;
+        self.remaining -= read as u64;
+        self.consumed += read as u64;
 
         Ok(read)
     }
@@ -37,12 +65,14 @@ where
             return Ok(&[]);
         }
 
+        let remaining = self.remaining_len();
+
         let loaded = self
             .raw_body
             .fill_buf()
             .await
             .map_err(|e| Error::Network(e.kind()))
-            .map(|data| &data[..data.len().min(self.remaining)])?;
+            .map(|data| &data[..data.len().min(remaining)])?;
 
         if loaded.is_empty() {
             return Err(Error::ConnectionAborted);
@@ -52,8 +82,9 @@ where
     }
 
     fn consume(&mut self, amt: usize) {
-        let amt = amt.min(self.remaining);
-        self.remaining -= amt;
+        let amt = amt.min(self.remaining_len());
+        self.remaining -= amt as u64;
+        self.consumed += amt as u64;
         self.raw_body.consume(amt)
     }
 }
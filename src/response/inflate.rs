@@ -0,0 +1,304 @@
+use embedded_io_async::{ErrorType, Read};
+use miniz_oxide::inflate::core::inflate_flags::{TINFL_FLAG_HAS_MORE_INPUT, TINFL_FLAG_PARSE_ZLIB_HEADER};
+use miniz_oxide::inflate::core::{decompress, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::response::BodyReader;
+use crate::Error;
+
+const INPUT_BUFFER_SIZE: usize = 128;
+
+/// A body reader that decodes the content encoding of the body
+#[allow(clippy::large_enum_variant)]
+pub enum DecodedBodyReader<'w, B> {
+    /// The body is not encoded, or the encoding is not supported
+    Identity(BodyReader<B>),
+    /// The body is `deflate` encoded
+    Deflate(InflateBodyReader<'w, B>),
+}
+
+impl<B> DecodedBodyReader<'_, B>
+where
+    B: Read,
+{
+    /// Read the entire decoded body
+    pub async fn read_to_end(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self {
+            DecodedBodyReader::Identity(reader) => reader.read_all(buf).await,
+            DecodedBodyReader::Deflate(reader) => reader.read_to_end(buf).await,
+        }
+    }
+}
+
+impl<B> ErrorType for DecodedBodyReader<'_, B> {
+    type Error = Error;
+}
+
+impl<B> Read for DecodedBodyReader<'_, B>
+where
+    B: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self {
+            DecodedBodyReader::Identity(reader) => reader.read(buf).await,
+            DecodedBodyReader::Deflate(reader) => reader.read(buf).await,
+        }
+    }
+}
+
+/// Deflate encoded body reader
+///
+/// Both zlib wrapped and raw deflate streams are supported, as servers are known to send either.
+pub struct InflateBodyReader<'w, B> {
+    raw_body: BodyReader<B>,
+    decompressor: DecompressorOxide,
+    /// The buffer holding the decompressed bytes, which is also the dictionary for back references.
+    window: &'w mut [u8],
+    /// The position in the window where the next decompressed bytes are written.
+    window_pos: usize,
+    /// The position of the decompressed bytes in the window that are not yet read.
+    unread_pos: usize,
+    /// The number of decompressed bytes in the window that are not yet read.
+    unread_len: usize,
+    input: [u8; INPUT_BUFFER_SIZE],
+    input_pos: usize,
+    input_len: usize,
+    input_done: bool,
+    /// Whether the stream has a zlib header, known after the first bytes are read.
+    zlib: Option<bool>,
+    done: bool,
+}
+
+impl<'w, B> InflateBodyReader<'w, B>
+where
+    B: Read,
+{
+    /// Create a reader inflating the body into `window`, which must have a size that is a power of two.
+    ///
+    /// Reading fails with [`Error::BufferTooSmall`] if the window size is not a power of two or smaller than the
+    /// window required by a zlib stream.
+    pub fn new(raw_body: BodyReader<B>, window: &'w mut [u8]) -> Self {
+        Self {
+            raw_body,
+            decompressor: DecompressorOxide::new(),
+            window,
+            window_pos: 0,
+            unread_pos: 0,
+            unread_len: 0,
+            input: [0; INPUT_BUFFER_SIZE],
+            input_pos: 0,
+            input_len: 0,
+            input_done: false,
+            zlib: None,
+            done: false,
+        }
+    }
+
+    /// Read the entire decoded body
+    pub async fn read_to_end(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut len = 0;
+        while len < buf.len() {
+            match self.read(&mut buf[len..]).await? {
+                0 => return Ok(len),
+                n => len += n,
+            }
+        }
+
+        let mut b = [0];
+        if self.read(&mut b).await? > 0 {
+            return Err(Error::BufferTooSmall);
+        }
+
+        Ok(len)
+    }
+
+    async fn fill_input(&mut self) -> Result<(), Error> {
+        // Move any unconsumed input to the beginning of the buffer
+        self.input.copy_within(self.input_pos..self.input_len, 0);
+        self.input_len -= self.input_pos;
+        self.input_pos = 0;
+
+        let read = self.raw_body.read(&mut self.input[self.input_len..]).await?;
+        if read == 0 {
+            self.input_done = true;
+        }
+        self.input_len += read;
+        Ok(())
+    }
+
+    async fn detect_zlib_header(&mut self) -> Result<bool, Error> {
+        while self.input_len - self.input_pos < 2 && !self.input_done {
+            self.fill_input().await?;
+        }
+
+        let header = &self.input[self.input_pos..self.input_len];
+        if header.len() < 2 {
+            return Ok(false);
+        }
+
+        let zlib = header[0] & 0x0F == 8 && header[0] >> 4 <= 7 && u16::from_be_bytes([header[0], header[1]]) % 31 == 0;
+        if zlib && self.window.len() < 1 << ((header[0] >> 4) + 8) {
+            // The stream uses a larger window than available
+            return Err(Error::BufferTooSmall);
+        }
+
+        Ok(zlib)
+    }
+
+    /// Decompress the next bytes into the window
+    async fn inflate(&mut self) -> Result<(), Error> {
+        if !self.window.len().is_power_of_two() {
+            warn!("The inflate window size must be a power of two");
+            return Err(Error::BufferTooSmall);
+        }
+
+        let zlib = match self.zlib {
+            Some(zlib) => zlib,
+            None => {
+                let zlib = self.detect_zlib_header().await?;
+                self.zlib = Some(zlib);
+                zlib
+            }
+        };
+
+        if self.input_pos == self.input_len && !self.input_done {
+            self.fill_input().await?;
+        }
+
+        let mut flags = 0;
+        if zlib {
+            flags |= TINFL_FLAG_PARSE_ZLIB_HEADER;
+        }
+        if !self.input_done {
+            flags |= TINFL_FLAG_HAS_MORE_INPUT;
+        }
+
+        let (status, consumed, written) = decompress(
+            &mut self.decompressor,
+            &self.input[self.input_pos..self.input_len],
+            self.window,
+            self.window_pos,
+            flags,
+        );
+
+        self.input_pos += consumed;
+        self.unread_pos = self.window_pos;
+        self.unread_len = written;
+        self.window_pos = (self.window_pos + written) & (self.window.len() - 1);
+
+        match status {
+            TINFLStatus::Done => self.done = true,
+            TINFLStatus::NeedsMoreInput | TINFLStatus::HasMoreOutput => {}
+            _ => {
+                warn!("Unable to inflate body");
+                return Err(Error::Codec);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<B> ErrorType for InflateBodyReader<'_, B> {
+    type Error = Error;
+}
+
+impl<B> Read for InflateBodyReader<'_, B>
+where
+    B: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.unread_len == 0 {
+            if self.done {
+                return Ok(0);
+            }
+            self.inflate().await?;
+        }
+
+        let len = buf.len().min(self.unread_len);
+        buf[..len].copy_from_slice(&self.window[self.unread_pos..self.unread_pos + len]);
+        self.unread_pos += len;
+        self.unread_len -= len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::ToEndBodyReader;
+
+    const ZLIB: &[u8] = b"\x78\x9c\xf3\x70\xf5\xf1\xf1\x57\x08\xf7\x0f\xf2\x71\x51\xf0\xc0\xce\x06\x00\xac\x13\x09\x95";
+    const ZLIB_SMALL_WINDOW: &[u8] =
+        b"\x18\xd3\xf3\x70\xf5\xf1\xf1\x57\x08\xf7\x0f\xf2\x71\x51\xf0\xc0\xce\x06\x00\xac\x13\x09\x95";
+    const RAW_DEFLATE: &[u8] = b"\xf3\x70\xf5\xf1\xf1\x57\x08\xf7\x0f\xf2\x71\x51\xf0\xc0\xce\x06\x00";
+
+    #[tokio::test]
+    async fn can_inflate_zlib() {
+        let mut window = [0; 32 * 1024];
+        let mut reader = InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(ZLIB)), &mut window);
+
+        let mut body = [0; 64];
+        let len = reader.read_to_end(&mut body).await.unwrap();
+
+        assert_eq!(b"HELLO WORLD HELLO WORLD HELLO WORLD", &body[..len]);
+    }
+
+    #[tokio::test]
+    async fn can_inflate_raw_deflate() {
+        let mut window = [0; 32 * 1024];
+        let mut reader = InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(RAW_DEFLATE)), &mut window);
+
+        let mut body = [0; 64];
+        let len = reader.read_to_end(&mut body).await.unwrap();
+
+        assert_eq!(b"HELLO WORLD HELLO WORLD HELLO WORLD", &body[..len]);
+    }
+
+    #[tokio::test]
+    async fn can_inflate_with_small_window_and_tiny_reads() {
+        let mut window = [0; 512];
+        let mut reader =
+            InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(ZLIB_SMALL_WINDOW)), &mut window);
+
+        let mut body = heapless::Vec::<u8, 64>::new();
+        let mut buf = [0; 1];
+        while reader.read(&mut buf).await.unwrap() == 1 {
+            body.push(buf[0]).unwrap();
+        }
+
+        assert_eq!(b"HELLO WORLD HELLO WORLD HELLO WORLD", body.as_slice());
+    }
+
+    #[tokio::test]
+    async fn window_must_fit_zlib_window() {
+        let mut window = [0; 512];
+        let mut reader = InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(ZLIB)), &mut window);
+
+        let mut body = [0; 64];
+        assert!(matches!(reader.read(&mut body).await, Err(Error::BufferTooSmall)));
+    }
+
+    #[tokio::test]
+    async fn window_must_be_power_of_two() {
+        let mut window = [0; 1000];
+        let mut reader = InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(RAW_DEFLATE)), &mut window);
+
+        let mut body = [0; 64];
+        assert!(matches!(reader.read(&mut body).await, Err(Error::BufferTooSmall)));
+    }
+
+    #[tokio::test]
+    async fn truncated_stream_is_an_error() {
+        let mut window = [0; 32 * 1024];
+        let mut reader = InflateBodyReader::new(BodyReader::ToEnd(ToEndBodyReader::new(&ZLIB[..10])), &mut window);
+
+        let mut body = [0; 64];
+        assert!(matches!(reader.read_to_end(&mut body).await, Err(Error::Codec)));
+    }
+}
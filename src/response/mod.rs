@@ -1,21 +1,38 @@
 use embedded_io::{Error as _, ErrorType};
-use embedded_io_async::{BufRead, Read};
-use heapless::Vec;
-
-use crate::headers::{ContentType, KeepAlive, TransferEncoding};
+use embedded_io_async::{BufRead, Read, Write};
+use heapless::{String, Vec};
+
+use crate::date::parse_http_date;
+use crate::headers::{
+    accepts_byte_ranges, is_utf8_charset, Allow, CacheControl, Connection, ContentEncoding, ContentRange, ContentType,
+    HeaderList, HeaderValue, KeepAlive, Redacted, RetryAfter, TransferEncoding, SENSITIVE_HEADERS,
+};
+#[cfg(feature = "integrity")]
+use crate::integrity::{parse_content_digest, parse_content_md5, Digest};
 use crate::reader::BufferingReader;
 use crate::request::Method;
-pub use crate::response::chunked::ChunkedBodyReader;
+pub use crate::response::chunked::{ChunkedBodyReader, TrailerIterator, MAX_TRAILERS_LEN};
+pub use crate::response::config::ResponseConfig;
 pub use crate::response::fixed_length::FixedLengthBodyReader;
+#[cfg(feature = "deflate")]
+pub use crate::response::inflate::{DecodedBodyReader, InflateBodyReader};
+#[cfg(feature = "alloc")]
+pub use crate::response::owned::ResponseOwned;
+pub use crate::response::to_end::ToEndBodyReader;
+use crate::validator::MAX_ETAG_LEN;
 use crate::{Error, TryBufRead};
 
 mod chunked;
+mod config;
 mod fixed_length;
+#[cfg(feature = "deflate")]
+mod inflate;
+#[cfg(feature = "alloc")]
+mod owned;
+mod to_end;
 
 /// Type representing a parsed HTTP response.
-#[derive(Debug)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Response<'resp, 'buf, C>
+pub struct Response<'resp, 'buf, C, const MAX_HEADERS: usize = 64>
 where
     C: Read,
 {
@@ -27,11 +44,20 @@ where
     /// The HTTP response content type.
     pub content_type: Option<ContentType>,
     /// The content length.
-    pub content_length: Option<usize>,
+    pub content_length: Option<u64>,
     /// The transfer encoding.
     pub transfer_encoding: heapless::Vec<TransferEncoding, 4>,
     /// The keep-alive parameters.
     pub keep_alive: Option<KeepAlive>,
+    /// The content encoding.
+    pub content_encoding: Option<ContentEncoding>,
+    /// The connection options.
+    pub connection: Option<Connection>,
+    /// The minor version of the HTTP/1.x response.
+    version: u8,
+    /// Whether the charset of the content type is UTF-8, which is assumed if there is none.
+    utf8: bool,
+    config: ResponseConfig,
     header_buf: &'buf mut [u8],
     header_len: usize,
     raw_body_read: usize,
@@ -43,67 +69,161 @@ where
 {
     // Read at least the headers from the connection.
     pub async fn read(conn: &'resp mut C, method: Method, header_buf: &'buf mut [u8]) -> Result<Self, Error> {
-        let mut header_len = 0;
+        Self::read_with_config(conn, method, header_buf, &ResponseConfig::new()).await
+    }
+}
+
+impl<'resp, 'buf, C, const MAX_HEADERS: usize> Response<'resp, 'buf, C, MAX_HEADERS>
+where
+    C: Read,
+{
+    /// Read at least the headers from the connection, using the provided parser configuration.
+    ///
+    /// At most `MAX_HEADERS` headers are parsed, each of which takes up stack space while parsing.
+    pub async fn read_with_config(
+        conn: &'resp mut C,
+        method: Method,
+        header_buf: &'buf mut [u8],
+        config: &ResponseConfig,
+    ) -> Result<Self, Error> {
+        Self::read_with_interim(conn, method, header_buf, config, |_, _| {}).await
+    }
+
+    /// Read at least the headers of the final response from the connection, using the provided parser configuration.
+    ///
+    /// Interim `1xx` responses, such as `103 Early Hints`, are consumed and passed to `on_interim` before
+    /// the final response is read. A `101 Switching Protocols` response is returned as the final response.
+    pub async fn read_with_interim<F>(
+        conn: &'resp mut C,
+        method: Method,
+        header_buf: &'buf mut [u8],
+        config: &ResponseConfig,
+        on_interim: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(StatusCode, HeaderIterator<'_, MAX_HEADERS>),
+    {
+        let (header_len, pos) = Self::read_head(conn, header_buf, config, on_interim).await?;
+        Self::from_head(conn, method, header_buf, header_len, pos, config)
+    }
+
+    /// Read the header section of the final response into `header_buf`, skipping interim responses.
+    ///
+    /// Returns the length of the header section and the number of bytes read into `header_buf`.
+    pub(crate) async fn read_head<F>(
+        conn: &mut C,
+        header_buf: &mut [u8],
+        config: &ResponseConfig,
+        mut on_interim: F,
+    ) -> Result<(usize, usize), Error>
+    where
+        F: FnMut(StatusCode, HeaderIterator<'_, MAX_HEADERS>),
+    {
         let mut pos = 0;
-        while pos < header_buf.len() {
-            let n = conn.read(&mut header_buf[pos..]).await.map_err(|e| {
-                /*warn!(
-                    "error {:?}, but read data from socket:  {:?}",
-                    defmt::Debug2Format(&e),
-                    defmt::Debug2Format(&core::str::from_utf8(&buf[..pos])),
-                );*/
-                e.kind()
-            })?;
+        let header_len = loop {
+            let header_len = Self::read_header_section(conn, header_buf, &mut pos, config).await?;
 
-            if n == 0 {
-                return Err(Error::ConnectionAborted);
-            }
+            config.unfold(&mut header_buf[..header_len]);
+            config.validate(&header_buf[..header_len])?;
 
-            pos += n;
+            {
+                let mut iterator = HeaderIterator(0, [httparse::EMPTY_HEADER; MAX_HEADERS]);
+                let mut response = httparse::Response::new(config.limit_headers(&mut iterator.1));
+                config.parse(&mut response, &header_buf[..header_len]).unwrap();
 
-            // Look for header end
-            let mut headers = [httparse::EMPTY_HEADER; 64];
-            let mut response = httparse::Response::new(&mut headers);
-            let parse_status = response.parse(&header_buf[..pos]).map_err(|_| Error::Codec)?;
-            if parse_status.is_complete() {
-                header_len = parse_status.unwrap();
-                break;
+                let status: StatusCode = response.code.unwrap().into();
+                if !status.is_informational() || status == Status::SwitchingProtocols {
+                    break header_len;
+                }
+
+                debug!("Skipping interim response with status {}", status.0);
+                on_interim(status, iterator);
             }
-        }
 
-        if header_len == 0 {
-            // Unable to completely read header
-            return Err(Error::BufferTooSmall);
-        }
+            // Move any bytes read beyond the interim response to the beginning of the buffer
+            header_buf.copy_within(header_len..pos, 0);
+            pos -= header_len;
+        };
+        Ok((header_len, pos))
+    }
 
+    /// Parse the header section read by [`Self::read_head()`].
+    pub(crate) fn from_head(
+        conn: &'resp mut C,
+        method: Method,
+        header_buf: &'buf mut [u8],
+        header_len: usize,
+        pos: usize,
+        config: &ResponseConfig,
+    ) -> Result<Self, Error> {
         // Parse status and known headers
-        let mut headers = [httparse::EMPTY_HEADER; 64];
-        let mut response = httparse::Response::new(&mut headers);
-        response.parse(&header_buf[..header_len]).unwrap();
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut response = httparse::Response::new(config.limit_headers(&mut headers));
+        config.parse(&mut response, &header_buf[..header_len]).unwrap();
 
         let status: StatusCode = response.code.unwrap().into();
+        let version = response.version.unwrap();
         let mut content_type = None;
+        let mut utf8 = true;
         let mut content_length = None;
         let mut transfer_encoding = Vec::new();
         let mut keep_alive = None;
+        let mut content_encoding = None;
+        let mut connection: Option<Connection> = None;
+        let mut range_length = None;
 
         for header in response.headers {
             if header.name.eq_ignore_ascii_case("content-type") {
                 content_type.replace(header.value.into());
+                utf8 = is_utf8_charset(header.value);
             } else if header.name.eq_ignore_ascii_case("content-length") {
-                content_length = Some(
-                    core::str::from_utf8(header.value)
-                        .map_err(|_| Error::Codec)?
-                        .parse::<usize>()
-                        .map_err(|_| Error::Codec)?,
-                );
+                let len = parse_content_length(header.value).ok_or(Error::InvalidContentLength)?;
+                if content_length.is_some_and(|content_length| content_length != len) {
+                    warn!("Response has conflicting Content-Length headers");
+                    return Err(Error::InvalidContentLength);
+                }
+                if len > config.max_content_length {
+                    warn!("Response Content-Length {} exceeds the maximum", len);
+                    return Err(Error::InvalidContentLength);
+                }
+                if len > config.max_body_size {
+                    warn!("Response Content-Length {} exceeds the maximum body size", len);
+                    return Err(Error::BodyTooLarge);
+                }
+                content_length = Some(len);
             } else if header.name.eq_ignore_ascii_case("transfer-encoding") {
                 transfer_encoding
                     .push(header.value.try_into().map_err(|_| Error::Codec)?)
                     .map_err(|_| Error::Codec)?;
             } else if header.name.eq_ignore_ascii_case("keep-alive") {
                 keep_alive.replace(header.value.try_into().map_err(|_| Error::Codec)?);
+            } else if header.name.eq_ignore_ascii_case("content-encoding") {
+                let encoding = ContentEncoding::from(header.value);
+                content_encoding = Some(content_encoding.unwrap_or(ContentEncoding::Identity).then(encoding));
+            } else if header.name.eq_ignore_ascii_case("connection") {
+                let options: Connection = header.value.try_into().map_err(|_| Error::Codec)?;
+                let connection = connection.get_or_insert_with(Connection::default);
+                connection.close |= options.close;
+                connection.keep_alive |= options.keep_alive;
+                connection.upgrade |= options.upgrade;
+            } else if header.name.eq_ignore_ascii_case("content-range") {
+                range_length = ContentRange::try_from(header.value)
+                    .ok()
+                    .filter(ContentRange::is_bytes)
+                    .map(|range| range.length());
+            }
+        }
+
+        if content_length.is_some() && !transfer_encoding.is_empty() {
+            // According to https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3
+            //  If a message is received with both a Transfer-Encoding and a
+            //  Content-Length header field, the Transfer-Encoding overrides the
+            //  Content-Length.
+            if config.reject_conflicting_length {
+                warn!("Response has both Content-Length and Transfer-Encoding");
+                return Err(Error::InvalidContentLength);
             }
+            content_length = None;
         }
 
         if status.is_informational() || status == Status::NoContent {
@@ -116,11 +236,25 @@ where
             content_length = Some(0);
         }
 
+        if status == Status::PartialContent && method != Method::HEAD {
+            // A body that does not match its range is a bug of the server, which would corrupt ranged downloads
+            if let (Some(content_length), Some(range_length)) = (content_length, range_length) {
+                if content_length != range_length {
+                    warn!(
+                        "Response Content-Length {} does not match the Content-Range length {}",
+                        content_length, range_length
+                    );
+                    return Err(Error::InvalidContentLength);
+                }
+            }
+        }
+
         // The number of bytes that we have read into the body part of the response
         let raw_body_read = pos - header_len;
 
-        if let Some(content_length) = content_length {
-            if content_length < raw_body_read {
+        // The bytes after a 101 response belong to the protocol switched to
+        if let Some(content_length) = content_length.filter(|_| status != Status::SwitchingProtocols) {
+            if content_length < raw_body_read as u64 {
                 // We have more into the body then what is specified in content_length
                 return Err(Error::Codec);
             }
@@ -134,33 +268,206 @@ where
             content_length,
             transfer_encoding,
             keep_alive,
+            content_encoding,
+            connection,
+            version,
+            utf8,
+            config: *config,
             header_buf,
             header_len,
             raw_body_read,
         })
     }
 
+    /// Read from the connection until `header_buf[..pos]` starts with a complete header section.
+    ///
+    /// Returns the length of the header section.
+    async fn read_header_section(
+        conn: &mut C,
+        header_buf: &mut [u8],
+        pos: &mut usize,
+        config: &ResponseConfig,
+    ) -> Result<usize, Error> {
+        loop {
+            if *pos > 0 {
+                // Look for header end
+                let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+                let mut response = httparse::Response::new(config.limit_headers(&mut headers));
+                if let httparse::Status::Complete(header_len) = config.parse(&mut response, &header_buf[..*pos])? {
+                    if header_len > config.max_header_size {
+                        warn!("Response header section exceeds {} bytes", config.max_header_size);
+                        return Err(Error::HeadersTooLarge);
+                    }
+                    return Ok(header_len);
+                }
+
+                if *pos >= config.max_header_size {
+                    warn!("Response header section exceeds {} bytes", config.max_header_size);
+                    return Err(Error::HeadersTooLarge);
+                }
+            }
+
+            if *pos == header_buf.len() {
+                // Unable to completely read header
+                return Err(Error::BufferTooSmall);
+            }
+
+            let n = conn.read(&mut header_buf[*pos..]).await.map_err(|e| e.kind())?;
+            if n == 0 {
+                return Err(Error::ConnectionAborted);
+            }
+
+            *pos += n;
+        }
+    }
+
+    /// Get the reason phrase sent by the server in the status line, which may be empty
+    ///
+    /// Use [`StatusCode::canonical_reason()`] for the standard reason phrase of the status code.
+    /// A reason phrase that is not valid UTF-8 is returned as an empty string.
+    pub fn reason(&self) -> &str {
+        let header = &self.header_buf[..self.header_len];
+        let line_end = header.iter().position(|b| *b == b'\n').unwrap_or(header.len());
+        // The status line starts with the 8 byte HTTP version, followed by the 3 digit status code
+        let mut status = header[..line_end].get(8..).unwrap_or_default();
+        while let [b' ', rest @ ..] = status {
+            status = rest;
+        }
+        let mut reason = status.get(3..).unwrap_or_default();
+        while let [b' ' | b'\t', rest @ ..] = reason {
+            reason = rest;
+        }
+        core::str::from_utf8(reason).map(str::trim_end).unwrap_or_default()
+    }
+
     /// Get the response headers
-    pub fn headers(&self) -> HeaderIterator {
-        let mut iterator = HeaderIterator(0, [httparse::EMPTY_HEADER; 64]);
-        let mut response = httparse::Response::new(&mut iterator.1);
-        response.parse(&self.header_buf[..self.header_len]).unwrap();
+    pub fn headers(&self) -> HeaderIterator<'_, MAX_HEADERS> {
+        let mut iterator = HeaderIterator(0, [httparse::EMPTY_HEADER; MAX_HEADERS]);
+        let mut response = httparse::Response::new(self.config.limit_headers(&mut iterator.1));
+        self.config
+            .parse(&mut response, &self.header_buf[..self.header_len])
+            .unwrap();
 
         iterator
     }
 
-    /// Get the response body
-    pub fn body(self) -> ResponseBody<'resp, 'buf, C> {
-        let reader_hint = if self.method == Method::HEAD {
-            // Head requests does not have a body so we return an empty reader
+    /// Get the value of the first header with the provided name, which is compared case-insensitively
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers()
+            .find(|(header, _)| !header.is_empty() && header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Get the values of all headers with the provided name, which is compared case-insensitively
+    pub fn headers_matching<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.headers()
+            .filter(move |(header, _)| !header.is_empty() && header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Get the `Date` header as the number of seconds since the Unix epoch, if present and valid
+    pub fn date(&self) -> Option<u64> {
+        self.header("date").and_then(|value| parse_http_date(value).ok())
+    }
+
+    /// Get the `Last-Modified` header as the number of seconds since the Unix epoch, if present and valid
+    pub fn last_modified(&self) -> Option<u64> {
+        self.header("last-modified")
+            .and_then(|value| parse_http_date(value).ok())
+    }
+
+    /// Get the `Expires` header as the number of seconds since the Unix epoch, if present and valid
+    ///
+    /// An invalid `Expires` value, such as `0`, means that the response is already expired.
+    pub fn expires(&self) -> Option<u64> {
+        self.header("expires").and_then(|value| parse_http_date(value).ok())
+    }
+
+    /// Get the `Cache-Control` header, if present and valid
+    pub fn cache_control(&self) -> Option<CacheControl> {
+        self.header("cache-control").and_then(|value| value.try_into().ok())
+    }
+
+    /// Get the `Retry-After` header, if present and valid
+    pub fn retry_after(&self) -> Option<RetryAfter> {
+        self.header("retry-after").and_then(|value| value.try_into().ok())
+    }
+
+    /// Get the `Content-Range` header of a `206 Partial Content` response, if present and valid
+    pub fn content_range(&self) -> Option<ContentRange<'_>> {
+        self.header("content-range").and_then(|value| value.try_into().ok())
+    }
+
+    /// Check whether the server advertises support for byte ranges in the `Accept-Ranges` header
+    ///
+    /// Servers may support ranges without advertising it, so a ranged request can be sent regardless.
+    pub fn accepts_ranges(&self) -> bool {
+        self.header("accept-ranges").is_some_and(accepts_byte_ranges)
+    }
+
+    /// Get the `Allow` header listing the methods supported by the resource, if present and valid
+    pub fn allow(&self) -> Option<Allow> {
+        self.header("allow").and_then(|value| value.try_into().ok())
+    }
+
+    /// Get the digest of the body from the `Content-Digest` or `Content-MD5` header, if present and valid
+    ///
+    /// The `sha-256` digest of `Content-Digest` is preferred over its `md5` digest. `None` is returned if neither
+    /// header is present or only digests of unsupported algorithms are listed, in which case the body cannot be
+    /// verified. The body can be verified against the digest using [`DigestReader`](crate::integrity::DigestReader).
+    #[cfg(feature = "integrity")]
+    pub fn content_digest(&self) -> Option<Digest> {
+        self.header("content-digest")
+            .and_then(parse_content_digest)
+            .or_else(|| self.header("content-md5").and_then(parse_content_md5))
+    }
+
+    /// Check whether the connection can be reused for another request once the response body is read.
+    ///
+    /// HTTP/1.1 connections are persistent unless the server sends `Connection: close`, and HTTP/1.0
+    /// connections are only persistent if the server sends `Connection: keep-alive`. A body without
+    /// `Content-Length` or chunked transfer encoding ends when the server closes the connection,
+    /// so reaching its end is expected and the connection cannot be reused afterwards.
+    pub fn is_persistent(&self) -> bool {
+        let connection = self.connection.unwrap_or_default();
+        if connection.close || self.status == Status::SwitchingProtocols {
+            return false;
+        }
+        if self.version == 0 && !connection.keep_alive {
+            return false;
+        }
+        !matches!(self.reader_hint(), ReaderHint::ToEnd)
+    }
+
+    /// Check whether the entire body was received together with the headers, such that nothing is left to be read
+    /// from the connection.
+    pub(crate) fn is_body_received(&self) -> bool {
+        match self.reader_hint() {
+            ReaderHint::Empty => true,
+            ReaderHint::FixedLength(content_length) => self.raw_body_read as u64 >= content_length,
+            ReaderHint::Chunked { .. } | ReaderHint::ToEnd => false,
+        }
+    }
+
+    fn reader_hint(&self) -> ReaderHint {
+        if self.method == Method::HEAD || self.status == Status::NotModified {
+            // Head requests and 304 responses do not have a body, even if they contain a Content-Length,
+            // so we return an empty reader
             ReaderHint::Empty
         } else if let Some(content_length) = self.content_length {
             ReaderHint::FixedLength(content_length)
         } else if self.transfer_encoding.contains(&TransferEncoding::Chunked) {
-            ReaderHint::Chunked
+            ReaderHint::Chunked {
+                allow_bare_lf: self.config.allow_bare_lf,
+            }
         } else {
             ReaderHint::ToEnd
-        };
+        }
+    }
+
+    /// Get the response body
+    pub fn body(self) -> ResponseBody<'resp, 'buf, C> {
+        let reader_hint = self.reader_hint();
 
         // Move the body part of the bytes in the header buffer to the beginning of the buffer.
         self.header_buf
@@ -169,15 +476,208 @@ where
         ResponseBody {
             conn: self.conn,
             reader_hint,
+            #[cfg(feature = "deflate")]
+            content_encoding: self.content_encoding,
             body_buf: self.header_buf,
             raw_body_read: self.raw_body_read,
+            max_body_size: self.config.max_body_size,
+            utf8: self.utf8,
+        }
+    }
+
+    /// Split the response into its head and body, such that the headers remain accessible while the body is read.
+    ///
+    /// Unlike [`Response::body()`], the header section is kept at the beginning of the buffer provided to
+    /// [`Response::read()`], so only the rest of the buffer is available to the body.
+    pub fn into_parts(self) -> (ResponseHead<'buf, MAX_HEADERS>, ResponseBody<'resp, 'buf, C>) {
+        let reader_hint = self.reader_hint();
+        let (header, body_buf) = self.header_buf.split_at_mut(self.header_len);
+
+        let head = ResponseHead {
+            status: self.status,
+            header,
+            config: self.config,
+        };
+        let body = ResponseBody {
+            conn: self.conn,
+            reader_hint,
+            #[cfg(feature = "deflate")]
+            content_encoding: self.content_encoding,
+            body_buf,
+            raw_body_read: self.raw_body_read,
+            max_body_size: self.config.max_body_size,
+            utf8: self.utf8,
+        };
+        (head, body)
+    }
+
+    /// Take over the connection after a `101 Switching Protocols` response, e.g. to a request using
+    /// [`RequestBuilder::upgrade()`](crate::request::RequestBuilder::upgrade).
+    ///
+    /// Returns [`Error::UpgradeRejected`] if the server responded with another status, in which case the response is
+    /// dropped without reading its body, so the connection cannot be reused.
+    pub fn upgrade(self) -> Result<Upgraded<'resp, 'buf, C>, Error> {
+        if self.status != Status::SwitchingProtocols {
+            return Err(Error::UpgradeRejected(self.status));
+        }
+        let (conn, buf, len) = self.into_upgraded();
+        Ok(Upgraded {
+            conn,
+            buffered: &mut buf[..len],
+        })
+    }
+
+    /// Take the connection after a `101 Switching Protocols` response, with the bytes of the new protocol that were
+    /// already read moved to the beginning of the header buffer, and the number of those bytes.
+    pub(crate) fn into_upgraded(self) -> (&'resp mut C, &'buf mut [u8], usize) {
+        self.header_buf
+            .copy_within(self.header_len..self.header_len + self.raw_body_read, 0);
+        (self.conn, self.header_buf, self.raw_body_read)
+    }
+}
+
+impl<C, const MAX_HEADERS: usize> Response<'_, '_, C, MAX_HEADERS>
+where
+    C: Read,
+{
+    /// Format the response using `Debug` or `defmt`, with the values of the `sensitive` headers masked.
+    ///
+    /// The `Debug` and `defmt` implementations of the response mask the [`SENSITIVE_HEADERS`].
+    pub fn redacted<'a>(&'a self, sensitive: &'a [&'a str]) -> Redacted<'a, Self> {
+        Redacted { value: self, sensitive }
+    }
+}
+
+impl<C, const MAX_HEADERS: usize> core::fmt::Debug for Response<'_, '_, C, MAX_HEADERS>
+where
+    C: Read,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.redacted(SENSITIVE_HEADERS).fmt(f)
+    }
+}
+
+impl<C, const MAX_HEADERS: usize> core::fmt::Debug for Redacted<'_, Response<'_, '_, C, MAX_HEADERS>>
+where
+    C: Read,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let response = self.value;
+        f.debug_struct("Response")
+            .field("method", &response.method)
+            .field("status", &response.status)
+            .field("version", &response.version)
+            .field("content_type", &response.content_type)
+            .field("content_length", &response.content_length)
+            .field("transfer_encoding", &response.transfer_encoding)
+            .field("keep_alive", &response.keep_alive)
+            .field("content_encoding", &response.content_encoding)
+            .field("connection", &response.connection)
+            .field("headers", &HeaderList(|| self.headers()))
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<C, const MAX_HEADERS: usize> defmt::Format for Response<'_, '_, C, MAX_HEADERS>
+where
+    C: Read,
+{
+    fn format(&self, fmt: defmt::Formatter) {
+        self.redacted(SENSITIVE_HEADERS).format(fmt)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<C, const MAX_HEADERS: usize> defmt::Format for Redacted<'_, Response<'_, '_, C, MAX_HEADERS>>
+where
+    C: Read,
+{
+    fn format(&self, fmt: defmt::Formatter) {
+        let response = self.value;
+        defmt::write!(
+            fmt,
+            "Response {{ method: {}, status: {}, version: {}, content_type: {}, content_length: {}, transfer_encoding: {}, keep_alive: {}, content_encoding: {}, connection: {}, headers: {} }}",
+            response.method,
+            response.status,
+            response.version,
+            response.content_type,
+            response.content_length,
+            response.transfer_encoding,
+            response.keep_alive,
+            response.content_encoding,
+            response.connection,
+            HeaderList(|| self.headers()),
+        )
+    }
+}
+
+impl<'a, C, const MAX_HEADERS: usize> Redacted<'a, Response<'_, '_, C, MAX_HEADERS>>
+where
+    C: Read,
+{
+    fn headers(&self) -> impl Iterator<Item = (&'a str, HeaderValue<'a>)> + '_ {
+        self.value
+            .headers()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (name, self.header(name, value)))
+    }
+}
+
+/// Parse a `Content-Length` value, which must consist of decimal digits only.
+///
+/// Signs, whitespace and lists of values are rejected, as are values that do not fit in a `u64`.
+fn parse_content_length(value: &[u8]) -> Option<u64> {
+    if value.is_empty() {
+        return None;
+    }
+    value.iter().try_fold(0u64, |len, b| {
+        if b.is_ascii_digit() {
+            len.checked_mul(10)?.checked_add((b - b'0') as u64)
+        } else {
+            None
+        }
+    })
+}
+
+/// The metadata of a resource, such as the response to a `HEAD` request, used e.g. to check for an update
+/// without downloading it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The length of the resource, if known.
+    pub content_length: Option<u64>,
+    /// The `ETag` of the resource, unless it is longer than [`MAX_ETAG_LEN`].
+    pub etag: Option<String<MAX_ETAG_LEN>>,
+    /// The `Last-Modified` header as the number of seconds since the Unix epoch.
+    pub last_modified: Option<u64>,
+    /// Whether the server advertises support for byte ranges.
+    pub accept_ranges: bool,
+}
+
+impl Metadata {
+    /// Get the metadata of the resource from the headers of a response.
+    pub fn from_response<C, const MAX_HEADERS: usize>(response: &Response<'_, '_, C, MAX_HEADERS>) -> Self
+    where
+        C: Read,
+    {
+        Self {
+            status: response.status,
+            content_length: response.content_length,
+            etag: response
+                .header("etag")
+                .and_then(|etag| core::str::from_utf8(etag).ok())
+                .and_then(|etag| String::try_from(etag).ok()),
+            last_modified: response.last_modified(),
+            accept_ranges: response.accepts_ranges(),
         }
     }
 }
 
-pub struct HeaderIterator<'a>(usize, [httparse::Header<'a>; 64]);
+pub struct HeaderIterator<'a, const MAX_HEADERS: usize = 64>(usize, [httparse::Header<'a>; MAX_HEADERS]);
 
-impl<'a> Iterator for HeaderIterator<'a> {
+impl<'a, const MAX_HEADERS: usize> Iterator for HeaderIterator<'a, MAX_HEADERS> {
     type Item = (&'a str, &'a [u8]);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -189,6 +689,50 @@ impl<'a> Iterator for HeaderIterator<'a> {
     }
 }
 
+/// The connection after a `101 Switching Protocols` response, which is taken over by the protocol switched to.
+pub struct Upgraded<'resp, 'buf, C> {
+    /// The connection.
+    pub conn: &'resp mut C,
+    /// The bytes of the new protocol that were read together with the response headers, which come before the
+    /// bytes that are read from the connection.
+    pub buffered: &'buf mut [u8],
+}
+
+/// The status and headers of a response, which remain accessible while its body is read.
+///
+/// Created with [`Response::into_parts()`].
+pub struct ResponseHead<'buf, const MAX_HEADERS: usize = 64> {
+    /// The HTTP response status code.
+    pub status: StatusCode,
+    header: &'buf [u8],
+    config: ResponseConfig,
+}
+
+impl<const MAX_HEADERS: usize> ResponseHead<'_, MAX_HEADERS> {
+    /// Get the response headers
+    pub fn headers(&self) -> HeaderIterator<'_, MAX_HEADERS> {
+        let mut iterator = HeaderIterator(0, [httparse::EMPTY_HEADER; MAX_HEADERS]);
+        let mut response = httparse::Response::new(self.config.limit_headers(&mut iterator.1));
+        self.config.parse(&mut response, self.header).unwrap();
+
+        iterator
+    }
+
+    /// Get the value of the first header with the provided name, which is compared case-insensitively
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers()
+            .find(|(header, _)| !header.is_empty() && header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Get the values of all headers with the provided name, which is compared case-insensitively
+    pub fn headers_matching<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.headers()
+            .filter(move |(header, _)| !header.is_empty() && header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
 /// Response body
 ///
 /// This type contains the original header buffer provided to `read_headers`,
@@ -200,8 +744,14 @@ where
 {
     conn: &'resp mut C,
     reader_hint: ReaderHint,
+    #[cfg(feature = "deflate")]
+    content_encoding: Option<ContentEncoding>,
     /// The number of raw bytes read from the body and available in the beginning of `body_buf`.
     raw_body_read: usize,
+    /// The maximum size of chunked bodies and bodies that end when the connection is closed.
+    max_body_size: u64,
+    /// Whether the charset of the content type is UTF-8.
+    utf8: bool,
     /// The buffer initially provided to read the header.
     pub body_buf: &'buf mut [u8],
 }
@@ -209,21 +759,24 @@ where
 #[derive(Clone, Copy)]
 enum ReaderHint {
     Empty,
-    FixedLength(usize),
-    Chunked,
+    FixedLength(u64),
+    Chunked { allow_bare_lf: bool },
     ToEnd, // https://www.rfc-editor.org/rfc/rfc7230#section-3.3.3 pt. 7: Until end of connection
 }
 
 impl ReaderHint {
-    fn reader<R: Read>(self, raw_body: R) -> BodyReader<R> {
+    fn reader<R: Read>(self, raw_body: R, max_body_size: u64) -> BodyReader<R> {
         match self {
             ReaderHint::Empty => BodyReader::Empty,
-            ReaderHint::FixedLength(content_length) => BodyReader::FixedLength(FixedLengthBodyReader {
-                raw_body,
-                remaining: content_length,
-            }),
-            ReaderHint::Chunked => BodyReader::Chunked(ChunkedBodyReader::new(raw_body)),
-            ReaderHint::ToEnd => BodyReader::ToEnd(raw_body),
+            ReaderHint::FixedLength(content_length) => {
+                BodyReader::FixedLength(FixedLengthBodyReader::new(raw_body, content_length))
+            }
+            ReaderHint::Chunked { allow_bare_lf } => BodyReader::Chunked(
+                ChunkedBodyReader::new(raw_body)
+                    .allow_bare_lf(allow_bare_lf)
+                    .max_body_size(max_body_size),
+            ),
+            ReaderHint::ToEnd => BodyReader::ToEnd(ToEndBodyReader::new(raw_body).max_body_size(max_body_size)),
         }
     }
 }
@@ -232,10 +785,26 @@ impl<'resp, 'buf, C> ResponseBody<'resp, 'buf, C>
 where
     C: Read,
 {
+    /// Get a reader for the body as it is received, i.e. with any `Content-Encoding` still applied.
     pub fn reader(self) -> BodyReader<BufferingReader<'resp, 'buf, C>> {
         let raw_body = BufferingReader::new(self.body_buf, self.raw_body_read, self.conn);
 
-        self.reader_hint.reader(raw_body)
+        self.reader_hint.reader(raw_body, self.max_body_size)
+    }
+
+    /// Get a reader that decodes a `deflate` encoded body, or passes the body through if it is not encoded.
+    ///
+    /// The `window` is used as the dictionary for the decompression and must have a size that is a power of two,
+    /// otherwise reading fails with [`Error::BufferTooSmall`].
+    /// Bodies compressed with the default zlib settings require a window of 32 KiB.
+    #[cfg(feature = "deflate")]
+    pub fn decoded_reader<'w>(self, window: &'w mut [u8]) -> DecodedBodyReader<'w, BufferingReader<'resp, 'buf, C>> {
+        let content_encoding = self.content_encoding;
+        let reader = self.reader();
+        match content_encoding {
+            Some(ContentEncoding::Deflate) => DecodedBodyReader::Deflate(InflateBodyReader::new(reader, window)),
+            _ => DecodedBodyReader::Identity(reader),
+        }
     }
 }
 
@@ -249,22 +818,33 @@ where
         match self.reader_hint {
             ReaderHint::Empty => Ok(&mut []),
             ReaderHint::FixedLength(content_length) => {
-                let read = BodyReader::FixedLength(FixedLengthBodyReader {
-                    raw_body: self.conn,
-                    remaining: content_length - self.raw_body_read,
-                })
-                .read_to_end(&mut self.body_buf[self.raw_body_read..])
+                let read = BodyReader::FixedLength(FixedLengthBodyReader::new(
+                    self.conn,
+                    content_length - self.raw_body_read as u64,
+                ))
+                .read_all(&mut self.body_buf[self.raw_body_read..])
                 .await?;
 
                 Ok(&mut self.body_buf[..read + self.raw_body_read])
             }
-            ReaderHint::Chunked => {
+            ReaderHint::Chunked { allow_bare_lf } => {
                 let raw_body = BufferingReader::new(self.body_buf, self.raw_body_read, self.conn);
-                ChunkedBodyReader::new(raw_body).read_to_end().await
+                ChunkedBodyReader::new(raw_body)
+                    .allow_bare_lf(allow_bare_lf)
+                    .max_body_size(self.max_body_size)
+                    .read_to_end()
+                    .await
             }
             ReaderHint::ToEnd => {
-                let read = BodyReader::ToEnd(&mut self.conn)
-                    .read_to_end(&mut self.body_buf[self.raw_body_read..])
+                let remaining = self
+                    .max_body_size
+                    .checked_sub(self.raw_body_read as u64)
+                    .ok_or_else(|| {
+                        warn!("ToEnd: Response body exceeds the maximum size");
+                        Error::BodyTooLarge
+                    })?;
+                let read = BodyReader::ToEnd(ToEndBodyReader::new(&mut self.conn).max_body_size(remaining))
+                    .read_all(&mut self.body_buf[self.raw_body_read..])
                     .await?;
 
                 Ok(&mut self.body_buf[..read + self.raw_body_read])
@@ -275,9 +855,54 @@ where
     /// Discard the entire body
     ///
     /// Returns the number of discarded body bytes
-    pub async fn discard(self) -> Result<usize, Error> {
+    pub async fn discard(self) -> Result<u64, Error> {
         self.reader().discard().await
     }
+
+    /// Read the entire body into the buffer originally provided [`Response::read()`], and decode it as text.
+    ///
+    /// Returns [`Error::UnsupportedCharset`] without reading the body if the `charset` of the content type is not
+    /// UTF-8 or a subset of it, and [`Error::Codec`] if the body is not valid UTF-8.
+    pub async fn text(self) -> Result<&'buf str, Error> {
+        if !self.utf8 {
+            warn!("Response charset is not UTF-8");
+            return Err(Error::UnsupportedCharset);
+        }
+        let body = self.read_to_end().await?;
+        Ok(core::str::from_utf8(body)?)
+    }
+
+    /// Copy the entire body to `writer` as it is received, e.g. to storage or a serial port, using the buffer
+    /// originally provided [`Response::read()`].
+    ///
+    /// Returns the number of bytes copied. The writer is not flushed.
+    pub async fn copy_to<W: Write>(self, writer: &mut W) -> Result<u64, Error> {
+        let mut reader = self.reader();
+        let mut copied = 0;
+        loop {
+            let buf = reader.fill_buf().await?;
+            if buf.is_empty() {
+                break;
+            }
+            let len = buf.len();
+            writer.write_all(buf).await.map_err(|e| e.kind())?;
+            reader.consume(len);
+            copied += len as u64;
+        }
+        Ok(copied)
+    }
+
+    /// Read the entire body into the buffer originally provided [`Response::read()`], and decode it as CBOR.
+    ///
+    /// The decoded value may borrow from the buffer. Decoding errors are returned as [`Error::Codec`].
+    #[cfg(feature = "cbor")]
+    pub async fn cbor<T>(self) -> Result<T, Error>
+    where
+        T: minicbor::Decode<'buf, ()>,
+    {
+        let body = self.read_to_end().await?;
+        minicbor::decode(body).map_err(|_| Error::Codec)
+    }
 }
 
 /// A body reader
@@ -285,7 +910,7 @@ pub enum BodyReader<B> {
     Empty,
     FixedLength(FixedLengthBodyReader<B>),
     Chunked(ChunkedBodyReader<B>),
-    ToEnd(B),
+    ToEnd(ToEndBodyReader<B>),
 }
 
 impl<B> BodyReader<B>
@@ -301,8 +926,55 @@ where
         }
     }
 
-    /// Read the entire body
-    pub async fn read_to_end(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+    /// Get the number of body bytes read or consumed so far.
+    ///
+    /// When reading the body fails, e.g. with [`Error::ConnectionAborted`], this is the offset at which a download
+    /// can be resumed.
+    pub fn consumed(&self) -> u64 {
+        match self {
+            BodyReader::Empty => 0,
+            BodyReader::FixedLength(reader) => reader.consumed(),
+            BodyReader::Chunked(reader) => reader.consumed(),
+            BodyReader::ToEnd(reader) => reader.consumed(),
+        }
+    }
+
+    /// Get the trailer fields of a chunked body.
+    ///
+    /// The trailers are only available once the entire body is read.
+    pub fn trailers(&self) -> Option<TrailerIterator<'_>> {
+        match self {
+            BodyReader::Chunked(reader) => Some(reader.trailers()),
+            _ => None,
+        }
+    }
+
+    /// Read exactly enough bytes of the body to fill `buf`.
+    ///
+    /// Returns [`Error::UnexpectedEndOfBody`] if the body ends before `buf` is filled, or
+    /// [`Error::ConnectionAborted`] if the connection is closed before the end of the body.
+    pub async fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            match self.read(buf).await? {
+                0 => return Err(self.end_of_body()),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    /// The error when the body ended before the requested number of bytes were read.
+    fn end_of_body(&self) -> Error {
+        match self {
+            BodyReader::FixedLength(reader) if reader.remaining > 0 => Error::ConnectionAborted,
+            _ => Error::UnexpectedEndOfBody,
+        }
+    }
+
+    /// Read the entire body using `read()`, for readers that are not buffered.
+    ///
+    /// Returns the same errors as [`BodyReader::read_to_end()`].
+    pub async fn read_all(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         let mut len = 0;
         while len < buf.len() {
             match self.read(&mut buf[len..]).await {
@@ -312,17 +984,26 @@ where
             }
         }
 
+        self.end_of_buffer(len, buf.len()).await?;
+        Ok(len)
+    }
+
+    /// Check that the entire body was read after `len` bytes were read into a buffer of `buf_len` bytes.
+    async fn end_of_buffer(&mut self, len: usize, buf_len: usize) -> Result<(), Error> {
         if !self.is_done() {
             let more = match self {
                 BodyReader::FixedLength(reader) => {
                     warn!("FixedLength: {} bytes remained", reader.remaining);
                     true
                 }
-                BodyReader::ToEnd(reader) if len == buf.len() => {
+                BodyReader::ToEnd(reader) if len == buf_len => {
                     warn!("ToEnd: Buffer full, waiting to see if there is unread data.");
 
                     let mut b = [0];
-                    matches!(reader.read(&mut b).await, Ok(1))
+                    match reader.read(&mut b).await {
+                        Err(Error::BodyTooLarge) => return Err(Error::BodyTooLarge),
+                        result => matches!(result, Ok(1)),
+                    }
                 }
 
                 BodyReader::ToEnd(_) => false,
@@ -334,40 +1015,214 @@ where
             }
         }
 
-        Ok(len)
+        Ok(())
+    }
+
+    /// Read the entire body, appending it to `vec`, which grows as needed.
+    ///
+    /// Returns the number of bytes appended. The bytes read before an error remain appended.
+    #[cfg(feature = "alloc")]
+    pub async fn read_to_end_vec(&mut self, vec: &mut alloc::vec::Vec<u8>) -> Result<usize, Error> {
+        let start = vec.len();
+        loop {
+            if vec.len() == vec.capacity() {
+                vec.reserve(512);
+            }
+            let len = vec.len();
+            vec.resize(vec.capacity(), 0);
+            let result = self.read(&mut vec[len..]).await;
+            vec.truncate(len + *result.as_ref().unwrap_or(&0));
+            if result? == 0 {
+                break;
+            }
+        }
+        Ok(vec.len() - start)
     }
 
-    async fn discard(&mut self) -> Result<usize, Error> {
+    /// Discard the entire body, reading it into `scratch`, for readers that are not buffered.
+    ///
+    /// Returns the number of discarded body bytes. A larger `scratch` requires fewer reads.
+    pub async fn discard_with(&mut self, scratch: &mut [u8]) -> Result<u64, Error> {
         let mut body_len = 0;
-        let mut buf = [0; 128];
         loop {
-            let buf = self.read(&mut buf).await?;
-            if buf == 0 {
+            let len = self.read(scratch).await?;
+            if len == 0 {
                 break;
             }
-            body_len += buf;
+            body_len += len as u64;
         }
 
         Ok(body_len)
     }
 }
 
-impl<B> ErrorType for BodyReader<B> {
-    type Error = Error;
-}
-
-impl<B> Read for BodyReader<B>
+impl<B> BodyReader<B>
 where
-    B: Read,
+    B: BufRead + Read,
 {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        match self {
-            BodyReader::Empty => Ok(0),
-            BodyReader::FixedLength(reader) => reader.read(buf).await,
-            BodyReader::Chunked(reader) => reader.read(buf).await,
-            BodyReader::ToEnd(conn) => conn.read(buf).await.map_err(|e| Error::Network(e.kind())),
-        }
-    }
+    /// Read the entire body
+    ///
+    /// The body is copied from the buffer of the underlying reader, such as a decrypted TLS record, into `buf` using
+    /// `fill_buf()` and `consume()`, such that each byte is copied once.
+    pub async fn read_to_end(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut len = 0;
+        while len < buf.len() {
+            let data = self.fill_buf().await?;
+            if data.is_empty() {
+                break;
+            }
+            let n = data.len().min(buf.len() - len);
+            buf[len..len + n].copy_from_slice(&data[..n]);
+            self.consume(n);
+            len += n;
+        }
+
+        self.end_of_buffer(len, buf.len()).await?;
+        Ok(len)
+    }
+
+    /// Skip the next `n` bytes of the body.
+    ///
+    /// The bytes are consumed from the buffer of the underlying reader without copying them. Returns the same errors
+    /// as [`BodyReader::read_exact()`] if the body ends before `n` bytes are skipped.
+    pub async fn skip(&mut self, mut n: u64) -> Result<(), Error> {
+        while n > 0 {
+            let len = self.fill_buf().await?.len();
+            if len == 0 {
+                return Err(self.end_of_body());
+            }
+            let len = n.min(len as u64) as usize;
+            self.consume(len);
+            n -= len as u64;
+        }
+        Ok(())
+    }
+
+    /// Discard the entire body
+    ///
+    /// The body is consumed from the buffer of the underlying reader without copying it.
+    /// Returns the number of discarded body bytes.
+    pub async fn discard(&mut self) -> Result<u64, Error> {
+        let mut body_len = 0;
+        loop {
+            let len = self.fill_buf().await?.len();
+            if len == 0 {
+                break;
+            }
+            self.consume(len);
+            body_len += len as u64;
+        }
+
+        Ok(body_len)
+    }
+
+    /// Read the entire body, appending it to `vec`.
+    ///
+    /// Returns the number of bytes appended, or [`Error::BufferTooSmall`] if the body does not fit in the remaining
+    /// capacity of `vec`, in which case `vec` is left unchanged.
+    pub async fn read_to_vec<const N: usize>(&mut self, vec: &mut Vec<u8, N>) -> Result<usize, Error> {
+        let start = vec.len();
+        vec.resize_default(N).unwrap();
+        let result = self.read_to_end(&mut vec[start..]).await;
+        vec.truncate(start + *result.as_ref().unwrap_or(&0));
+        result
+    }
+
+    /// Read the next line of the body into `buf`, without the `\n` or `\r\n` line ending.
+    ///
+    /// Lines may be split across reads and chunks of the body. Returns `None` at the end of the body, and
+    /// [`Error::BufferTooSmall`] if the line does not fit in `buf`.
+    pub async fn read_line<'b>(&mut self, buf: &'b mut [u8]) -> Result<Option<&'b [u8]>, Error> {
+        let mut len = 0;
+        loop {
+            let data = self.fill_buf().await?;
+            if data.is_empty() {
+                if len == 0 {
+                    return Ok(None);
+                }
+                break;
+            }
+
+            let end = data.iter().position(|&b| b == b'\n');
+            let line = &data[..end.unwrap_or(data.len())];
+            buf.get_mut(len..len + line.len())
+                .ok_or(Error::BufferTooSmall)?
+                .copy_from_slice(line);
+            len += line.len();
+
+            let consumed = line.len() + end.map_or(0, |_| 1);
+            self.consume(consumed);
+            if end.is_some() {
+                break;
+            }
+        }
+
+        let line = &buf[..len];
+        Ok(Some(line.strip_suffix(b"\r").unwrap_or(line)))
+    }
+}
+
+impl<C> BodyReader<BufferingReader<'_, '_, C>>
+where
+    C: TryBufRead,
+{
+    /// Fill the buffer until it contains at least `n` bytes of the body, without consuming them.
+    ///
+    /// Fewer bytes are returned if the body ends, or if `n` exceeds the size of the buffer passed to
+    /// [`Response::read()`]. The bytes of a chunked body are limited to the current chunk.
+    pub async fn fill_at_least(&mut self, n: usize) -> Result<&[u8], Error> {
+        match self {
+            BodyReader::Empty => Ok(&[]),
+            BodyReader::FixedLength(reader) => {
+                if reader.remaining == 0 {
+                    return Ok(&[]);
+                }
+                let remaining = reader.remaining_len();
+                let loaded = reader.raw_body.fill_at_least(n.min(remaining)).await?;
+                if loaded.is_empty() {
+                    return Err(Error::ConnectionAborted);
+                }
+                Ok(&loaded[..loaded.len().min(remaining)])
+            }
+            BodyReader::Chunked(reader) => reader.fill_at_least(n).await,
+            BodyReader::ToEnd(reader) => {
+                let remaining = reader.remaining;
+                let loaded = reader.raw_body.fill_at_least(n).await?;
+                if loaded.len() as u64 > remaining {
+                    warn!("ToEnd: Response body exceeds the maximum size");
+                    return Err(Error::BodyTooLarge);
+                }
+                Ok(loaded)
+            }
+        }
+    }
+
+    /// Get up to the next `n` bytes of the body without consuming them, e.g. to detect the format of the body from
+    /// its first bytes.
+    ///
+    /// See [`BodyReader::fill_at_least()`] for when fewer bytes are returned.
+    pub async fn peek(&mut self, n: usize) -> Result<&[u8], Error> {
+        let data = self.fill_at_least(n).await?;
+        Ok(&data[..data.len().min(n)])
+    }
+}
+
+impl<B> ErrorType for BodyReader<B> {
+    type Error = Error;
+}
+
+impl<B> Read for BodyReader<B>
+where
+    B: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self {
+            BodyReader::Empty => Ok(0),
+            BodyReader::FixedLength(reader) => reader.read(buf).await,
+            BodyReader::Chunked(reader) => reader.read(buf).await,
+            BodyReader::ToEnd(reader) => reader.read(buf).await,
+        }
+    }
 }
 
 impl<B> BufRead for BodyReader<B>
@@ -379,7 +1234,7 @@ where
             BodyReader::Empty => Ok(&[]),
             BodyReader::FixedLength(reader) => reader.fill_buf().await,
             BodyReader::Chunked(reader) => reader.fill_buf().await,
-            BodyReader::ToEnd(conn) => conn.fill_buf().await.map_err(|e| Error::Network(e.kind())),
+            BodyReader::ToEnd(reader) => reader.fill_buf().await,
         }
     }
 
@@ -388,7 +1243,7 @@ where
             BodyReader::Empty => {}
             BodyReader::FixedLength(reader) => reader.consume(amt),
             BodyReader::Chunked(reader) => reader.consume(amt),
-            BodyReader::ToEnd(conn) => conn.consume(amt),
+            BodyReader::ToEnd(reader) => reader.consume(amt),
         }
     }
 }
@@ -432,30 +1287,68 @@ impl PartialEq<Status> for StatusCode {
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Status {
+    Continue = 100,
+    SwitchingProtocols = 101,
+    Processing = 102,
+    EarlyHints = 103,
     Ok = 200,
     Created = 201,
     Accepted = 202,
+    NonAuthoritativeInformation = 203,
     NoContent = 204,
+    ResetContent = 205,
     PartialContent = 206,
+    MultiStatus = 207,
+    AlreadyReported = 208,
+    ImUsed = 226,
+    MultipleChoices = 300,
     MovedPermanently = 301,
     Found = 302,
     SeeOther = 303,
     NotModified = 304,
+    UseProxy = 305,
     TemporaryRedirect = 307,
     PermanentRedirect = 308,
     BadRequest = 400,
     Unauthorized = 401,
+    PaymentRequired = 402,
     Forbidden = 403,
     NotFound = 404,
     MethodNotAllowed = 405,
+    NotAcceptable = 406,
+    ProxyAuthenticationRequired = 407,
+    RequestTimeout = 408,
     Conflict = 409,
+    Gone = 410,
+    LengthRequired = 411,
+    PreconditionFailed = 412,
+    ContentTooLarge = 413,
+    UriTooLong = 414,
     UnsupportedMediaType = 415,
     RangeNotSatisfiable = 416,
+    ExpectationFailed = 417,
+    ImATeapot = 418,
+    MisdirectedRequest = 421,
+    UnprocessableContent = 422,
+    Locked = 423,
+    FailedDependency = 424,
+    TooEarly = 425,
+    UpgradeRequired = 426,
+    PreconditionRequired = 428,
     TooManyRequests = 429,
+    RequestHeaderFieldsTooLarge = 431,
+    UnavailableForLegalReasons = 451,
     InternalServerError = 500,
+    NotImplemented = 501,
     BadGateway = 502,
     ServiceUnavailable = 503,
     GatewayTimeout = 504,
+    HttpVersionNotSupported = 505,
+    VariantAlsoNegotiates = 506,
+    InsufficientStorage = 507,
+    LoopDetected = 508,
+    NotExtended = 510,
+    NetworkAuthenticationRequired = 511,
     Unknown = 0,
 }
 
@@ -479,6 +1372,76 @@ impl StatusCode {
     pub fn is_server_error(&self) -> bool {
         (500..=599).contains(&self.0)
     }
+
+    /// Get the standard reason phrase of the status code, if it is registered with IANA.
+    pub fn canonical_reason(&self) -> Option<&'static str> {
+        let reason = match self.0 {
+            100 => "Continue",
+            101 => "Switching Protocols",
+            102 => "Processing",
+            103 => "Early Hints",
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            203 => "Non-Authoritative Information",
+            204 => "No Content",
+            205 => "Reset Content",
+            206 => "Partial Content",
+            207 => "Multi-Status",
+            208 => "Already Reported",
+            226 => "IM Used",
+            300 => "Multiple Choices",
+            301 => "Moved Permanently",
+            302 => "Found",
+            303 => "See Other",
+            304 => "Not Modified",
+            305 => "Use Proxy",
+            307 => "Temporary Redirect",
+            308 => "Permanent Redirect",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            402 => "Payment Required",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            406 => "Not Acceptable",
+            407 => "Proxy Authentication Required",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            410 => "Gone",
+            411 => "Length Required",
+            412 => "Precondition Failed",
+            413 => "Content Too Large",
+            414 => "URI Too Long",
+            415 => "Unsupported Media Type",
+            416 => "Range Not Satisfiable",
+            417 => "Expectation Failed",
+            418 => "I'm a teapot",
+            421 => "Misdirected Request",
+            422 => "Unprocessable Content",
+            423 => "Locked",
+            424 => "Failed Dependency",
+            425 => "Too Early",
+            426 => "Upgrade Required",
+            428 => "Precondition Required",
+            429 => "Too Many Requests",
+            431 => "Request Header Fields Too Large",
+            451 => "Unavailable For Legal Reasons",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            505 => "HTTP Version Not Supported",
+            506 => "Variant Also Negotiates",
+            507 => "Insufficient Storage",
+            508 => "Loop Detected",
+            510 => "Not Extended",
+            511 => "Network Authentication Required",
+            _ => return None,
+        };
+        Some(reason)
+    }
 }
 
 impl PartialEq<StatusCode> for Status {
@@ -496,84 +1459,650 @@ impl From<u16> for Status {
 impl From<StatusCode> for Status {
     fn from(from: StatusCode) -> Status {
         match from.0 {
+            100 => Status::Continue,
+            101 => Status::SwitchingProtocols,
+            102 => Status::Processing,
+            103 => Status::EarlyHints,
             200 => Status::Ok,
             201 => Status::Created,
             202 => Status::Accepted,
+            203 => Status::NonAuthoritativeInformation,
             204 => Status::NoContent,
+            205 => Status::ResetContent,
             206 => Status::PartialContent,
+            207 => Status::MultiStatus,
+            208 => Status::AlreadyReported,
+            226 => Status::ImUsed,
+            300 => Status::MultipleChoices,
             301 => Status::MovedPermanently,
             302 => Status::Found,
             303 => Status::SeeOther,
             304 => Status::NotModified,
+            305 => Status::UseProxy,
             307 => Status::TemporaryRedirect,
             308 => Status::PermanentRedirect,
             400 => Status::BadRequest,
             401 => Status::Unauthorized,
+            402 => Status::PaymentRequired,
             403 => Status::Forbidden,
             404 => Status::NotFound,
             405 => Status::MethodNotAllowed,
+            406 => Status::NotAcceptable,
+            407 => Status::ProxyAuthenticationRequired,
+            408 => Status::RequestTimeout,
             409 => Status::Conflict,
+            410 => Status::Gone,
+            411 => Status::LengthRequired,
+            412 => Status::PreconditionFailed,
+            413 => Status::ContentTooLarge,
+            414 => Status::UriTooLong,
             415 => Status::UnsupportedMediaType,
             416 => Status::RangeNotSatisfiable,
+            417 => Status::ExpectationFailed,
+            418 => Status::ImATeapot,
+            421 => Status::MisdirectedRequest,
+            422 => Status::UnprocessableContent,
+            423 => Status::Locked,
+            424 => Status::FailedDependency,
+            425 => Status::TooEarly,
+            426 => Status::UpgradeRequired,
+            428 => Status::PreconditionRequired,
             429 => Status::TooManyRequests,
+            431 => Status::RequestHeaderFieldsTooLarge,
+            451 => Status::UnavailableForLegalReasons,
             500 => Status::InternalServerError,
+            501 => Status::NotImplemented,
             502 => Status::BadGateway,
             503 => Status::ServiceUnavailable,
             504 => Status::GatewayTimeout,
-            n => {
-                warn!("Unknown status code: {:?}", n);
-                Status::Unknown
-            }
+            505 => Status::HttpVersionNotSupported,
+            506 => Status::VariantAlsoNegotiates,
+            507 => Status::InsufficientStorage,
+            508 => Status::LoopDetected,
+            510 => Status::NotExtended,
+            511 => Status::NetworkAuthenticationRequired,
+            _ => Status::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+    use embedded_io_async::{BufRead, Read};
+
+    use super::{Status, StatusCode};
+    use crate::{
+        headers::ContentEncoding,
+        reader::BufferingReader,
+        request::Method,
+        response::{chunked::ChunkedBodyReader, BodyReader, FixedLengthBodyReader, Metadata, Response, ResponseConfig},
+        Error, TryBufRead,
+    };
+
+    #[tokio::test]
+    async fn debug_masks_sensitive_headers() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nSet-Cookie: session=secret\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        let formatted = format!("{:?}", response);
+        assert!(!formatted.contains("secret"));
+        assert!(formatted.contains(r#"("Set-Cookie", ***)"#));
+        assert!(formatted.contains(r#"("Content-Length", "0")"#));
+    }
+
+    #[tokio::test]
+    async fn can_read_no_content() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::POST, &mut response_buf)
+            .await
+            .unwrap();
+
+        assert_eq!(b"", response.body().read_to_end().await.unwrap());
+        assert!(conn.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn can_read_no_content_with_zero_content_length() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::POST, &mut response_buf)
+            .await
+            .unwrap();
+
+        assert_eq!(b"", response.body().read_to_end().await.unwrap());
+        assert!(conn.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn cannot_read_no_content_with_nonzero_content_length() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\nContent-Length: 5\r\n\r\nHELLO");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::POST, &mut response_buf).await;
+
+        assert!(matches!(response, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn can_read_not_modified_with_content_length() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 304 Not Modified\r\nContent-Length: 5\r\n\r\n");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert_eq!(b"", response.body().read_to_end().await.unwrap());
+        assert!(conn.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn can_read_content_encoding() {
+        let mut conn =
+            FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 0\r\n\r\n");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert_eq!(Some(ContentEncoding::Gzip), response.content_encoding);
+    }
+
+    #[tokio::test]
+    async fn can_read_metadata() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 1024\r\nETag: \"v1\"\r\nLast-Modified: Sun, 06 Nov 1994 08:49:37 GMT\r\nAccept-Ranges: bytes\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::HEAD, &mut response_buf)
+            .await
+            .unwrap();
+        let metadata = Metadata::from_response(&response);
+
+        assert_eq!(Status::Ok, metadata.status);
+        assert_eq!(Some(1024), metadata.content_length);
+        assert_eq!(Some("\"v1\""), metadata.etag.as_deref());
+        assert_eq!(Some(784111777), metadata.last_modified);
+        assert!(metadata.accept_ranges);
+        assert_eq!(0, response.body().discard().await.unwrap());
+    }
+
+    #[cfg(feature = "integrity")]
+    #[tokio::test]
+    async fn can_read_content_digest() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-MD5: Nh+t8ccS6BLRmMTKtXEqeQ==\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert_eq!(
+            Some(crate::integrity::Digest::Md5([
+                0x36, 0x1f, 0xad, 0xf1, 0xc7, 0x12, 0xe8, 0x12, 0xd1, 0x98, 0xc4, 0xca, 0xb5, 0x71, 0x2a, 0x79
+            ])),
+            response.content_digest()
+        );
+
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-MD5: Nh+t8ccS6BLRmMTKtXEqeQ==\r\nContent-Digest: sha-512=:AAAA:, sha-256=:eH7Hbcr9IMGQjrCTahL5Ht0QWrXNfswrGuIDJkg0Xf8=:\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert!(matches!(
+            response.content_digest(),
+            Some(crate::integrity::Digest::Sha256(_))
+        ));
+
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Digest: sha-512=:AAAA:\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert_eq!(None, response.content_digest());
+    }
+
+    #[tokio::test]
+    async fn can_read_partial_content() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 206 Partial Content\r\nAccept-Ranges: bytes\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 6\r\n\r\n456789",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        assert!(response.accepts_ranges());
+        let range = response.content_range().unwrap();
+        assert_eq!((4, 9, Some(10)), (range.start, range.end, range.complete_length));
+    }
+
+    #[tokio::test]
+    async fn cannot_read_partial_content_not_matching_range() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 10\r\n\r\n0123456789",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await;
+
+        assert!(matches!(response, Err(Error::InvalidContentLength)));
+    }
+
+    #[tokio::test]
+    async fn can_peek_body() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n{\"a\":true}");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        assert_eq!(b"{\"a", reader.peek(3).await.unwrap());
+        assert_eq!(b"{\"a\":true}", reader.peek(100).await.unwrap());
+
+        let mut body = [0; 20];
+        let len = reader.read_to_end(&mut body).await.unwrap();
+        assert_eq!(b"{\"a\":true}", &body[..len]);
+        assert!(reader.peek(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn can_peek_chunked_body() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nGIF8\r\n3\r\n9a!\r\n0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        // Only the bytes of the current chunk are returned
+        assert_eq!(b"GIF8", reader.peek(6).await.unwrap());
+        reader.consume(4);
+        assert_eq!(b"9a!", reader.peek(6).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_read_lines() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n7\r\nid,name\r\n8\r\n\r\n1,foo\n\r\n6\r\n\n2,bar\r\n0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        let mut line = [0; 16];
+        assert_eq!(Some(b"id,name".as_slice()), reader.read_line(&mut line).await.unwrap());
+        assert_eq!(Some(b"1,foo".as_slice()), reader.read_line(&mut line).await.unwrap());
+        assert_eq!(Some(b"".as_slice()), reader.read_line(&mut line).await.unwrap());
+        assert_eq!(Some(b"2,bar".as_slice()), reader.read_line(&mut line).await.unwrap());
+        assert_eq!(None, reader.read_line(&mut line).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn cannot_read_line_larger_than_buffer() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 12\r\n\r\nlong line\r\n");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        let mut line = [0; 4];
+        assert!(matches!(reader.read_line(&mut line).await, Err(Error::BufferTooSmall)));
+    }
+
+    #[tokio::test]
+    async fn can_read_raw_body_with_unsupported_content_encoding() {
+        for (encoding, expected) in [
+            ("GZip", ContentEncoding::Gzip),
+            ("br", ContentEncoding::Brotli),
+            ("gzip, identity", ContentEncoding::Gzip),
+            ("gzip, br", ContentEncoding::Unsupported),
+            ("zstd", ContentEncoding::Unsupported),
+        ] {
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: 5\r\n\r\nHELLO",
+                encoding
+            );
+            let mut conn = response.as_bytes();
+            let mut response_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+            assert_eq!(Some(expected), response.content_encoding);
+            assert_eq!(b"HELLO", response.body().read_to_end().await.unwrap());
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use core::convert::Infallible;
+    #[tokio::test]
+    async fn can_read_duplicate_identical_content_length() {
+        let mut conn =
+            FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nHELLO");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        assert_eq!(Some(5), response.content_length);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_content_length() {
+        for response in [
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: +5\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: -5\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5 5\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5, 5\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: \r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 99999999999999999999\r\n\r\nHELLO".as_slice(),
+        ] {
+            let mut conn = FakeSingleReadConnection::new(response);
+            let mut header_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut header_buf).await;
+
+            assert!(matches!(response, Err(Error::InvalidContentLength)));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_content_length_above_maximum() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 1025\r\n\r\nHELLO");
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().max_content_length(1024);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
+
+        assert!(matches!(response, Err(Error::InvalidContentLength)));
+    }
+
+    #[tokio::test]
+    async fn default_config_accepts_bare_lf_and_missing_reason_phrase() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200\nContent-Length: 5\n\nHELLO");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        assert_eq!(Status::Ok, response.status);
+
+        let body = response.body().read_to_end().await.unwrap();
+
+        assert_eq!(b"HELLO", body);
+    }
+
+    #[tokio::test]
+    async fn strict_config_rejects_non_conforming_responses() {
+        for response in [
+            b"HTTP/1.1 200 OK\nContent-Length: 0\r\n\r\n".as_slice(),
+            b"HTTP/1.1 200\r\nContent-Length: 0\r\n\r\n".as_slice(),
+            b"HTTP/1.1  200 OK\r\nContent-Length: 0\r\n\r\n".as_slice(),
+            b"HTTP/1.1 200 OK\r\nContent-Length : 0\r\n\r\n".as_slice(),
+        ] {
+            let mut conn = FakeSingleReadConnection::new(response);
+            let mut header_buf = [0; 200];
+            let config = ResponseConfig::strict();
+            let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
+
+            assert!(matches!(response, Err(Error::Codec)));
+        }
+    }
+
+    #[tokio::test]
+    async fn lenient_config_accepts_extra_whitespace() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1  200 OK\r\nContent-Length : 5\r\n\r\nHELLO");
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::lenient();
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(Some(5), response.content_length);
+        assert_eq!(Some(("Content-Length", b"5".as_slice())), response.headers().next());
+    }
+
+    #[tokio::test]
+    async fn can_read_obsolete_line_folding() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nX-Folded: first\r\n  second\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().allow_obsolete_line_folding(true);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(Some(0), response.content_length);
+        assert_eq!(
+            Some(("X-Folded", b"first    second".as_slice())),
+            response.headers().next()
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_obsolete_line_folding_by_default() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nX-Folded: first\r\n  second\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await;
+
+        assert!(matches!(response, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn rejects_too_large_header_section() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nX-Large: 0123456789012345678901234567890123456789\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().max_header_size(32);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
+
+        assert!(matches!(response, Err(Error::HeadersTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn rejects_too_many_headers() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n");
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().max_headers(2);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
+
+        assert!(matches!(response, Err(Error::HeadersTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn can_read_with_small_header_capacity() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nA: 1\r\nContent-Length: 0\r\n\r\n");
+        let mut header_buf = [0; 200];
+        let response =
+            Response::<_, 2>::read_with_config(&mut conn, Method::GET, &mut header_buf, &ResponseConfig::new())
+                .await
+                .unwrap();
+
+        assert_eq!(Some(0), response.content_length);
+        assert_eq!(2, response.headers().count());
+    }
+
+    #[tokio::test]
+    async fn rejects_more_headers_than_capacity() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n");
+        let mut header_buf = [0; 200];
+        let response =
+            Response::<_, 2>::read_with_config(&mut conn, Method::GET, &mut header_buf, &ResponseConfig::new()).await;
+
+        assert!(matches!(response, Err(Error::HeadersTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn can_lookup_headers_by_name() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nX-Request-Id: abc\r\nVary: Accept\r\nvary: Origin\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        assert_eq!(Some(b"abc".as_slice()), response.header("x-request-id"));
+        assert_eq!(Some(b"Accept".as_slice()), response.header("VARY"));
+        assert_eq!(None, response.header("etag"));
+        assert_eq!(None, response.header(""));
+
+        let mut vary = response.headers_matching("Vary");
+        assert_eq!(Some(b"Accept".as_slice()), vary.next());
+        assert_eq!(Some(b"Origin".as_slice()), vary.next());
+        assert_eq!(None, vary.next());
+    }
+
+    #[tokio::test]
+    async fn can_read_dates() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nDate: Sun, 06 Nov 1994 08:49:37 GMT\r\nLast-Modified: Sunday, 06-Nov-94 08:49:37 GMT\r\nExpires: 0\r\nContent-Length: 0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
 
-    use embedded_io::ErrorType;
-    use embedded_io_async::Read;
+        assert_eq!(Some(784111777), response.date());
+        assert_eq!(Some(784111777), response.last_modified());
+        assert_eq!(None, response.expires());
+    }
 
-    use super::{Status, StatusCode};
-    use crate::{
-        reader::BufferingReader,
-        request::Method,
-        response::{chunked::ChunkedBodyReader, Response},
-        Error, TryBufRead,
-    };
+    #[tokio::test]
+    async fn skips_interim_responses() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </style.css>\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHELLO",
+        );
+        conn.read_length = 100;
+        let mut header_buf = [0; 200];
+        let mut interim = heapless::Vec::<(u16, usize), 2>::new();
+        let response = Response::<_>::read_with_interim(
+            &mut conn,
+            Method::GET,
+            &mut header_buf,
+            &ResponseConfig::new(),
+            |status, headers| {
+                let links = headers.filter(|(name, _)| name.eq_ignore_ascii_case("link")).count();
+                interim.push((status.0, links)).unwrap();
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(Status::Ok, response.status);
+        assert_eq!(&[(100, 0), (103, 1)], interim.as_slice());
+        assert_eq!(b"HELLO", response.body().read_to_end().await.unwrap());
+        assert!(conn.is_exhausted());
+    }
 
     #[tokio::test]
-    async fn can_read_no_content() {
-        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\n\r\n");
-        let mut response_buf = [0; 200];
-        let response = Response::read(&mut conn, Method::POST, &mut response_buf)
-            .await
-            .unwrap();
+    async fn returns_switching_protocols_as_final_response() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
 
-        assert_eq!(b"", response.body().read_to_end().await.unwrap());
-        assert!(conn.is_exhausted());
+        assert_eq!(Status::SwitchingProtocols, response.status);
     }
 
     #[tokio::test]
-    async fn can_read_no_content_with_zero_content_length() {
-        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n");
-        let mut response_buf = [0; 200];
-        let response = Response::read(&mut conn, Method::POST, &mut response_buf)
-            .await
-            .unwrap();
+    async fn can_upgrade_with_buffered_bytes() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: custom\r\n\r\nHELLO WORLD",
+        );
+        conn.read_length = 79;
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+        let upgraded = response.upgrade().unwrap();
+        assert_eq!(b"HELLO", upgraded.buffered);
 
-        assert_eq!(b"", response.body().read_to_end().await.unwrap());
+        let mut rest = [0; 10];
+        let len = upgraded.conn.read(&mut rest).await.unwrap();
+        assert_eq!(b" WORLD", &rest[..len]);
+
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+        assert!(matches!(
+            response.upgrade(),
+            Err(Error::UpgradeRejected(StatusCode(200)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_determine_persistent_connection() {
+        for (response, persistent) in [
+            (b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".as_slice(), true),
+            (
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".as_slice(),
+                true,
+            ),
+            (
+                b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n".as_slice(),
+                false,
+            ),
+            (b"HTTP/1.1 200 OK\r\nConnection: keep-alive\r\n\r\n".as_slice(), false),
+            (b"HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n".as_slice(), false),
+            (
+                b"HTTP/1.0 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n".as_slice(),
+                true,
+            ),
+            (
+                b"HTTP/1.1 101 Switching Protocols\r\nConnection: upgrade\r\n\r\n".as_slice(),
+                false,
+            ),
+        ] {
+            let mut conn = response;
+            let mut header_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+            assert_eq!(persistent, response.is_persistent());
+        }
+    }
+
+    #[tokio::test]
+    async fn can_determine_whether_body_is_received() {
+        for (response, header_buf_len, received) in [
+            (
+                b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHELLO".as_slice(),
+                200,
+                true,
+            ),
+            (
+                b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHELLO".as_slice(),
+                40,
+                false,
+            ),
+            (b"HTTP/1.1 204 No Content\r\n\r\n".as_slice(), 200, true),
+            (
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n".as_slice(),
+                200,
+                false,
+            ),
+        ] {
+            let mut conn = response;
+            let mut header_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut header_buf[..header_buf_len])
+                .await
+                .unwrap();
+
+            assert_eq!(received, response.is_body_received());
+        }
+    }
+
+    #[tokio::test]
+    async fn transfer_encoding_overrides_content_length() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        assert_eq!(None, response.content_length);
+
+        let body = response.body().read_to_end().await.unwrap();
+
+        assert_eq!(b"HELLO", body);
         assert!(conn.is_exhausted());
     }
 
     #[tokio::test]
-    async fn cannot_read_no_content_with_nonzero_content_length() {
-        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 204 No Content\r\nContent-Length: 5\r\n\r\nHELLO");
-        let mut response_buf = [0; 200];
-        let response = Response::read(&mut conn, Method::POST, &mut response_buf).await;
+    async fn can_reject_conflicting_content_length_and_transfer_encoding() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().reject_conflicting_length(true);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
 
-        assert!(matches!(response, Err(Error::Codec)));
+        assert!(matches!(response, Err(Error::InvalidContentLength)));
     }
 
     #[tokio::test]
@@ -588,6 +2117,39 @@ mod tests {
         assert!(conn.is_exhausted());
     }
 
+    #[tokio::test]
+    async fn can_read_text() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 6\r\n\r\nGR\xc3\x9c\xc3\x9f",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        assert_eq!("GR\u{dc}\u{df}", response.body().text().await.unwrap());
+
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=iso-8859-1\r\nContent-Length: 4\r\n\r\nGR\xdc\xdf",
+        );
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        assert!(matches!(response.body().text().await, Err(Error::UnsupportedCharset)));
+
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nGR\xdc\xdf");
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        assert!(matches!(response.body().text().await, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn can_copy_body_to_writer() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n6\r\n WORLD\r\n0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        let mut sink = Vec::new();
+        assert_eq!(11, response.body().copy_to(&mut sink).await.unwrap());
+        assert_eq!(b"HELLO WORLD", sink.as_slice());
+    }
+
     #[tokio::test]
     async fn can_read_with_content_length_to_other_buffer() {
         let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHELLO WORLD");
@@ -617,6 +2179,45 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn can_read_content_length_larger_than_4_gib() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5000000000\r\n\r\nHELLO");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        assert_eq!(Some(5_000_000_000), response.content_length);
+
+        let mut reader = response.body().reader();
+        let mut body_buf = [0; 5];
+        reader.read_exact(&mut body_buf).await.unwrap();
+
+        assert_eq!(b"HELLO", &body_buf);
+        assert!(matches!(
+            reader,
+            BodyReader::FixedLength(FixedLengthBodyReader {
+                remaining: 4_999_999_995,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_read_all_from_unbuffered_reader() {
+        let conn = FakeSingleReadConnection::new(b"HELLO WORLD");
+        let mut reader = BodyReader::FixedLength(FixedLengthBodyReader::new(conn, 11));
+
+        let mut body = [0; 16];
+        assert_eq!(11, reader.read_all(&mut body).await.unwrap());
+        assert_eq!(b"HELLO WORLD", &body[..11]);
+
+        let conn = FakeSingleReadConnection::new(b"HELLO WORLD");
+        let mut reader = BodyReader::FixedLength(FixedLengthBodyReader::new(conn, 11));
+        assert!(matches!(
+            reader.read_all(&mut body[..5]).await,
+            Err(Error::BufferTooSmall)
+        ));
+    }
+
     #[tokio::test]
     async fn can_discard_with_content_length() {
         let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHELLO WORLD");
@@ -627,6 +2228,87 @@ mod tests {
         assert!(conn.is_exhausted());
     }
 
+    #[tokio::test]
+    async fn can_read_headers_while_reading_body() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nX-Next-Cursor: abc\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let (head, body) = response.into_parts();
+        let mut reader = body.reader();
+
+        let mut body = [0; 16];
+        let len = reader.read_to_end(&mut body).await.unwrap();
+        assert_eq!(b"HELLO", &body[..len]);
+        assert_eq!(StatusCode(200), head.status);
+        assert_eq!(Some(b"abc".as_slice()), head.header("x-next-cursor"));
+    }
+
+    #[tokio::test]
+    async fn can_read_exact_and_skip() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n\x01\x02\x03\r\n5\r\nHELLO\r\n0\r\n\r\n",
+        );
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        let mut header = [0; 2];
+        reader.read_exact(&mut header).await.unwrap();
+        assert_eq!([1, 2], header);
+        reader.skip(4).await.unwrap();
+        reader.read_exact(&mut header).await.unwrap();
+        assert_eq!(b"LO", &header);
+        assert!(matches!(reader.skip(1).await, Err(Error::UnexpectedEndOfBody)));
+    }
+
+    #[tokio::test]
+    async fn cannot_read_exact_of_aborted_body() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHELLO");
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+        let mut reader = response.body().reader();
+
+        let mut body = [0; 8];
+        assert!(matches!(
+            reader.read_exact(&mut body).await,
+            Err(Error::ConnectionAborted)
+        ));
+        assert_eq!(5, reader.consumed());
+    }
+
+    #[tokio::test]
+    async fn reports_consumed_bytes_of_aborted_body() {
+        for response in [
+            b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHELLO".as_slice(),
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n6\r\n".as_slice(),
+        ] {
+            let mut conn = response;
+            let mut response_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+            let mut reader = response.body().reader();
+
+            let mut body = [0; 16];
+            assert!(reader.read_to_end(&mut body).await.is_err());
+            assert_eq!(5, reader.consumed());
+        }
+    }
+
+    #[tokio::test]
+    async fn can_discard_with_scratch_buffer() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n6\r\n WORLD\r\n0\r\n\r\n",
+        );
+        conn.read_length = 10;
+        let mut response_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+
+        let mut scratch = [0; 4];
+        assert_eq!(11, response.body().reader().discard_with(&mut scratch).await.unwrap());
+        assert!(conn.is_exhausted());
+    }
+
     #[tokio::test]
     async fn incorrect_fragment_length_does_not_panic() {
         let mut conn = FakeSingleReadConnection::new(
@@ -727,6 +2409,31 @@ mod tests {
         assert!(conn.is_exhausted());
     }
 
+    #[tokio::test]
+    async fn can_read_to_vec() {
+        let mut conn = FakeSingleReadConnection::new(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n6\r\n WORLD\r\n0\r\n\r\n",
+        );
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        let mut body = heapless::Vec::<u8, 16>::from_slice(b"> ").unwrap();
+        assert_eq!(11, response.body().reader().read_to_vec(&mut body).await.unwrap());
+        assert_eq!(b"> HELLO WORLD", body.as_slice());
+    }
+
+    #[tokio::test]
+    async fn cannot_read_to_vec_without_capacity() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHELLO WORLD");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        let mut body = heapless::Vec::<u8, 16>::from_slice(b"> > > ").unwrap();
+        let result = response.body().reader().read_to_vec(&mut body).await;
+        assert!(matches!(result, Err(Error::BufferTooSmall)));
+        assert_eq!(b"> > > ", body.as_slice());
+    }
+
     #[tokio::test]
     async fn can_read_to_end_of_connection_with_same_buffer() {
         let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\n\r\nHELLO WORLD");
@@ -739,6 +2446,43 @@ mod tests {
         assert!(conn.is_exhausted());
     }
 
+    #[tokio::test]
+    async fn rejects_body_above_maximum_size() {
+        let config = ResponseConfig::new().max_body_size(5);
+        for response in [
+            b"HTTP/1.1 200 OK\r\n\r\nHELLO WORLD".as_slice(),
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n1\r\n \r\n0\r\n\r\n".as_slice(),
+        ] {
+            let mut conn = FakeSingleReadConnection::new(response);
+            let mut header_buf = [0; 200];
+            let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config)
+                .await
+                .unwrap();
+
+            let mut body_buf = [0; 200];
+            let result = response.body().reader().read_to_end(&mut body_buf).await;
+            assert!(matches!(result, Err(Error::BodyTooLarge)));
+        }
+
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nHELLO!");
+        let mut header_buf = [0; 200];
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config).await;
+        assert!(matches!(response, Err(Error::BodyTooLarge)));
+    }
+
+    #[tokio::test]
+    async fn can_read_body_of_maximum_size() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\n\r\nHELLO");
+        let mut header_buf = [0; 200];
+        let config = ResponseConfig::new().max_body_size(5);
+        let response = Response::<_>::read_with_config(&mut conn, Method::GET, &mut header_buf, &config)
+            .await
+            .unwrap();
+
+        let body = response.body().read_to_end().await.unwrap();
+        assert_eq!(b"HELLO", body);
+    }
+
     #[tokio::test]
     async fn can_read_to_end_of_connection_to_other_buffer() {
         let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\r\n\r\nHELLO WORLD");
@@ -795,6 +2539,150 @@ mod tests {
         assert_eq!(b"XYYYYYYYYYYYYYYYY", &body);
     }
 
+    #[tokio::test]
+    async fn chunked_body_reader_ignores_chunk_extensions() {
+        let mut raw_body = b"5;foo=bar\r\nHELLO\r\n6 ; name=\"quoted;value\"\r\n WORLD\r\n0;last\r\n\r\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        let mut len = 0;
+        loop {
+            match reader.read(&mut body[len..]).await.unwrap() {
+                0 => break,
+                n => len += n,
+            }
+        }
+
+        assert_eq!(b"HELLO WORLD", &body[..len]);
+        assert!(raw_body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_rejects_invalid_chunk_size() {
+        for raw_body in [
+            b"\r\nHELLO\r\n0\r\n\r\n".as_slice(),
+            b";foo\r\nHELLO\r\n0\r\n\r\n".as_slice(),
+            b"5 5\r\nHELLO\r\n0\r\n\r\n".as_slice(),
+            b"5X\r\nHELLO\r\n0\r\n\r\n".as_slice(),
+            b"10000000000000000\r\nHELLO\r\n0\r\n\r\n".as_slice(),
+        ] {
+            let mut raw_body = raw_body;
+            let mut read_buffer = [0; 128];
+            let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+            let mut body = [0; 16];
+            assert!(matches!(reader.read(&mut body).await, Err(Error::Codec)));
+        }
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_can_read_chunk_larger_than_4_gib() {
+        let mut raw_body = b"100000000\r\nHELLO".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert_eq!(5, reader.read(&mut body).await.unwrap());
+        assert_eq!(b"HELLO", &body[..5]);
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_can_allow_bare_lf() {
+        let mut raw_body = b"5\nHELLO\n6\r\n WORLD\r\n0\nX-Checksum: abc\n\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader =
+            ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body)).allow_bare_lf(true);
+
+        let mut body = [0; 16];
+        let mut len = 0;
+        loop {
+            match reader.read(&mut body[len..]).await.unwrap() {
+                0 => break,
+                n => len += n,
+            }
+        }
+
+        assert_eq!(b"HELLO WORLD", &body[..len]);
+        assert_eq!(Some(("X-Checksum", b"abc".as_slice())), reader.trailers().next());
+        assert!(raw_body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_rejects_bare_lf_by_default() {
+        let mut raw_body = b"5\nHELLO\n0\n\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert!(matches!(reader.read(&mut body).await, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn can_read_chunked_body_with_bare_lf() {
+        let mut conn = FakeSingleReadConnection::new(b"HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\n5\nHELLO\n0\n\n");
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        let body = response.body().read_to_end().await.unwrap();
+
+        assert_eq!(b"HELLO", body);
+        assert!(conn.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_rejects_too_long_chunk_extensions() {
+        let mut raw_body = [b'x'; 512];
+        raw_body[..2].copy_from_slice(b"5;");
+        let mut raw_body = raw_body.as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert!(matches!(reader.read(&mut body).await, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_can_read_trailers() {
+        let mut raw_body = b"5\r\nHELLO\r\n0\r\nX-Checksum: abc\r\nX-Other:  value \r\n\r\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert_eq!(5, reader.read(&mut body).await.unwrap());
+        assert_eq!(0, reader.read(&mut body).await.unwrap());
+
+        let mut trailers = reader.trailers();
+        assert_eq!(Some(("X-Checksum", b"abc".as_slice())), trailers.next());
+        assert_eq!(Some(("X-Other", b"value".as_slice())), trailers.next());
+        assert_eq!(None, trailers.next());
+        assert!(raw_body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_discards_trailers_that_do_not_fit() {
+        let mut raw_body = b"0\r\nX-Large: 0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789\r\nX-Small: 1\r\n\r\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert_eq!(0, reader.read(&mut body).await.unwrap());
+
+        let mut trailers = reader.trailers();
+        assert_eq!(Some(("X-Small", b"1".as_slice())), trailers.next());
+        assert_eq!(None, trailers.next());
+    }
+
+    #[tokio::test]
+    async fn chunked_body_reader_rejects_invalid_trailers() {
+        let mut raw_body = b"0\r\ninvalid\r\n\r\n".as_slice();
+        let mut read_buffer = [0; 128];
+        let mut reader = ChunkedBodyReader::new(BufferingReader::new(&mut read_buffer, 0, &mut raw_body));
+
+        let mut body = [0; 16];
+        assert!(matches!(reader.read(&mut body).await, Err(Error::Codec)));
+    }
+
     struct FakeSingleReadConnection {
         response: &'static [u8],
         offset: usize,
@@ -837,6 +2725,31 @@ mod tests {
 
     impl TryBufRead for FakeSingleReadConnection {}
 
+    #[tokio::test]
+    async fn can_get_reason_phrase() {
+        for (response, reason) in [
+            (b"HTTP/1.1 200 OK\r\n\r\n".as_slice(), "OK"),
+            (
+                b"HTTP/1.1 503 Sensor offline: battery low \r\n\r\n".as_slice(),
+                "Sensor offline: battery low",
+            ),
+            (b"HTTP/1.1 204\r\n\r\n".as_slice(), ""),
+            (b"HTTP/1.1 204 \r\n\r\n".as_slice(), ""),
+        ] {
+            let mut conn = response;
+            let mut response_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+            assert_eq!(reason, response.reason());
+        }
+    }
+
+    #[test]
+    fn canonical_reason() {
+        assert_eq!(Some("OK"), StatusCode(200).canonical_reason());
+        assert_eq!(Some("I'm a teapot"), StatusCode(418).canonical_reason());
+        assert_eq!(None, StatusCode(299).canonical_reason());
+    }
+
     #[test]
     fn status_equality() {
         // StatusCode and Status values can be compared
@@ -860,6 +2773,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_fallible_conversions)]
     fn status_try_from() {
         let s: Status = 200.into();
         assert_eq!(Status::Ok, s);
@@ -868,15 +2782,18 @@ mod tests {
         let s: StatusCode = Status::NotModified.try_into().unwrap();
         assert_eq!(s, StatusCode(304));
 
-        // Unknown status code values can be converted into Status::Unknown
         let im_a_teapot: Status = StatusCode(418).into();
-        assert_eq!(Status::Unknown, im_a_teapot);
-        let im_a_teapot: Status = 418.into();
-        assert_eq!(Status::Unknown, im_a_teapot);
+        assert_eq!(Status::ImATeapot, im_a_teapot);
+
+        // Unknown status code values can be converted into Status::Unknown
+        let unknown: Status = StatusCode(299).into();
+        assert_eq!(Status::Unknown, unknown);
+        let unknown: Status = 299.into();
+        assert_eq!(Status::Unknown, unknown);
 
         // Converting Status::Unknown back to a StatusCode will fail
         <Status as TryInto<StatusCode>>::try_into(Status::Unknown).expect_err("Status::Unknown conversion should fail");
-        <Status as TryInto<StatusCode>>::try_into(StatusCode(418).into())
+        <Status as TryInto<StatusCode>>::try_into(StatusCode(299).into())
             .expect_err("Status::Unknown conversion should fail");
     }
 }
@@ -0,0 +1,96 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use embedded_io_async::Read;
+
+use crate::headers::{HeaderList, Redacted, SENSITIVE_HEADERS};
+use crate::response::{Response, StatusCode};
+use crate::{Error, TryBufRead};
+
+/// A response that owns its headers and body, such that it does not borrow the connection or the buffer it was
+/// read into.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ResponseOwned {
+    /// The HTTP response status code.
+    pub status: StatusCode,
+    /// The names and values of the headers, in the order they were received.
+    pub headers: Vec<(String, Vec<u8>)>,
+    /// The entire body.
+    pub body: Vec<u8>,
+}
+
+impl ResponseOwned {
+    /// Get the value of the first header with the provided name, which is compared case-insensitively
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Get the values of all headers with the provided name, which is compared case-insensitively
+    pub fn headers_matching<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_slice())
+    }
+}
+
+impl<C, const MAX_HEADERS: usize> Response<'_, '_, C, MAX_HEADERS>
+where
+    C: Read + TryBufRead,
+{
+    /// Copy the headers and read the entire body of the response into an owned response.
+    pub async fn into_owned(self) -> Result<ResponseOwned, Error> {
+        let headers = self
+            .headers()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (String::from(name), Vec::from(value)))
+            .collect();
+        let status = self.status;
+        let mut body = Vec::new();
+        self.body().reader().read_to_end_vec(&mut body).await?;
+        Ok(ResponseOwned { status, headers, body })
+    }
+}
+
+impl core::fmt::Debug for ResponseOwned {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let redacted = Redacted {
+            value: self,
+            sensitive: SENSITIVE_HEADERS,
+        };
+        let headers = || {
+            self.headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), redacted.header(name, value)))
+        };
+        f.debug_struct("ResponseOwned")
+            .field("status", &self.status)
+            .field("headers", &HeaderList(headers))
+            .field("body_len", &self.body.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::Method;
+
+    #[tokio::test]
+    async fn can_read_owned_response() {
+        let mut conn: &[u8] =
+            b"HTTP/1.1 200 OK\r\nX-Next-Cursor: abc\r\nSet-Cookie: session=secret\r\nContent-Length: 11\r\n\r\nHELLO WORLD";
+        let owned = {
+            let mut response_buf = [0; 200];
+            let response = Response::read(&mut conn, Method::GET, &mut response_buf).await.unwrap();
+            response.into_owned().await.unwrap()
+        };
+
+        assert_eq!(StatusCode(200), owned.status);
+        assert_eq!(Some(b"abc".as_slice()), owned.header("x-next-cursor"));
+        assert_eq!(b"HELLO WORLD", owned.body.as_slice());
+        assert!(!format!("{:?}", owned).contains("secret"));
+    }
+}
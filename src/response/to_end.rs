@@ -0,0 +1,82 @@
+use embedded_io_async::{BufRead, Error as _, ErrorType, Read};
+
+use crate::Error;
+
+/// Response body reader for bodies that end when the connection is closed
+pub struct ToEndBodyReader<B> {
+    pub raw_body: B,
+    /// The number of bytes that can still be read before the body exceeds the maximum size.
+    pub remaining: u64,
+    consumed: u64,
+}
+
+impl<B> ToEndBodyReader<B> {
+    /// Read the body until the connection is closed, regardless of its size.
+    pub fn new(raw_body: B) -> Self {
+        Self {
+            raw_body,
+            remaining: u64::MAX,
+            consumed: 0,
+        }
+    }
+
+    /// Fail reads with [`Error::BodyTooLarge`] once the body exceeds `max` bytes.
+    pub fn max_body_size(mut self, max: u64) -> Self {
+        self.remaining = max;
+        self
+    }
+
+    /// Get the number of body bytes read or consumed so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+}
+
+impl<C> ErrorType for ToEndBodyReader<C> {
+    type Error = Error;
+}
+
+impl<C> Read for ToEndBodyReader<C>
+where
+    C: Read,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // Read one byte more than remaining, to detect whether the body exceeds the maximum size
+        let len = usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX);
+        let len = buf.len().min(len);
+        let read = self
+            .raw_body
+            .read(&mut buf[..len])
+            .await
+            .map_err(|e| Error::Network(e.kind()))?;
+        if read as u64 > self.remaining {
+            warn!("ToEnd: Response body exceeds the maximum size");
+            return Err(Error::BodyTooLarge);
+        }
+        self.remaining -= read as u64;
+        self.consumed += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl<C> BufRead for ToEndBodyReader<C>
+where
+    C: BufRead + Read,
+{
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        let remaining = self.remaining;
+        let loaded = self.raw_body.fill_buf().await.map_err(|e| Error::Network(e.kind()))?;
+        if loaded.len() as u64 > remaining {
+            warn!("ToEnd: Response body exceeds the maximum size");
+            return Err(Error::BodyTooLarge);
+        }
+        Ok(loaded)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.remaining = self.remaining.saturating_sub(amt as u64);
+        self.consumed += amt as u64;
+        self.raw_body.consume(amt);
+    }
+}
@@ -0,0 +1,203 @@
+/// Resuming interrupted downloads at the offset where they stopped, using a `Range` request for the rest of the
+/// resource, such that a download over a flaky link does not start over after every disconnect.
+///
+use embedded_io::ErrorType;
+use embedded_io_async::{BufRead, Read};
+
+use crate::reader::BufferingReader;
+use crate::response::{BodyReader, Response, Status};
+use crate::Error;
+
+/// The body of a download resumed at an offset, such as the body of
+/// [`HttpResource::resume()`](crate::client::HttpResource::resume).
+///
+/// A server that does not support ranges responds with the whole resource, in which case the bytes before the
+/// offset are skipped as the body is read. If the `ETag` of the resource changed since the download started, the
+/// new resource is received from the start, and [`Resumed::offset()`] is 0.
+pub struct Resumed<R> {
+    reader: R,
+    skip: u64,
+    offset: u64,
+    complete_length: Option<u64>,
+}
+
+impl<'resp, 'buf, C> Resumed<BodyReader<BufferingReader<'resp, 'buf, C>>>
+where
+    C: Read,
+{
+    /// Resume the download at `offset` using the response to a request for the range starting at `offset`,
+    /// which is conditional on the `etag` of the resource, if any.
+    ///
+    /// Returns [`Error::UnexpectedResponse`] without reading the body if the server did not respond with the
+    /// resource, or [`Error::Codec`] if it responded with another range than requested.
+    pub fn new<const MAX_HEADERS: usize>(
+        response: Response<'resp, 'buf, C, MAX_HEADERS>,
+        offset: u64,
+        etag: Option<&str>,
+    ) -> Result<Self, Error> {
+        let (skip, offset, complete_length) = if response.status == Status::PartialContent {
+            let Some(range) = response
+                .content_range()
+                .filter(|range| range.is_bytes() && range.start == offset)
+            else {
+                warn!("Unexpected Content-Range of resumed download");
+                return Err(Error::Codec);
+            };
+            (0, offset, range.complete_length)
+        } else if response.status == Status::Ok {
+            // The whole resource is sent if the server does not support ranges, or if the resource changed
+            let changed = etag.is_some_and(|etag| response.header("etag") != Some(etag.as_bytes()));
+            if changed {
+                debug!("Resource changed, restarting download");
+                (0, 0, response.content_length)
+            } else {
+                (offset, offset, response.content_length)
+            }
+        } else {
+            warn!("Unexpected response to resumed download");
+            return Err(Error::UnexpectedResponse {
+                status: response.status,
+                content_type: response.content_type,
+            });
+        };
+
+        Ok(Self {
+            reader: response.body().reader(),
+            skip,
+            offset,
+            complete_length,
+        })
+    }
+}
+
+impl<R> Resumed<R>
+where
+    R: BufRead<Error = Error>,
+{
+    /// Get the offset in the resource of the first byte of the body, which is 0 if the download started over.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the length of the whole resource, if known.
+    pub fn complete_length(&self) -> Option<u64> {
+        self.complete_length
+    }
+
+    /// Skip the bytes before the offset, if the server sent the whole resource.
+    async fn skip(&mut self) -> Result<(), Error> {
+        while self.skip > 0 {
+            let data = self.reader.fill_buf().await?;
+            if data.is_empty() {
+                break;
+            }
+            let len = self.skip.min(data.len() as u64) as usize;
+            self.reader.consume(len);
+            self.skip -= len as u64;
+        }
+        Ok(())
+    }
+}
+
+impl<R> ErrorType for Resumed<R> {
+    type Error = Error;
+}
+
+impl<R> Read for Resumed<R>
+where
+    R: BufRead<Error = Error> + Read<Error = Error>,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.skip().await?;
+        self.reader.read(buf).await
+    }
+}
+
+impl<R> BufRead for Resumed<R>
+where
+    R: BufRead<Error = Error> + Read<Error = Error>,
+{
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.skip().await?;
+        self.reader.fill_buf().await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::Method;
+
+    async fn resume(
+        response: &'static [u8],
+        offset: u64,
+        etag: Option<&str>,
+    ) -> Result<(u64, std::vec::Vec<u8>), Error> {
+        let mut conn = response;
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await?;
+        let mut body = Resumed::new(response, offset, etag)?;
+        let mut received = std::vec::Vec::new();
+        loop {
+            let data = body.fill_buf().await?;
+            if data.is_empty() {
+                break;
+            }
+            received.extend_from_slice(data);
+            let len = data.len();
+            body.consume(len);
+        }
+        Ok((body.offset(), received))
+    }
+
+    #[tokio::test]
+    async fn can_resume_partial_content() {
+        let (offset, body) = resume(
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 6\r\n\r\n456789",
+            4,
+            Some("\"v1\""),
+        )
+        .await
+        .unwrap();
+        assert_eq!(4, offset);
+        assert_eq!(b"456789", body.as_slice());
+
+        let result = resume(
+            b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-9/10\r\nContent-Length: 10\r\n\r\n0123456789",
+            4,
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Codec)));
+    }
+
+    #[tokio::test]
+    async fn skips_to_offset_of_whole_resource() {
+        let (offset, body) = resume(
+            b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 10\r\n\r\n0123456789",
+            4,
+            Some("\"v1\""),
+        )
+        .await
+        .unwrap();
+        assert_eq!(4, offset);
+        assert_eq!(b"456789", body.as_slice());
+    }
+
+    #[tokio::test]
+    async fn restarts_download_of_changed_resource() {
+        let (offset, body) = resume(
+            b"HTTP/1.1 200 OK\r\nETag: \"v2\"\r\nContent-Length: 4\r\n\r\nabcd",
+            4,
+            Some("\"v1\""),
+        )
+        .await
+        .unwrap();
+        assert_eq!(0, offset);
+        assert_eq!(b"abcd", body.as_slice());
+    }
+}
@@ -0,0 +1,153 @@
+/// Helpers for backing off as requested by the server with the `Retry-After` header.
+///
+use core::time::Duration;
+use embedded_io_async::Read;
+
+use crate::headers::RetryAfter;
+use crate::response::{Response, Status};
+use crate::{Error, TryBufRead};
+
+/// A source of delays used to wait before retrying a request.
+pub trait Delay {
+    /// Wait for the provided number of seconds.
+    async fn delay_secs(&mut self, secs: u64);
+
+    /// Wait for the provided duration.
+    ///
+    /// The duration is rounded up to whole seconds, unless implemented with a finer resolution.
+    async fn delay(&mut self, duration: Duration) {
+        let secs = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+        self.delay_secs(secs).await;
+    }
+}
+
+/// Get the number of seconds to wait before retrying the request that resulted in the response.
+///
+/// Returns `None` if the response is not a `429 Too Many Requests` or `503 Service Unavailable` response.
+/// An HTTP-date in the `Retry-After` header is made relative to the `Date` header of the response.
+/// The `default_secs` is used if `Retry-After` is missing or invalid, or if it is a date and `Date` is missing.
+pub fn retry_delay<C, const MAX_HEADERS: usize>(
+    response: &Response<'_, '_, C, MAX_HEADERS>,
+    default_secs: u64,
+) -> Option<u64>
+where
+    C: Read,
+{
+    let status: Status = response.status.into();
+    if !matches!(status, Status::TooManyRequests | Status::ServiceUnavailable) {
+        return None;
+    }
+
+    let delay = match response.retry_after() {
+        Some(RetryAfter::Delay(delay)) => delay,
+        Some(retry_after @ RetryAfter::Date(_)) => match response.date() {
+            Some(now) => retry_after.delay_secs(now),
+            None => default_secs,
+        },
+        None => default_secs,
+    };
+    Some(delay)
+}
+
+/// Wait as requested by a `429 Too Many Requests` or `503 Service Unavailable` response.
+///
+/// The response body is discarded, such that the connection can be reused for the retry.
+/// The delay is determined by [`retry_delay()`] and is capped at `max_secs`.
+///
+/// Returns `true` if the request should be retried, and `false` without waiting for any other response.
+/// The body of the request must be rewound using [`RequestBody::rewind()`](crate::request::RequestBody::rewind)
+/// before it is resent.
+pub async fn wait_for_retry<C, D, const MAX_HEADERS: usize>(
+    response: Response<'_, '_, C, MAX_HEADERS>,
+    delay: &mut D,
+    default_secs: u64,
+    max_secs: u64,
+) -> Result<bool, Error>
+where
+    C: Read + TryBufRead,
+    D: Delay,
+{
+    let Some(secs) = retry_delay(&response, default_secs) else {
+        return Ok(false);
+    };
+
+    response.body().discard().await?;
+
+    debug!("Retrying in {} seconds", secs.min(max_secs));
+    delay.delay_secs(secs.min(max_secs)).await;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeConnection;
+    use crate::request::Method;
+
+    #[derive(Default)]
+    struct FakeDelay(Option<u64>);
+
+    impl Delay for FakeDelay {
+        async fn delay_secs(&mut self, secs: u64) {
+            self.0 = Some(secs);
+        }
+    }
+
+    async fn delay_for(response: &'static [u8], default_secs: u64) -> Option<u64> {
+        let mut conn = FakeConnection::new(response);
+        let mut header_buf = [0; 256];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+        retry_delay(&response, default_secs)
+    }
+
+    #[tokio::test]
+    async fn can_get_retry_delay() {
+        assert_eq!(
+            Some(120),
+            delay_for(b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\n\r\n", 5).await
+        );
+        assert_eq!(
+            Some(30),
+            delay_for(
+                b"HTTP/1.1 503 Service Unavailable\r\nDate: Sun, 06 Nov 1994 08:49:37 GMT\r\nRetry-After: Sun, 06 Nov 1994 08:50:07 GMT\r\n\r\n",
+                5
+            )
+            .await
+        );
+        assert_eq!(
+            Some(5),
+            delay_for(
+                b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: Sun, 06 Nov 1994 08:50:07 GMT\r\n\r\n",
+                5
+            )
+            .await
+        );
+        assert_eq!(Some(5), delay_for(b"HTTP/1.1 429 Too Many Requests\r\n\r\n", 5).await);
+        assert_eq!(None, delay_for(b"HTTP/1.1 200 OK\r\nRetry-After: 120\r\n\r\n", 5).await);
+    }
+
+    #[tokio::test]
+    async fn can_wait_for_retry() {
+        let mut conn = FakeConnection::new(
+            b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nContent-Length: 5\r\n\r\nHELLO",
+        );
+        let mut header_buf = [0; 256];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        let mut delay = FakeDelay::default();
+        assert!(wait_for_retry(response, &mut delay, 5, 60).await.unwrap());
+        assert_eq!(Some(60), delay.0);
+        assert!(conn.response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_wait_for_other_responses() {
+        let mut conn = FakeConnection::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHELLO");
+        let mut header_buf = [0; 256];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+
+        let mut delay = FakeDelay::default();
+        assert!(!wait_for_retry(response, &mut delay, 5, 60).await.unwrap());
+        assert_eq!(None, delay.0);
+    }
+}
@@ -0,0 +1,179 @@
+/// Segmented downloads, fetching the ranges of a large resource concurrently over separate connections, which
+/// increases the throughput on links with a high latency.
+///
+use core::cell::Cell;
+use embassy_futures::join::join_array;
+use embedded_io_async::BufRead;
+use embedded_nal_async::{Dns, TcpConnect};
+
+use crate::client::HttpClient;
+use crate::request::{Method, RequestBuilder};
+use crate::response::Status;
+use crate::Error;
+
+/// The default length of a segment.
+pub const DEFAULT_SEGMENT_LEN: u64 = 64 * 1024;
+
+/// The storage the segments are written to, which reassembles the resource.
+///
+/// The segments are received concurrently, so the storage is shared between the connections and must handle
+/// writes to any offset in any order, e.g. by writing to flash or to a file at the offset.
+pub trait SegmentStorage {
+    /// Write the part of the resource at `offset`.
+    async fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), Error>;
+}
+
+/// A download of a resource of a known length, split into segments that are requested using `Range` requests.
+///
+/// The length of the resource can be discovered with a `HEAD` request. The server must support ranges, as the
+/// segments are not requested otherwise.
+pub struct SegmentedDownload<'a, S>
+where
+    S: SegmentStorage,
+{
+    url: &'a str,
+    len: u64,
+    storage: &'a S,
+    segment_len: u64,
+    retries: u32,
+}
+
+impl<'a, S> SegmentedDownload<'a, S>
+where
+    S: SegmentStorage,
+{
+    /// Download the `len` bytes of the resource at `url` to `storage`.
+    pub fn new(url: &'a str, len: u64, storage: &'a S) -> Self {
+        Self {
+            url,
+            len,
+            storage,
+            segment_len: DEFAULT_SEGMENT_LEN,
+            retries: 0,
+        }
+    }
+
+    /// Set the length of the segments, except the last segment which may be shorter.
+    pub fn segment_len(mut self, segment_len: u64) -> Self {
+        self.segment_len = segment_len.max(1);
+        self
+    }
+
+    /// Split the resource into `segments` segments of equal length.
+    pub fn segments(self, segments: u64) -> Self {
+        let segment_len = self.len.div_ceil(segments.max(1));
+        self.segment_len(segment_len)
+    }
+
+    /// Set the number of times a segment is requested again after e.g. the connection failed, such that only the
+    /// rest of the segment is received again.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Run the download, fetching up to `N` segments at the same time.
+    ///
+    /// Every segment is fetched with a new connection of one of the `clients`, each of which needs its own TLS
+    /// buffers, and its response is read into the `rx_buf` of that client. Once a segment is complete, the next
+    /// segment that is not yet fetched is requested. The first error of a segment that cannot be retried aborts
+    /// the download: the other connections complete the segments they are fetching, but no further segments are
+    /// requested, and any segment may be partially written.
+    pub async fn run<T, D, const N: usize>(
+        &self,
+        clients: [&mut HttpClient<'_, T, D>; N],
+        rx_bufs: [&mut [u8]; N],
+    ) -> Result<(), Error>
+    where
+        T: TcpConnect,
+        D: Dns,
+    {
+        let next = Cell::new(0);
+        let mut workers = clients.into_iter().zip(rx_bufs);
+        let workers: [_; N] = core::array::from_fn(|_| {
+            let (client, rx_buf) = workers.next().unwrap();
+            self.worker(client, rx_buf, &next)
+        });
+        join_array(workers).await.into_iter().collect()
+    }
+
+    /// Fetch the next segment using `client`, until all segments are fetched.
+    async fn worker<T, D>(
+        &self,
+        client: &mut HttpClient<'_, T, D>,
+        rx_buf: &mut [u8],
+        next: &Cell<u64>,
+    ) -> Result<(), Error>
+    where
+        T: TcpConnect,
+        D: Dns,
+    {
+        loop {
+            let start = next.get();
+            if start >= self.len {
+                return Ok(());
+            }
+            let end = start.saturating_add(self.segment_len).min(self.len) - 1;
+            next.set(end + 1);
+
+            let mut offset = start;
+            let mut attempt = 0;
+            loop {
+                let e = match self.fetch(client, rx_buf, &mut offset, end).await {
+                    Ok(()) => break,
+                    Err(e) => e,
+                };
+                // The server does not support ranges, or the storage failed
+                let fatal = matches!(e, Error::Storage | Error::UnexpectedResponse { .. });
+                if fatal || attempt >= self.retries {
+                    // Keep the other workers from requesting further segments
+                    next.set(self.len);
+                    return Err(e);
+                }
+                attempt += 1;
+                warn!("Retrying segment {}-{} at offset {}", start, end, offset);
+            }
+        }
+    }
+
+    /// Fetch the part of a segment from `offset` to `end`, advancing `offset` as the segment is written.
+    async fn fetch<T, D>(
+        &self,
+        client: &mut HttpClient<'_, T, D>,
+        rx_buf: &mut [u8],
+        offset: &mut u64,
+        end: u64,
+    ) -> Result<(), Error>
+    where
+        T: TcpConnect,
+        D: Dns,
+    {
+        debug!("Fetching segment {}-{}", offset, end);
+        let mut request = client.request(Method::GET, self.url).await?.range(*offset, Some(end));
+        let response = request.send(rx_buf).await?;
+        let range = response.content_range().map(|range| (range.start, range.end));
+        if response.status != Status::PartialContent || range != Some((*offset, end)) {
+            warn!("Unexpected response to segment request");
+            return Err(Error::UnexpectedResponse {
+                status: response.status,
+                content_type: response.content_type,
+            });
+        }
+
+        let mut reader = response.body().reader();
+        loop {
+            let data = reader.fill_buf().await?;
+            if data.is_empty() {
+                break;
+            }
+            let len = data.len();
+            self.storage.write_at(*offset, data).await?;
+            *offset += len as u64;
+            reader.consume(len);
+        }
+        if *offset <= end {
+            return Err(Error::ConnectionAborted);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+/// Signing of requests, such as webhook-style schemes sending an HMAC of the request in an `X-Signature` header.
+///
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+
+use crate::request::{check_body_error, Request, RequestBody};
+use crate::Error;
+
+/// A signer of requests, which computes the signature using the crypto of the application.
+///
+/// The request body is passed to [`RequestSigner::update()`] before the request is written, after which
+/// [`RequestSigner::sign()`] is invoked with the request to select the method, target and headers that are signed.
+/// The body is written twice, so it must produce the same bytes each time after [`RequestBody::rewind()`].
+pub trait RequestSigner {
+    /// Update the digest of the body with the next part of the body.
+    fn update(&mut self, data: &[u8]);
+
+    /// Sign the request, after the whole body was passed to `update`.
+    ///
+    /// Returns the name and value of the header containing the signature, e.g. `("X-Signature", "sha256=...")`.
+    fn sign<B: RequestBody>(&mut self, request: &Request<'_, B>) -> Result<(&str, &str), Error>;
+}
+
+/// Compute the signature header of the request.
+pub(crate) async fn sign<'s, B, S>(request: &Request<'_, B>, signer: &'s mut S) -> Result<(&'s str, &'s str), Error>
+where
+    B: RequestBody,
+    S: RequestSigner,
+{
+    if let Some(body) = request.body.as_ref() {
+        body.write(&mut DigestWriter(&mut *signer)).await?;
+        check_body_error(body)?;
+        if !body.rewind().await {
+            return Err(Error::BodyNotRewindable);
+        }
+    }
+    signer.sign(request)
+}
+
+/// A writer passing the bytes of the body to a signer.
+struct DigestWriter<'a, S>(&'a mut S);
+
+impl<S> ErrorType for DigestWriter<'_, S> {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl<S> Write for DigestWriter<'_, S>
+where
+    S: RequestSigner,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+}
@@ -0,0 +1,394 @@
+/// Server-Sent Events, reading the events of a `text/event-stream` response as they arrive.
+///
+/// When the stream ends, reconnect after [`EventStream::retry()`] with the `Last-Event-ID` header set to
+/// [`EventStream::last_event_id()`] to receive the events that were missed.
+///
+use embedded_io_async::BufRead;
+use heapless::Vec;
+
+use crate::Error;
+
+/// The maximum length of an event type, longer types are truncated.
+pub const MAX_EVENT_TYPE_LEN: usize = 32;
+
+/// The maximum length of an event id, longer ids are ignored.
+pub const MAX_EVENT_ID_LEN: usize = 64;
+
+/// An event of an event stream.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Event<'a> {
+    /// The type of the event, which is `message` unless set by the server.
+    pub event: &'a str,
+    /// The data of the event, where the values of multiple `data` fields are joined with `\n`.
+    pub data: &'a str,
+    /// The id of the last event, if any.
+    pub id: Option<&'a str>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Event,
+    Data,
+    Id,
+    Retry,
+    Ignored,
+}
+
+impl Field {
+    fn from_name(name: &[u8]) -> Self {
+        match name {
+            b"event" => Field::Event,
+            b"data" => Field::Data,
+            b"id" => Field::Id,
+            b"retry" => Field::Retry,
+            _ => Field::Ignored,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The name of the field, at the start of a line.
+    Name,
+    /// The first byte of the value, which is skipped if it is a space.
+    ValueStart(Field),
+    Value(Field),
+}
+
+/// A stream of events read from the body of a `text/event-stream` response.
+///
+/// The body is parsed incrementally, and the data of each event is stored in the provided buffer, which must be large
+/// enough to contain the data of the largest event.
+pub struct EventStream<'a, R>
+where
+    R: BufRead<Error = Error>,
+{
+    reader: R,
+    parser: Parser<'a>,
+    at_start: bool,
+    dispatched: bool,
+}
+
+/// The state of the parser, which is updated while the data of the reader is borrowed.
+struct Parser<'a> {
+    buf: &'a mut [u8],
+    data_len: usize,
+    state: State,
+    name: Vec<u8, 8>,
+    event: Vec<u8, MAX_EVENT_TYPE_LEN>,
+    id: Vec<u8, MAX_EVENT_ID_LEN>,
+    /// The id being parsed, which is `None` if it is invalid.
+    pending_id: Option<Vec<u8, MAX_EVENT_ID_LEN>>,
+    retry: Option<u64>,
+    /// The reconnection time being parsed, which is `None` if it is invalid.
+    pending_retry: Option<u64>,
+    after_cr: bool,
+}
+
+impl<'a, R> EventStream<'a, R>
+where
+    R: BufRead<Error = Error>,
+{
+    /// Read events from `reader`, which is usually the reader of the response body.
+    pub fn new(reader: R, buf: &'a mut [u8]) -> Self {
+        Self {
+            reader,
+            parser: Parser {
+                buf,
+                data_len: 0,
+                state: State::Name,
+                name: Vec::new(),
+                event: Vec::new(),
+                id: Vec::new(),
+                pending_id: None,
+                retry: None,
+                pending_retry: None,
+                after_cr: false,
+            },
+            at_start: true,
+            dispatched: false,
+        }
+    }
+
+    /// The id of the last event, to send in the `Last-Event-ID` header when reconnecting, if any.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.last_event_id()
+    }
+
+    /// The reconnection time in milliseconds set by the server, if any.
+    pub fn retry(&self) -> Option<u64> {
+        self.parser.retry
+    }
+
+    /// Read the next event, or `None` if the stream ended.
+    ///
+    /// An incomplete event at the end of the stream is discarded.
+    pub async fn next_event(&mut self) -> Result<Option<Event<'_>>, Error> {
+        if self.dispatched {
+            self.dispatched = false;
+            self.parser.data_len = 0;
+            self.parser.event.clear();
+        }
+
+        while !self.dispatched {
+            let data = self.reader.fill_buf().await?;
+            if data.is_empty() {
+                return Ok(None);
+            }
+
+            let mut consumed = 0;
+            if self.at_start {
+                self.at_start = false;
+                // The byte order mark is ignored
+                if data.starts_with(&[0xef, 0xbb, 0xbf]) {
+                    consumed = 3;
+                }
+            }
+            for &b in &data[consumed..] {
+                consumed += 1;
+                if self.parser.push(b)? {
+                    self.dispatched = true;
+                    break;
+                }
+            }
+            self.reader.consume(consumed);
+        }
+
+        self.parser.event().map(Some)
+    }
+}
+
+impl Parser<'_> {
+    fn last_event_id(&self) -> Option<&str> {
+        if self.id.is_empty() {
+            None
+        } else {
+            core::str::from_utf8(&self.id).ok()
+        }
+    }
+
+    fn event(&self) -> Result<Event<'_>, Error> {
+        // The data ends with a line feed, which is removed
+        let data = &self.buf[..self.data_len - 1];
+        let event = if self.event.is_empty() {
+            "message"
+        } else {
+            utf8_prefix(&self.event)
+        };
+        Ok(Event {
+            event,
+            data: core::str::from_utf8(data)?,
+            id: self.last_event_id(),
+        })
+    }
+
+    /// Process the next byte, returning whether the event is dispatched.
+    fn push(&mut self, b: u8) -> Result<bool, Error> {
+        let after_cr = core::mem::replace(&mut self.after_cr, b == b'\r');
+        match b {
+            // CRLF is a single line ending
+            b'\n' if after_cr => Ok(false),
+            b'\r' | b'\n' => self.end_of_line(),
+            _ => self.push_field(b).map(|_| false),
+        }
+    }
+
+    /// Process a byte that is not a line ending.
+    fn push_field(&mut self, b: u8) -> Result<(), Error> {
+        match self.state {
+            State::Name if b == b':' => {
+                // Lines starting with a colon are comments, and are ignored
+                let field = Field::from_name(&self.name);
+                self.start(field);
+                self.state = State::ValueStart(field);
+            }
+            State::Name => {
+                // Unknown names that are too long are ignored, so they only need to be distinct from known names
+                self.name.push(b).ok();
+            }
+            State::ValueStart(field) => {
+                self.state = State::Value(field);
+                if b != b' ' {
+                    self.value(field, b)?;
+                }
+            }
+            State::Value(field) => self.value(field, b)?,
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, field: Field) {
+        match field {
+            Field::Event => self.event.clear(),
+            Field::Id => self.pending_id = Some(Vec::new()),
+            Field::Retry => self.pending_retry = Some(0),
+            Field::Data | Field::Ignored => {}
+        }
+    }
+
+    fn value(&mut self, field: Field, b: u8) -> Result<(), Error> {
+        match field {
+            Field::Event => {
+                self.event.push(b).ok();
+            }
+            Field::Data => self.push_data(b)?,
+            Field::Id => {
+                if b == 0 || self.pending_id.as_mut().is_some_and(|id| id.push(b).is_err()) {
+                    self.pending_id = None;
+                }
+            }
+            Field::Retry => {
+                self.pending_retry = self
+                    .pending_retry
+                    .filter(|_| b.is_ascii_digit())
+                    .and_then(|retry| retry.checked_mul(10)?.checked_add((b - b'0') as u64));
+            }
+            Field::Ignored => {}
+        }
+        Ok(())
+    }
+
+    fn push_data(&mut self, b: u8) -> Result<(), Error> {
+        let slot = self.buf.get_mut(self.data_len).ok_or(Error::BufferTooSmall)?;
+        *slot = b;
+        self.data_len += 1;
+        Ok(())
+    }
+
+    /// Process the end of a line, returning whether the event is dispatched.
+    fn end_of_line(&mut self) -> Result<bool, Error> {
+        let state = self.state;
+        let field = match state {
+            // An empty line dispatches the event, unless there is no data
+            State::Name if self.name.is_empty() => {
+                if self.data_len == 0 {
+                    self.event.clear();
+                }
+                return Ok(self.data_len > 0);
+            }
+            // A field without a colon has an empty value
+            State::Name => {
+                let field = Field::from_name(&self.name);
+                self.start(field);
+                field
+            }
+            State::ValueStart(field) | State::Value(field) => field,
+        };
+        self.state = State::Name;
+        self.name.clear();
+
+        match field {
+            Field::Data => self.push_data(b'\n')?,
+            Field::Id => {
+                if let Some(id) = self.pending_id.take() {
+                    self.id = id;
+                }
+            }
+            Field::Retry => {
+                // An empty value is not a number
+                if let (Some(retry), State::Value(_)) = (self.pending_retry.take(), state) {
+                    self.retry = Some(retry);
+                }
+            }
+            Field::Event | Field::Ignored => {}
+        }
+        Ok(false)
+    }
+}
+
+/// The longest valid UTF-8 prefix, as truncation may split a character.
+fn utf8_prefix(value: &[u8]) -> &str {
+    match core::str::from_utf8(value) {
+        Ok(value) => value,
+        Err(e) => core::str::from_utf8(&value[..e.valid_up_to()]).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_io::ErrorType;
+
+    use super::*;
+
+    /// A body that is received in parts of `len` bytes.
+    struct Parts {
+        data: &'static [u8],
+        len: usize,
+    }
+
+    impl ErrorType for Parts {
+        type Error = Error;
+    }
+
+    impl embedded_io_async::Read for Parts {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            let data = self.fill_buf().await?;
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            self.consume(len);
+            Ok(len)
+        }
+    }
+
+    impl BufRead for Parts {
+        async fn fill_buf(&mut self) -> Result<&[u8], Error> {
+            Ok(&self.data[..self.len.min(self.data.len())])
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.data = &self.data[amt..];
+        }
+    }
+
+    const STREAM: &[u8] = b"\xef\xbb\xbf: comment\r\nretry: 3000\r\n\r\ndata: first\r\ndata:  second\r\n\r\nevent: update\nid: 42\ndata\n\nevent: ignored\n\nid\rdata:{\"a\":1}\r\rdata: incomplete\n";
+
+    #[tokio::test]
+    async fn can_read_events() {
+        for len in [1, 2, 7, STREAM.len()] {
+            let mut buf = [0; 32];
+            let mut events = EventStream::new(Parts { data: STREAM, len }, &mut buf);
+
+            let event = events.next_event().await.unwrap().unwrap();
+            assert_eq!(
+                Event {
+                    event: "message",
+                    data: "first\n second",
+                    id: None
+                },
+                event
+            );
+            assert_eq!(Some(3000), events.retry());
+
+            let event = events.next_event().await.unwrap().unwrap();
+            assert_eq!(
+                Event {
+                    event: "update",
+                    data: "",
+                    id: Some("42")
+                },
+                event
+            );
+
+            let event = events.next_event().await.unwrap().unwrap();
+            assert_eq!(
+                Event {
+                    event: "message",
+                    data: "{\"a\":1}",
+                    id: None
+                },
+                event
+            );
+            assert_eq!(None, events.last_event_id());
+
+            assert_eq!(None, events.next_event().await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_data_larger_than_buffer() {
+        let mut buf = [0; 4];
+        let mut events = EventStream::new(Parts { data: STREAM, len: 8 }, &mut buf);
+        assert!(matches!(events.next_event().await, Err(Error::BufferTooSmall)));
+    }
+}
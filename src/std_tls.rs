@@ -0,0 +1,181 @@
+/// TLS on hosts with `std` using rustls, to run the same client code in tests and simulations of firmware.
+///
+use std::io::Read as _;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::pinning::{self, Pin};
+use crate::tls::TlsProvider;
+use crate::Error;
+
+/// The size of the buffer used to read TLS records from the connection.
+const READ_BUF_LEN: usize = 4096;
+
+/// A [`TlsProvider`] establishing TLS connections using rustls.
+#[derive(Clone)]
+pub struct RustlsProvider {
+    config: Arc<rustls::ClientConfig>,
+}
+
+impl RustlsProvider {
+    /// Create a provider establishing connections with the provided rustls configuration.
+    pub fn new(config: Arc<rustls::ClientConfig>) -> Self {
+        Self { config }
+    }
+
+    /// Create a provider accepting only servers whose certificate matches one of the provided pins.
+    ///
+    /// The pins replace the verification of the certificate chain and of the host name, so no root certificates
+    /// are needed. The server must still prove that it holds the private key of the pinned certificate.
+    pub fn pinned(pins: &[Pin]) -> Self {
+        let verifier = PinnedCertVerifier { pins: pins.to_vec() };
+        let config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        Self::new(Arc::new(config))
+    }
+}
+
+/// A certificate verifier accepting only the certificates matching one of its pins.
+struct PinnedCertVerifier {
+    pins: Vec<Pin>,
+}
+
+impl rustls::client::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        if pinning::is_pinned(&self.pins, &end_entity.0) {
+            Ok(rustls::client::ServerCertVerified::assertion())
+        } else {
+            warn!("Server certificate does not match any pin");
+            Err(rustls::Error::InvalidCertificateData(
+                "certificate does not match any pin".into(),
+            ))
+        }
+    }
+}
+
+impl<C> TlsProvider<C> for RustlsProvider
+where
+    C: Read + Write,
+{
+    type Connection<'a>
+        = RustlsConnection<C>
+    where
+        Self: 'a,
+        C: 'a;
+
+    async fn connect<'a>(&'a mut self, host: &str, conn: C) -> Result<Self::Connection<'a>, Error>
+    where
+        C: 'a,
+    {
+        let server_name = rustls::ServerName::try_from(host).map_err(|_| Error::Network(ErrorKind::InvalidInput))?;
+        let session = rustls::ClientConnection::new(self.config.clone(), server_name).map_err(tls_error)?;
+        let mut conn = RustlsConnection { conn, session };
+
+        while conn.session.is_handshaking() {
+            conn.write_records().await?;
+            if conn.session.wants_read() && conn.read_records().await? == 0 {
+                return Err(Error::ConnectionAborted);
+            }
+        }
+        conn.write_records().await?;
+        Ok(conn)
+    }
+}
+
+/// A TLS connection established by a [`RustlsProvider`].
+pub struct RustlsConnection<C>
+where
+    C: Read + Write,
+{
+    conn: C,
+    session: rustls::ClientConnection,
+}
+
+impl<C> RustlsConnection<C>
+where
+    C: Read + Write,
+{
+    /// Write all pending TLS records to the connection.
+    async fn write_records(&mut self) -> Result<(), Error> {
+        let mut records = Vec::new();
+        while self.session.wants_write() {
+            self.session.write_tls(&mut records).map_err(io_error)?;
+        }
+        self.conn.write_all(&records).await.map_err(|e| e.kind())?;
+        self.conn.flush().await.map_err(|e| e.kind())?;
+        Ok(())
+    }
+
+    /// Read TLS records from the connection, returning the number of bytes read.
+    async fn read_records(&mut self) -> Result<usize, Error> {
+        let mut buf = [0; READ_BUF_LEN];
+        let len = self.conn.read(&mut buf).await.map_err(|e| e.kind())?;
+        // Passing an empty slice tells rustls that the connection is closed
+        self.session.read_tls(&mut &buf[..len]).map_err(io_error)?;
+        self.session.process_new_packets().map_err(tls_error)?;
+        Ok(len)
+    }
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::Network(e.kind().into())
+}
+
+fn tls_error(e: rustls::Error) -> Error {
+    warn!("TLS error: {:?}", e);
+    Error::Network(ErrorKind::InvalidData)
+}
+
+impl<C> ErrorType for RustlsConnection<C>
+where
+    C: Read + Write,
+{
+    type Error = Error;
+}
+
+impl<C> Read for RustlsConnection<C>
+where
+    C: Read + Write,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            match self.session.reader().read(buf) {
+                Ok(len) => return Ok(len),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    self.write_records().await?;
+                    self.read_records().await?;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Err(Error::ConnectionAborted),
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+    }
+}
+
+impl<C> Write for RustlsConnection<C>
+where
+    C: Read + Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = std::io::Write::write(&mut self.session.writer(), buf).map_err(io_error)?;
+        self.write_records().await?;
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.write_records().await
+    }
+}
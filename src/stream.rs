@@ -0,0 +1,64 @@
+/// Exposing response bodies as a [`Stream`] of chunks, to combine them with stream-based pipelines of the application.
+///
+use embedded_io_async::BufRead;
+use futures_core::Stream;
+use heapless::Vec;
+
+use crate::Error;
+
+/// Get a stream of the chunks of at most `N` bytes read from `reader`, such as a
+/// [`BodyReader`](crate::response::BodyReader).
+///
+/// Every chunk contains the bytes available in the buffer of the reader, up to `N` bytes, which are copied as the
+/// chunks are owned by the consumer of the stream. The stream ends after the first error.
+pub fn chunks<R, const N: usize>(reader: R) -> impl Stream<Item = Result<Vec<u8, N>, Error>>
+where
+    R: BufRead<Error = Error>,
+{
+    futures_util::stream::unfold(Some(reader), |reader| async move {
+        let mut reader = reader?;
+        let chunk = async {
+            let data = reader.fill_buf().await?;
+            let len = data.len().min(N);
+            let chunk = Vec::from_slice(&data[..len]).unwrap();
+            reader.consume(len);
+            Ok(chunk)
+        }
+        .await;
+        match chunk {
+            Ok(chunk) if chunk.is_empty() => None,
+            Ok(chunk) => Some((Ok(chunk), Some(reader))),
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::request::Method;
+    use crate::response::Response;
+
+    #[tokio::test]
+    async fn can_stream_body() {
+        let mut conn: &[u8] =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHELLO\r\n6\r\n WORLD\r\n0\r\n\r\n";
+        let mut header_buf = [0; 200];
+        let response = Response::read(&mut conn, Method::GET, &mut header_buf).await.unwrap();
+        let stream = chunks::<_, 4>(response.body().reader());
+        let chunks: std::vec::Vec<_> = stream.map(|chunk| chunk.unwrap()).collect().await;
+
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4));
+        assert_eq!(
+            b"HELLO WORLD",
+            chunks
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<std::vec::Vec<_>>()
+                .as_slice()
+        );
+    }
+}
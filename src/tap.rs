@@ -0,0 +1,239 @@
+/// A wire-tap passing the raw bytes written to and read from connections to a debugging hook.
+///
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::transport::ConnectionDecorator;
+
+/// The headers whose values are redacted.
+const SENSITIVE_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// The maximum length of a header name that is checked for redaction.
+const MAX_NAME_LEN: usize = 20;
+
+/// The direction of the bytes passed to a [`Tap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// The bytes were written to the connection.
+    Written,
+    /// The bytes were read from the connection.
+    Read,
+}
+
+/// A hook receiving the bytes written to and read from connections, e.g. to log them over defmt/RTT.
+pub trait Tap {
+    /// Receive bytes written to or read from a connection.
+    ///
+    /// The bytes of a single read or write may be passed in multiple parts.
+    fn tap(&self, direction: Direction, data: &[u8]);
+}
+
+impl<F> Tap for F
+where
+    F: Fn(Direction, &[u8]),
+{
+    fn tap(&self, direction: Direction, data: &[u8]) {
+        self(direction, data)
+    }
+}
+
+/// A [`ConnectionDecorator`] that passes the bytes written to and read from connections to a [`Tap`].
+///
+/// The decorator wraps the TCP connection, so the bytes of HTTPS connections are the encrypted TLS records.
+/// By default, the values of the `Authorization`, `Proxy-Authorization`, `Cookie` and `Set-Cookie` headers are
+/// replaced with `*`. Lines of the body that look like such a header are redacted as well.
+#[derive(Clone, Copy)]
+pub struct WireTap<'a> {
+    tap: &'a dyn Tap,
+    redact: bool,
+}
+
+impl<'a> WireTap<'a> {
+    /// Pass the bytes of connections to `tap`, redacting credentials.
+    pub fn new(tap: &'a dyn Tap) -> Self {
+        Self { tap, redact: true }
+    }
+
+    /// Set whether the values of headers containing credentials are redacted.
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+}
+
+impl<'a, C> ConnectionDecorator<C> for WireTap<'a>
+where
+    C: Read + Write,
+{
+    type Connection = TappedConnection<'a, C>;
+
+    fn decorate(&self, conn: C) -> Self::Connection {
+        TappedConnection {
+            conn,
+            tap: self.tap,
+            written: Redactor::new(self.redact),
+            read: Redactor::new(self.redact),
+        }
+    }
+}
+
+/// A connection whose bytes are passed to a [`Tap`].
+pub struct TappedConnection<'a, C>
+where
+    C: Read + Write,
+{
+    conn: C,
+    tap: &'a dyn Tap,
+    written: Redactor,
+    read: Redactor,
+}
+
+impl<C> ErrorType for TappedConnection<'_, C>
+where
+    C: Read + Write,
+{
+    type Error = ErrorKind;
+}
+
+impl<C> Read for TappedConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.conn.read(buf).await.map_err(|e| e.kind())?;
+        self.read.tap(self.tap, Direction::Read, &buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<C> Write for TappedConnection<'_, C>
+where
+    C: Read + Write,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = self.conn.write(buf).await.map_err(|e| e.kind())?;
+        self.written.tap(self.tap, Direction::Written, &buf[..len]);
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.conn.flush().await.map_err(|e| e.kind())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    /// The start of the line, which may be a header name.
+    Name,
+    /// The value of a header that is redacted.
+    Redacted,
+    /// The remainder of any other line.
+    Other,
+}
+
+/// Redacts the values of sensitive headers in a stream of bytes, one direction of a connection.
+struct Redactor {
+    enabled: bool,
+    line: Line,
+    name: heapless::Vec<u8, MAX_NAME_LEN>,
+}
+
+impl Redactor {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            line: Line::Name,
+            name: heapless::Vec::new(),
+        }
+    }
+
+    /// Pass `data` to `tap`, with the values of sensitive headers replaced.
+    fn tap(&mut self, tap: &dyn Tap, direction: Direction, data: &[u8]) {
+        if !self.enabled {
+            tap.tap(direction, data);
+            return;
+        }
+
+        let mut start = 0;
+        for (i, b) in data.iter().enumerate() {
+            match self.line {
+                Line::Name if *b == b':' => {
+                    let name = core::str::from_utf8(&self.name).unwrap_or_default();
+                    if SENSITIVE_HEADERS.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+                        tap.tap(direction, &data[start..=i]);
+                        start = i + 1;
+                        self.line = Line::Redacted;
+                    } else {
+                        self.line = Line::Other;
+                    }
+                }
+                Line::Name if *b != b'\n' && self.name.push(*b).is_err() => self.line = Line::Other,
+                Line::Redacted if *b == b'\r' || *b == b'\n' => {
+                    redacted(tap, direction, i - start);
+                    start = i;
+                    self.line = Line::Other;
+                }
+                _ => {}
+            }
+            if *b == b'\n' {
+                self.line = Line::Name;
+                self.name.clear();
+            }
+        }
+
+        if self.line == Line::Redacted {
+            redacted(tap, direction, data.len() - start);
+        } else {
+            tap.tap(direction, &data[start..]);
+        }
+    }
+}
+
+/// Pass `len` redacted bytes to `tap`.
+fn redacted(tap: &dyn Tap, direction: Direction, mut len: usize) {
+    const STARS: [u8; 16] = [b'*'; 16];
+    while len > 0 {
+        let part = len.min(STARS.len());
+        tap.tap(direction, &STARS[..part]);
+        len -= part;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+
+    fn redact(parts: &[&[u8]]) -> Vec<u8> {
+        let tapped = RefCell::new(Vec::new());
+        let tap = |_: Direction, data: &[u8]| tapped.borrow_mut().extend_from_slice(data);
+        let mut redactor = Redactor::new(true);
+        for part in parts {
+            redactor.tap(&tap, Direction::Written, part);
+        }
+        tapped.into_inner()
+    }
+
+    #[test]
+    fn redacts_sensitive_headers() {
+        assert_eq!(
+            b"GET / HTTP/1.1\r\nauthorization:*************\r\nHost: a\r\nCookie:*****\r\n\r\n".as_slice(),
+            redact(&[b"GET / HTTP/1.1\r\nauthorization: Basic secret\r\nHost: a\r\nCookie: id=1\r\n\r\n".as_slice()])
+        );
+    }
+
+    #[test]
+    fn redacts_across_parts() {
+        assert_eq!(
+            b"GET / HTTP/1.1\r\nAuthorization:**************\r\n\r\nbody".as_slice(),
+            redact(&[
+                b"GET / HTTP/1.1\r\nAuthor".as_slice(),
+                b"ization: Bear",
+                b"er secret\r",
+                b"\n\r\nbody"
+            ])
+        );
+    }
+}
@@ -0,0 +1,162 @@
+/// Limiting the throughput of request bodies, e.g. to leave bandwidth for other sockets on a slow link.
+///
+use core::time::Duration;
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+
+use crate::request::RequestBody;
+use crate::retry::Delay;
+
+/// A writer that limits the throughput of the wrapped writer to a number of bytes per second.
+///
+/// Writes are split into parts of at most one second worth of bytes, and every part is followed by a delay
+/// proportional to its size. The time spent writing is not accounted for, so the throughput stays below the limit.
+/// The delays are only as accurate as the resolution of the [`Delay`] in use.
+pub struct ThrottledWriter<W, M> {
+    writer: W,
+    delay: M,
+    bytes_per_sec: u32,
+}
+
+impl<W, M> ThrottledWriter<W, M>
+where
+    W: Write,
+    M: Delay,
+{
+    /// Limit the throughput of `writer` to `bytes_per_sec`, waiting using the provided delay.
+    pub fn new(writer: W, delay: M, bytes_per_sec: u32) -> Self {
+        Self {
+            writer,
+            delay,
+            bytes_per_sec: bytes_per_sec.max(1),
+        }
+    }
+
+    /// Get the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W, M> ErrorType for ThrottledWriter<W, M>
+where
+    W: Write,
+{
+    type Error = W::Error;
+}
+
+impl<W, M> Write for ThrottledWriter<W, M>
+where
+    W: Write,
+    M: Delay,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.bytes_per_sec as usize);
+        let written = self.writer.write(&buf[..len]).await?;
+        if written > 0 {
+            let nanos = written as u64 * 1_000_000_000 / self.bytes_per_sec as u64;
+            self.delay.delay(Duration::from_nanos(nanos)).await;
+        }
+        Ok(written)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.writer.flush().await
+    }
+}
+
+/// A request body whose throughput is limited using a [`ThrottledWriter`].
+///
+/// The trailer fields of the body are written without limiting their throughput.
+pub struct Throttled<B, M> {
+    body: B,
+    delay: M,
+    bytes_per_sec: u32,
+}
+
+impl<B, M> Throttled<B, M>
+where
+    B: RequestBody,
+    M: Delay + Clone,
+{
+    /// Limit the throughput of writing `body` to `bytes_per_sec`, waiting using the provided delay.
+    pub fn new(body: B, delay: M, bytes_per_sec: u32) -> Self {
+        Self {
+            body,
+            delay,
+            bytes_per_sec,
+        }
+    }
+}
+
+impl<B, M> RequestBody for Throttled<B, M>
+where
+    B: RequestBody,
+    M: Delay + Clone,
+{
+    fn len(&self) -> Option<usize> {
+        self.body.len()
+    }
+
+    async fn write<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        let mut writer = ThrottledWriter::new(writer, self.delay.clone(), self.bytes_per_sec);
+        self.body.write(&mut writer).await
+    }
+
+    async fn write_trailers<W: Write>(&self, writer: &mut W) -> Result<(), W::Error> {
+        self.body.write_trailers(writer).await
+    }
+
+    fn error(&self) -> Option<embedded_io::ErrorKind> {
+        self.body.error()
+    }
+
+    async fn rewind(&self) -> bool {
+        self.body.rewind().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    /// A delay that only records the total duration waited for.
+    #[derive(Clone, Copy)]
+    struct RecordingDelay<'a>(&'a Cell<Duration>);
+
+    impl Delay for RecordingDelay<'_> {
+        async fn delay_secs(&mut self, secs: u64) {
+            self.delay(Duration::from_secs(secs)).await;
+        }
+
+        async fn delay(&mut self, duration: Duration) {
+            self.0.set(self.0.get() + duration);
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_throughput() {
+        let waited = Cell::new(Duration::ZERO);
+        let mut buf = Vec::new();
+        let mut writer = ThrottledWriter::new(&mut buf, RecordingDelay(&waited), 4);
+
+        assert_eq!(4, writer.write(b"0123456789").await.unwrap());
+        writer.write_all(b"456789").await.unwrap();
+        assert_eq!(b"0123456789", buf.as_slice());
+        assert_eq!(Duration::from_millis(2500), waited.get());
+    }
+
+    #[tokio::test]
+    async fn throttled_body() {
+        let waited = Cell::new(Duration::ZERO);
+        let body = Throttled::new(b"hello".as_slice(), RecordingDelay(&waited), 1000);
+        assert_eq!(Some(5), body.len());
+
+        let mut buf = Vec::new();
+        body.write(&mut buf).await.unwrap();
+        assert_eq!(b"hello", buf.as_slice());
+        assert_eq!(Duration::from_millis(5), waited.get());
+    }
+}
@@ -0,0 +1,243 @@
+/// Timeouts for the phases of a request, such as connecting and waiting for the response.
+///
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+use core::time::Duration;
+use embedded_io::{Error as _, ErrorKind, ErrorType};
+use embedded_io_async::{Read, Write};
+
+use crate::retry::Delay;
+
+/// The timeouts of the phases of a request, each of which is disabled unless set.
+///
+/// Every timeout applies to a single phase, so that e.g. a slow cellular link can be given a generous
+/// connect timeout, while a stalled download is still detected quickly.
+#[derive(Clone, Debug)]
+pub struct Timeouts<M> {
+    pub(crate) delay: M,
+    pub(crate) connect: Option<Duration>,
+    pub(crate) tls_handshake: Option<Duration>,
+    first_byte: Option<Duration>,
+    inactivity: Option<Duration>,
+}
+
+impl<M> Timeouts<M>
+where
+    M: Delay + Clone,
+{
+    /// Create timeouts that wait using the provided delay.
+    pub fn new(delay: M) -> Self {
+        Self {
+            delay,
+            connect: None,
+            tls_handshake: None,
+            first_byte: None,
+            inactivity: None,
+        }
+    }
+
+    /// Set the maximum duration of resolving the host and establishing the TCP connection.
+    pub fn connect(mut self, timeout: Duration) -> Self {
+        self.connect = Some(timeout);
+        self
+    }
+
+    /// Set the maximum duration of the TLS handshake.
+    ///
+    /// The reads and writes of the handshake are bounded by the first byte and inactivity timeouts as well.
+    pub fn tls_handshake(mut self, timeout: Duration) -> Self {
+        self.tls_handshake = Some(timeout);
+        self
+    }
+
+    /// Set the maximum duration between writing to the connection and receiving the first byte in return,
+    /// e.g. the time the server takes to start responding to a request or to the `ClientHello` of the TLS handshake.
+    pub fn first_byte(mut self, timeout: Duration) -> Self {
+        self.first_byte = Some(timeout);
+        self
+    }
+
+    /// Set the maximum duration of any other single read or write on the connection, including those of the
+    /// TLS handshake.
+    pub fn inactivity(mut self, timeout: Duration) -> Self {
+        self.inactivity = Some(timeout);
+        self
+    }
+}
+
+/// Wait for `future` to complete, or for the timeout to expire, whichever comes first.
+///
+/// Returns `None` if the timeout expired first. Without a timeout, the future is awaited until it completes.
+pub async fn with_timeout<M, F>(delay: &mut M, timeout: Option<Duration>, future: F) -> Option<F::Output>
+where
+    M: Delay,
+    F: Future,
+{
+    let Some(timeout) = timeout else {
+        return Some(future.await);
+    };
+    unless(future, delay.delay(timeout)).await
+}
+
+/// Wait for `future` to complete, unless `abort` completes first, in which case `None` is returned.
+///
+/// The `abort` future is polled first, so `future` is not polled at all if `abort` is already complete.
+pub(crate) async fn unless<F, A>(future: F, abort: A) -> Option<F::Output>
+where
+    F: Future,
+    A: Future,
+{
+    let mut future = pin!(future);
+    let mut abort = pin!(abort);
+    poll_fn(|cx| {
+        if abort.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        future.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+/// A connection whose reads and writes fail with [`ErrorKind::TimedOut`] when they take too long.
+pub struct TimeoutConnection<C, M>
+where
+    C: Read + Write,
+{
+    conn: C,
+    delay: M,
+    first_byte: Option<Duration>,
+    inactivity: Option<Duration>,
+    /// Whether data was written, and nothing has been read since.
+    awaiting_response: bool,
+}
+
+impl<C, M> TimeoutConnection<C, M>
+where
+    C: Read + Write,
+    M: Delay + Clone,
+{
+    /// Apply the first byte and inactivity timeouts of `timeouts` to the connection.
+    pub fn new(conn: C, timeouts: &Timeouts<M>) -> Self {
+        Self {
+            conn,
+            delay: timeouts.delay.clone(),
+            first_byte: timeouts.first_byte,
+            inactivity: timeouts.inactivity,
+            awaiting_response: false,
+        }
+    }
+
+    /// Get the wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.conn
+    }
+}
+
+impl<C, M> ErrorType for TimeoutConnection<C, M>
+where
+    C: Read + Write,
+{
+    type Error = ErrorKind;
+}
+
+impl<C, M> Read for TimeoutConnection<C, M>
+where
+    C: Read + Write,
+    M: Delay,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let timeout = if self.awaiting_response {
+            self.first_byte.or(self.inactivity)
+        } else {
+            self.inactivity
+        };
+        let len = with_timeout(&mut self.delay, timeout, self.conn.read(buf))
+            .await
+            .ok_or(ErrorKind::TimedOut)?
+            .map_err(|e| e.kind())?;
+        self.awaiting_response = false;
+        Ok(len)
+    }
+}
+
+impl<C, M> Write for TimeoutConnection<C, M>
+where
+    C: Read + Write,
+    M: Delay,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = with_timeout(&mut self.delay, self.inactivity, self.conn.write(buf))
+            .await
+            .ok_or(ErrorKind::TimedOut)?
+            .map_err(|e| e.kind())?;
+        self.awaiting_response = true;
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        with_timeout(&mut self.delay, self.inactivity, self.conn.flush())
+            .await
+            .ok_or(ErrorKind::TimedOut)?
+            .map_err(|e| e.kind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct TokioDelay;
+
+    impl Delay for TokioDelay {
+        async fn delay_secs(&mut self, secs: u64) {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+        }
+
+        async fn delay(&mut self, duration: Duration) {
+            tokio::time::sleep(duration).await;
+        }
+    }
+
+    /// A connection that accepts writes, but never responds.
+    struct SilentConnection;
+
+    impl ErrorType for SilentConnection {
+        type Error = Infallible;
+    }
+
+    impl Read for SilentConnection {
+        async fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> {
+            core::future::pending().await
+        }
+    }
+
+    impl Write for SilentConnection {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            Ok(buf.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn with_timeout_completes_before_expiry() {
+        let timeout = Some(Duration::from_millis(100));
+        assert_eq!(Some(1), with_timeout(&mut TokioDelay, timeout, async { 1 }).await);
+        assert_eq!(
+            None,
+            with_timeout(&mut TokioDelay, timeout, core::future::pending::<()>()).await
+        );
+    }
+
+    #[tokio::test]
+    async fn first_byte_timeout() {
+        let timeouts = Timeouts::new(TokioDelay).first_byte(Duration::from_millis(10));
+        let mut conn = TimeoutConnection::new(SilentConnection, &timeouts);
+
+        conn.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(Err(ErrorKind::TimedOut), conn.read(&mut buf).await);
+    }
+}
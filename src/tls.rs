@@ -0,0 +1,26 @@
+/// Pluggable TLS implementations for establishing HTTPS connections.
+///
+use embedded_io_async::{Read, Write};
+
+use crate::Error;
+
+/// A TLS implementation that establishes a secure connection over a connection `C`.
+///
+/// This allows using TLS stacks other than the built-in backends, such as TLS offloaded to a modem,
+/// with [`HttpClient::request_with_tls()`](crate::client::HttpClient::request_with_tls) and
+/// [`HttpClient::resource_with_tls()`](crate::client::HttpClient::resource_with_tls).
+pub trait TlsProvider<C>
+where
+    C: Read + Write,
+{
+    /// The secure connection, which usually borrows the buffers of the provider.
+    type Connection<'a>: Read + Write
+    where
+        Self: 'a,
+        C: 'a;
+
+    /// Perform the TLS handshake with `host` over the established connection `conn`.
+    async fn connect<'a>(&'a mut self, host: &str, conn: C) -> Result<Self::Connection<'a>, Error>
+    where
+        C: 'a;
+}
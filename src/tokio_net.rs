@@ -0,0 +1,91 @@
+/// `embedded-nal-async` adapters for tokio, to use [`HttpClient`](crate::client::HttpClient) on hosts.
+///
+use core::net::{IpAddr, SocketAddr};
+
+use embedded_io_adapters::tokio_1::FromTokio;
+use embedded_io_async::{ErrorType, Read, Write};
+use embedded_nal_async::{AddrType, Dns, TcpConnect};
+use tokio::net::TcpStream;
+
+use crate::TryBufRead;
+
+/// Establishes TCP connections using tokio.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioTcp;
+
+/// A TCP connection established by [`TokioTcp`].
+pub struct TokioStream(FromTokio<TcpStream>);
+
+impl TokioStream {
+    /// Get the underlying tokio stream.
+    pub fn into_inner(self) -> TcpStream {
+        self.0.into_inner()
+    }
+}
+
+impl TcpConnect for TokioTcp {
+    type Error = std::io::Error;
+    type Connection<'m> = TokioStream;
+
+    async fn connect<'m>(&'m self, remote: SocketAddr) -> Result<Self::Connection<'m>, Self::Error> {
+        let stream = TcpStream::connect(remote).await?;
+        stream.set_nodelay(true)?;
+        Ok(TokioStream(FromTokio::new(stream)))
+    }
+}
+
+impl ErrorType for TokioStream {
+    type Error = std::io::Error;
+}
+
+impl Read for TokioStream {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.read(buf).await
+    }
+}
+
+impl Write for TokioStream {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.flush().await
+    }
+}
+
+impl TryBufRead for TokioStream {}
+
+/// Resolves host names using the resolver of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioDns;
+
+impl Dns for TokioDns {
+    type Error = std::io::Error;
+
+    async fn get_host_by_name(&self, host: &str, addr_type: AddrType) -> Result<IpAddr, Self::Error> {
+        for address in tokio::net::lookup_host((host, 0)).await? {
+            match address {
+                SocketAddr::V4(a) if addr_type != AddrType::IPv6 => return Ok(IpAddr::V4(*a.ip())),
+                SocketAddr::V6(a) if addr_type != AddrType::IPv4 => return Ok(IpAddr::V6(*a.ip())),
+                _ => {}
+            }
+        }
+        Err(std::io::ErrorKind::AddrNotAvailable.into())
+    }
+
+    async fn get_host_by_address(&self, _: IpAddr, _: &mut [u8]) -> Result<usize, Self::Error> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_resolve_localhost() {
+        let address = TokioDns.get_host_by_name("localhost", AddrType::Either).await.unwrap();
+        assert!(address.is_loopback());
+    }
+}
@@ -0,0 +1,223 @@
+/// Propagation of the [W3C Trace Context](https://www.w3.org/TR/trace-context/) in the `traceparent` and
+/// `tracestate` headers, such that requests show up in distributed tracing backends.
+///
+use core::fmt::Write as _;
+use heapless::{String, Vec};
+
+/// The length of a version `00` `traceparent` header value.
+pub const TRACEPARENT_LEN: usize = 55;
+
+/// The `sampled` trace flag.
+const FLAG_SAMPLED: u8 = 0x01;
+
+/// A source of random trace and span ids.
+pub trait IdSource {
+    /// Fill `id` with random bytes.
+    fn fill_id(&mut self, id: &mut [u8]);
+}
+
+impl<R> IdSource for R
+where
+    R: rand_core::RngCore,
+{
+    fn fill_id(&mut self, id: &mut [u8]) {
+        self.fill_bytes(id)
+    }
+}
+
+/// The trace context of a request, identifying the trace and the span of the caller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TraceContext {
+    trace_id: [u8; 16],
+    parent_id: [u8; 8],
+    flags: u8,
+}
+
+impl TraceContext {
+    /// Start a new trace with random ids.
+    pub fn new<S: IdSource>(source: &mut S, sampled: bool) -> Self {
+        Self {
+            trace_id: random_id(source),
+            parent_id: random_id(source),
+            flags: if sampled { FLAG_SAMPLED } else { 0 },
+        }
+    }
+
+    /// Parse a received `traceparent` header value, e.g. to continue the trace of a request to the device.
+    ///
+    /// Returns `None` if the value is invalid, in which case a new trace should be started.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        // Later versions may append fields, which are ignored
+        let version = parse_hex::<1>(value.get(..2)?)?[0];
+        let valid_len = match version {
+            0x00 => value.len() == TRACEPARENT_LEN,
+            0xff => false,
+            _ => value.len() == TRACEPARENT_LEN || value.get(TRACEPARENT_LEN) == Some(&b'-'),
+        };
+        if !valid_len || value[2] != b'-' || value[35] != b'-' || value[52] != b'-' {
+            return None;
+        }
+
+        let trace_id = parse_hex::<16>(&value[3..35])?;
+        let parent_id = parse_hex::<8>(&value[36..52])?;
+        let flags = parse_hex::<1>(&value[53..55])?[0];
+        if trace_id == [0; 16] || parent_id == [0; 8] {
+            return None;
+        }
+        Some(Self {
+            trace_id,
+            parent_id,
+            flags,
+        })
+    }
+
+    /// Create the context of a request made within this trace, with a random span id.
+    pub fn child<S: IdSource>(&self, source: &mut S) -> Self {
+        Self {
+            parent_id: random_id(source),
+            ..*self
+        }
+    }
+
+    /// The id of the trace.
+    pub fn trace_id(&self) -> [u8; 16] {
+        self.trace_id
+    }
+
+    /// The id of the span of the caller.
+    pub fn parent_id(&self) -> [u8; 8] {
+        self.parent_id
+    }
+
+    /// Whether the caller may have recorded the trace.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & FLAG_SAMPLED != 0
+    }
+
+    /// Format the `traceparent` header value.
+    pub fn traceparent(&self) -> String<TRACEPARENT_LEN> {
+        let mut value = String::new();
+        value.push_str("00-").unwrap();
+        for b in self.trace_id {
+            write!(value, "{:02x}", b).unwrap();
+        }
+        value.push('-').unwrap();
+        for b in self.parent_id {
+            write!(value, "{:02x}", b).unwrap();
+        }
+        write!(value, "-{:02x}", self.flags & FLAG_SAMPLED).unwrap();
+        value
+    }
+
+    /// Get the headers propagating this context, with the `tracestate` of the vendors if any.
+    pub fn headers<'a>(&self, tracestate: Option<&'a str>) -> TraceHeaders<'a> {
+        TraceHeaders {
+            traceparent: self.traceparent(),
+            tracestate,
+        }
+    }
+}
+
+/// The `traceparent` and `tracestate` headers of a request.
+pub struct TraceHeaders<'a> {
+    traceparent: String<TRACEPARENT_LEN>,
+    tracestate: Option<&'a str>,
+}
+
+impl TraceHeaders<'_> {
+    /// The headers to pass to [`RequestBuilder::headers()`](crate::request::RequestBuilder::headers).
+    pub fn as_headers(&self) -> Vec<(&str, &str), 2> {
+        let mut headers = Vec::new();
+        headers.push(("traceparent", self.traceparent.as_str())).unwrap();
+        if let Some(tracestate) = self.tracestate.filter(|s| !s.is_empty()) {
+            headers.push(("tracestate", tracestate)).unwrap();
+        }
+        headers
+    }
+}
+
+fn random_id<S: IdSource, const N: usize>(source: &mut S) -> [u8; N] {
+    let mut id = [0; N];
+    // An all-zero id is invalid
+    while id == [0; N] {
+        source.fill_id(&mut id);
+    }
+    id
+}
+
+/// Parse lowercase hex digits into `N` bytes.
+fn parse_hex<const N: usize>(hex: &[u8]) -> Option<[u8; N]> {
+    if hex.len() != 2 * N {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+        let digit = |b: u8| match b {
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            _ => None,
+        };
+        *byte = digit(pair[0])? << 4 | digit(pair[1])?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u8);
+
+    impl IdSource for Counter {
+        fn fill_id(&mut self, id: &mut [u8]) {
+            for b in id {
+                *b = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+    }
+
+    #[test]
+    fn can_parse_and_format_traceparent() {
+        let value = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::parse(value.as_bytes()).unwrap();
+        assert!(context.is_sampled());
+        assert_eq!([0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7], context.parent_id());
+        assert_eq!(value, context.traceparent().as_str());
+
+        let child = context.child(&mut Counter(1));
+        assert_eq!(context.trace_id(), child.trace_id());
+        assert_eq!(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0102030405060708-01",
+            child.traceparent().as_str()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_traceparent() {
+        for value in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        ] {
+            assert_eq!(None, TraceContext::parse(value.as_bytes()));
+        }
+        assert!(TraceContext::parse(b"01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_some());
+    }
+
+    #[test]
+    fn new_trace_headers() {
+        let context = TraceContext::new(&mut Counter(0), false);
+        let headers = context.headers(Some("vendor=value"));
+        let headers = headers.as_headers();
+        assert_eq!(
+            ("traceparent", "00-000102030405060708090a0b0c0d0e0f-1011121314151617-00"),
+            headers[0]
+        );
+        assert_eq!(("tracestate", "vendor=value"), headers[1]);
+    }
+}
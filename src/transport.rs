@@ -0,0 +1,31 @@
+/// Wrapping of established connections for cross-cutting transport concerns.
+///
+use embedded_io_async::{Read, Write};
+
+/// Wraps the connections established by an [`HttpClient`](crate::client::HttpClient), e.g. to count the
+/// transferred bytes or to throttle the connection.
+pub trait ConnectionDecorator<C>
+where
+    C: Read + Write,
+{
+    /// The wrapped connection.
+    type Connection: Read + Write;
+
+    /// Wrap a newly established connection, before any TLS handshake is performed on it.
+    fn decorate(&self, conn: C) -> Self::Connection;
+}
+
+/// A decorator that leaves connections as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Undecorated;
+
+impl<C> ConnectionDecorator<C> for Undecorated
+where
+    C: Read + Write,
+{
+    type Connection = C;
+
+    fn decorate(&self, conn: C) -> Self::Connection {
+        conn
+    }
+}
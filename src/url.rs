@@ -0,0 +1,404 @@
+/// Helpers for working with request URLs.
+///
+use crate::Error;
+use core::net::IpAddr;
+use heapless::String;
+use nourl::UrlScheme;
+
+/// A parsed `http` or `https` URL.
+///
+/// In addition to host names and IPv4 addresses, the host may be an IPv6 literal in brackets,
+/// e.g. `https://[2001:db8::1]:8443/path`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Url<'a> {
+    origin: &'a str,
+    scheme: UrlScheme,
+    host: &'a str,
+    port: Option<u16>,
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    /// Parse a URL with the `http` or `https` scheme.
+    pub fn parse(url: &'a str) -> Result<Self, nourl::Error> {
+        let (scheme, rest) = url.split_once("://").ok_or(nourl::Error::NoScheme)?;
+        let scheme = if scheme.eq_ignore_ascii_case("http") {
+            UrlScheme::HTTP
+        } else if scheme.eq_ignore_ascii_case("https") {
+            UrlScheme::HTTPS
+        } else {
+            return Err(nourl::Error::UnsupportedScheme);
+        };
+
+        let (authority, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+        let origin = &url[..url.len() - rest.len() + authority.len()];
+        // The fragment is not sent to the server
+        let path = match &path[..path.find('#').unwrap_or(path.len())] {
+            "" => "/",
+            path => path,
+        };
+
+        // The colons of an IPv6 literal are not port separators
+        let port_start = match authority.find(']') {
+            Some(end) if authority.starts_with('[') => end + 1,
+            _ => 0,
+        };
+        let (host, port) = match authority[port_start..].find(':') {
+            Some(index) => {
+                let (host, port) = authority.split_at(port_start + index);
+                (host, Some(port[1..].parse().map_err(|_| nourl::Error::InvalidPort)?))
+            }
+            None => (authority, None),
+        };
+
+        Ok(Self {
+            origin,
+            scheme,
+            host,
+            port,
+            path,
+        })
+    }
+
+    /// The scheme and authority of the URL, e.g. `http://example.com:8080`.
+    pub fn origin(&self) -> &'a str {
+        self.origin
+    }
+
+    /// The scheme of the URL.
+    pub fn scheme(&self) -> UrlScheme {
+        self.scheme
+    }
+
+    /// The host of the URL, without the brackets of an IPv6 literal.
+    pub fn host(&self) -> &'a str {
+        self.host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(self.host)
+    }
+
+    /// The host of the URL, including the brackets of an IPv6 literal.
+    pub fn bracketed_host(&self) -> &'a str {
+        self.host
+    }
+
+    /// The host and port of the URL as sent in the `Host` header, e.g. `example.com:8080`.
+    ///
+    /// The port is only included if it is not the default port of the scheme.
+    pub fn authority(&self) -> &'a str {
+        let default_port = if self.scheme == UrlScheme::HTTPS { 443 } else { 80 };
+        match self.port {
+            // The origin ends with the authority of the URL
+            Some(port) if port != default_port => &self.origin[self.origin.find("://").unwrap() + 3..],
+            _ => self.host,
+        }
+    }
+
+    /// The address of the host if it is an IP literal, in which case it does not need to be resolved.
+    pub fn ip(&self) -> Option<IpAddr> {
+        ip_literal(self.host)
+    }
+
+    /// The port of the URL, if specified.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The port of the URL, or the default port of the scheme if it is not specified.
+    pub fn port_or_default(&self) -> u16 {
+        self.port.unwrap_or(match self.scheme {
+            UrlScheme::HTTP => 80,
+            UrlScheme::HTTPS => 443,
+        })
+    }
+
+    /// The path of the URL, including any query but not the fragment.
+    ///
+    /// A URL without a path has the path `/`, unless it has a query, in which case the path is the query,
+    /// e.g. `?x=1`, which requests complete to `/?x=1`.
+    pub fn path(&self) -> &'a str {
+        self.path
+    }
+}
+
+/// Resolve a `Location` header value against the URL of the request that produced it.
+///
+/// The location may be an absolute URL, a scheme-relative URL (`//host/path`), an absolute
+/// path (`/path`) or a path relative to the request path (`other`, `../other`, `?query`).
+/// Dot segments are removed from the resulting path as specified in
+/// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2).
+///
+/// The absolute target URL is returned, and can be passed to [`Url::parse()`].
+pub fn resolve_location<const N: usize>(base: &Url<'_>, location: &str) -> Result<String<N>, Error> {
+    let location = location.trim();
+    let mut target = String::new();
+
+    if has_scheme(location) {
+        push(&mut target, location)?;
+        return Ok(target);
+    }
+
+    push(&mut target, scheme_str(base.scheme()))?;
+    push(&mut target, ":")?;
+    if location.starts_with("//") {
+        push(&mut target, location)?;
+        return Ok(target);
+    }
+
+    push(&mut target, "//")?;
+    push(&mut target, base.authority())?;
+
+    let base_path = base.path();
+    let base_path = &base_path[..base_path.find('#').unwrap_or(base_path.len())];
+    let base_path_without_query = &base_path[..base_path.find('?').unwrap_or(base_path.len())];
+
+    if location.starts_with('/') {
+        push_path(&mut target, location)?;
+    } else if location.is_empty() || location.starts_with('#') {
+        // Keep the query of the base
+        push_path(&mut target, base_path)?;
+        push(&mut target, location)?;
+    } else if location.starts_with('?') {
+        push_path(&mut target, base_path_without_query)?;
+        push(&mut target, location)?;
+    } else {
+        // Merge the relative path with the "directory" of the base path
+        let directory = &base_path_without_query[..base_path_without_query.rfind('/').map(|i| i + 1).unwrap_or(0)];
+        let mut merged: String<N> = String::new();
+        if !directory.starts_with('/') {
+            push(&mut merged, "/")?;
+        }
+        push(&mut merged, directory)?;
+        push(&mut merged, location)?;
+        push_path(&mut target, &merged)?;
+    }
+
+    Ok(target)
+}
+
+/// Parse a host that is an IPv4 address or an IPv6 address, which may be in brackets.
+pub(crate) fn ip_literal(host: &str) -> Option<IpAddr> {
+    match host.strip_prefix('[').and_then(|host| host.strip_suffix(']')) {
+        Some(host) => host.parse().ok().filter(IpAddr::is_ipv6),
+        None => host.parse().ok(),
+    }
+}
+
+fn scheme_str(scheme: UrlScheme) -> &'static str {
+    if scheme == UrlScheme::HTTPS {
+        "https"
+    } else {
+        "http"
+    }
+}
+
+/// Check whether the reference starts with a scheme, e.g. `https:`.
+fn has_scheme(reference: &str) -> bool {
+    match reference.find(':') {
+        Some(0) | None => false,
+        Some(end) => {
+            let scheme = &reference[..end];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+    }
+}
+
+fn push<const N: usize>(target: &mut String<N>, s: &str) -> Result<(), Error> {
+    target.push_str(s).map_err(|_| Error::BufferTooSmall)
+}
+
+/// Append an absolute path, including any query and fragment, while removing dot segments.
+fn push_path<const N: usize>(target: &mut String<N>, path: &str) -> Result<(), Error> {
+    let (path, suffix) = path.split_at(path.find(['?', '#']).unwrap_or(path.len()));
+    let root = target.len();
+
+    let mut segments = path.strip_prefix('/').unwrap_or(path).split('/').peekable();
+    while let Some(segment) = segments.next() {
+        let last = segments.peek().is_none();
+        match segment {
+            "." => {}
+            ".." => {
+                let parent = target[root..].rfind('/').map(|i| root + i).unwrap_or(root);
+                target.truncate(parent);
+            }
+            segment => {
+                push(target, "/")?;
+                push(target, segment)?;
+                continue;
+            }
+        }
+        if last {
+            push(target, "/")?;
+        }
+    }
+
+    if target.len() == root {
+        push(target, "/")?;
+    }
+    push(target, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_url() {
+        let url = Url::parse("https://example.com:8443/a/b?x=1").unwrap();
+        assert_eq!(UrlScheme::HTTPS, url.scheme());
+        assert_eq!("example.com", url.host());
+        assert_eq!(Some(8443), url.port());
+        assert_eq!("/a/b?x=1", url.path());
+        assert_eq!("https://example.com:8443", url.origin());
+        assert_eq!("example.com:8443", url.authority());
+        assert_eq!(None, url.ip());
+
+        let url = Url::parse("HTTP://127.0.0.1").unwrap();
+        assert_eq!(UrlScheme::HTTP, url.scheme());
+        assert_eq!(80, url.port_or_default());
+        assert_eq!("/", url.path());
+        assert_eq!("HTTP://127.0.0.1", url.origin());
+        assert_eq!("127.0.0.1", url.authority());
+        assert_eq!(Some(IpAddr::from([127, 0, 0, 1])), url.ip());
+
+        let url = Url::parse("https://example.com:443/a").unwrap();
+        assert_eq!("example.com", url.authority());
+
+        assert!(matches!(Url::parse("localhost/a"), Err(nourl::Error::NoScheme)));
+        assert!(matches!(
+            Url::parse("ftp://localhost/a"),
+            Err(nourl::Error::UnsupportedScheme)
+        ));
+    }
+
+    #[test]
+    fn can_parse_ipv6_literal() {
+        let url = Url::parse("https://[2001:db8::1]:8443/path").unwrap();
+        assert_eq!("2001:db8::1", url.host());
+        assert_eq!("[2001:db8::1]", url.bracketed_host());
+        assert_eq!("[2001:db8::1]:8443", url.authority());
+        assert_eq!(Some(8443), url.port());
+        assert_eq!("/path", url.path());
+        assert_eq!(Some(IpAddr::from([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1])), url.ip());
+
+        let url = Url::parse("http://[::1]").unwrap();
+        assert_eq!("::1", url.host());
+        assert_eq!("[::1]", url.bracketed_host());
+        assert_eq!(80, url.port_or_default());
+        assert_eq!("/", url.path());
+        assert_eq!(Some(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])), url.ip());
+    }
+
+    #[test]
+    fn cannot_parse_invalid_port() {
+        assert!(matches!(
+            Url::parse("http://localhost:http/a"),
+            Err(nourl::Error::InvalidPort)
+        ));
+        assert!(matches!(
+            Url::parse("http://localhost:65536"),
+            Err(nourl::Error::InvalidPort)
+        ));
+        assert!(matches!(Url::parse("http://[::1]:/a"), Err(nourl::Error::InvalidPort)));
+    }
+
+    #[test]
+    fn can_parse_url_without_path() {
+        let url = Url::parse("http://example.com?x=1").unwrap();
+        assert_eq!("example.com", url.host());
+        assert_eq!(None, url.port());
+        assert_eq!("?x=1", url.path());
+        assert_eq!("http://example.com", url.origin());
+
+        let url = Url::parse("http://example.com:8080#top").unwrap();
+        assert_eq!("example.com", url.host());
+        assert_eq!(Some(8080), url.port());
+        assert_eq!("/", url.path());
+
+        let url = Url::parse("http://example.com/a?x=1#top").unwrap();
+        assert_eq!("/a?x=1", url.path());
+    }
+
+    #[test]
+    fn ipv6_literal_location() {
+        assert_eq!("http://[::1]:8080/other", resolve("http://[::1]:8080/a/b", "/other"));
+    }
+
+    fn resolve(base: &str, location: &str) -> String<128> {
+        resolve_location(&Url::parse(base).unwrap(), location).unwrap()
+    }
+
+    #[test]
+    fn absolute_location() {
+        assert_eq!(
+            "https://example.com/other",
+            resolve("http://localhost/a/b", "https://example.com/other")
+        );
+    }
+
+    #[test]
+    fn scheme_relative_location() {
+        assert_eq!(
+            "https://example.com/other",
+            resolve("https://localhost/a/b", "//example.com/other")
+        );
+    }
+
+    #[test]
+    fn absolute_path_location() {
+        assert_eq!(
+            "http://localhost/other/path",
+            resolve("http://localhost/a/b", "/other/path")
+        );
+        assert_eq!(
+            "http://localhost:8080/other",
+            resolve("http://localhost:8080/a/b", "/other")
+        );
+        assert_eq!("https://localhost/other", resolve("https://localhost:443/a", "/other"));
+    }
+
+    #[test]
+    fn relative_path_location() {
+        assert_eq!("http://localhost/a/c", resolve("http://localhost/a/b", "c"));
+        assert_eq!("http://localhost/a/c/", resolve("http://localhost/a/b", "c/"));
+        assert_eq!("http://localhost/c", resolve("http://localhost/a/b", "../c"));
+        assert_eq!(
+            "http://localhost/a/c?x=1",
+            resolve("http://localhost/a/b?y=2", "./c?x=1")
+        );
+        assert_eq!("http://localhost/", resolve("http://localhost/a/b", ".."));
+        assert_eq!("http://localhost/c", resolve("http://localhost", "c"));
+    }
+
+    #[test]
+    fn query_and_fragment_location() {
+        assert_eq!("http://localhost/a/b?x=1", resolve("http://localhost/a/b?y=2", "?x=1"));
+        assert_eq!(
+            "http://localhost/a/b?y=2#top",
+            resolve("http://localhost/a/b?y=2", "#top")
+        );
+    }
+
+    #[test]
+    fn dot_segments_are_removed() {
+        assert_eq!(
+            "http://localhost/a/g",
+            resolve("http://localhost/a/b/c", "/a/b/../../a/./g")
+        );
+        assert_eq!("http://localhost/g", resolve("http://localhost/a", "/../../g"));
+    }
+
+    #[test]
+    fn too_small_buffer() {
+        let base = Url::parse("http://localhost/a").unwrap();
+        assert!(matches!(
+            resolve_location::<8>(&base, "/other"),
+            Err(Error::BufferTooSmall)
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+/// Conditional requests using the `ETag` and `Last-Modified` validators of previous responses.
+///
+use core::fmt::Write as _;
+use embedded_io_async::Read;
+use heapless::String;
+
+use crate::request::Request;
+use crate::response::{Response, Status};
+use crate::Error;
+
+/// The maximum length of the key of a resource, i.e. the host and path of the request.
+pub const MAX_KEY_LEN: usize = 256;
+
+/// The maximum length of a stored `ETag`. Longer entity tags are not used for revalidation.
+pub const MAX_ETAG_LEN: usize = 64;
+
+/// The maximum length of a stored `Last-Modified` value, which fits any valid HTTP-date.
+pub const MAX_LAST_MODIFIED_LEN: usize = 40;
+
+/// The validators of a response, used to make a subsequent request conditional.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// The `ETag` of the response, sent as `If-None-Match`.
+    pub etag: Option<String<MAX_ETAG_LEN>>,
+    /// The `Last-Modified` value of the response, sent as `If-Modified-Since`.
+    pub last_modified: Option<String<MAX_LAST_MODIFIED_LEN>>,
+}
+
+impl Validators {
+    /// Get the validators of a response.
+    pub fn from_response<C, const MAX_HEADERS: usize>(response: &Response<'_, '_, C, MAX_HEADERS>) -> Self
+    where
+        C: Read,
+    {
+        let mut validators = Self::default();
+        validators.update(response);
+        validators
+    }
+
+    /// Replace the validators with those present in a response, e.g. a `304 Not Modified` response.
+    pub fn update<C, const MAX_HEADERS: usize>(&mut self, response: &Response<'_, '_, C, MAX_HEADERS>)
+    where
+        C: Read,
+    {
+        if let Some(etag) = response.header("etag") {
+            self.etag = core::str::from_utf8(etag)
+                .ok()
+                .and_then(|etag| String::try_from(etag).ok());
+        }
+        if let Some(last_modified) = response.header("last-modified") {
+            // The value is sent back as is, but only if it is a valid date
+            self.last_modified = response
+                .last_modified()
+                .and_then(|_| core::str::from_utf8(last_modified).ok())
+                .and_then(|last_modified| String::try_from(last_modified).ok());
+        }
+    }
+
+    /// Whether there are no validators, i.e. a request cannot be made conditional.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Make the request conditional on the validators.
+    pub(crate) fn apply<'req, B>(&'req self, request: &mut Request<'req, B>)
+    where
+        B: crate::request::RequestBody,
+    {
+        request.if_none_match = self.etag.as_deref();
+        request.if_modified_since = self.last_modified.as_deref();
+    }
+}
+
+/// Storage of the validators of previous responses, e.g. in RAM or flash.
+pub trait ValidatorStore {
+    /// Load the validators stored for `key`.
+    async fn load(&mut self, key: &str) -> Result<Option<Validators>, Error>;
+
+    /// Store the validators for `key`, replacing any previously stored validators.
+    async fn store(&mut self, key: &str, validators: &Validators) -> Result<(), Error>;
+
+    /// Remove the validators stored for `key`, if any.
+    async fn remove(&mut self, key: &str) -> Result<(), Error>;
+}
+
+/// The outcome of a conditional request.
+pub enum Conditional<'resp, 'buf, C, const MAX_HEADERS: usize = 64>
+where
+    C: Read,
+{
+    /// The resource was not modified since the validators were stored.
+    NotModified,
+    /// The response to the request, which is not a `304 Not Modified` response.
+    Modified(Response<'resp, 'buf, C, MAX_HEADERS>),
+}
+
+/// Load the validators stored for `key`, ignoring storage errors.
+pub(crate) async fn load<S: ValidatorStore>(store: &mut S, key: &str) -> Validators {
+    match store.load(key).await {
+        Ok(validators) => validators.unwrap_or_default(),
+        Err(e) => {
+            warn!("Unable to load validators: {:?}", e);
+            Validators::default()
+        }
+    }
+}
+
+/// Store the validators of a successful response for `key`, ignoring storage errors.
+pub(crate) async fn store<S: ValidatorStore, C: Read, const MAX_HEADERS: usize>(
+    store: &mut S,
+    key: &str,
+    response: &Response<'_, '_, C, MAX_HEADERS>,
+) {
+    if response.status != Status::Ok {
+        return;
+    }
+
+    let validators = Validators::from_response(response);
+    let result = if validators.is_empty() {
+        store.remove(key).await
+    } else {
+        store.store(key, &validators).await
+    };
+    if let Err(e) = result {
+        warn!("Unable to store validators: {:?}", e);
+    }
+}
+
+/// The key of a resource, which is the host and the path as written in the request line.
+pub(crate) fn resource_key(host: &str, base_path: &str, path: &str) -> Result<String<MAX_KEY_LEN>, Error> {
+    let mut key = String::new();
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    write!(key, "{}{}{}{}", host, base_path.trim_end_matches('/'), separator, path)
+        .map_err(|_| Error::BufferTooSmall)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{HttpConnection, HttpResource};
+    use crate::fake::FakeConnection;
+    use crate::response::ResponseConfig;
+
+    #[derive(Default)]
+    struct MemoryStore(Option<(std::string::String, Validators)>);
+
+    impl ValidatorStore for MemoryStore {
+        async fn load(&mut self, key: &str) -> Result<Option<Validators>, Error> {
+            Ok(self.0.as_ref().filter(|(k, _)| k == key).map(|(_, v)| v.clone()))
+        }
+
+        async fn store(&mut self, key: &str, validators: &Validators) -> Result<(), Error> {
+            self.0 = Some((key.into(), validators.clone()));
+            Ok(())
+        }
+
+        async fn remove(&mut self, key: &str) -> Result<(), Error> {
+            if self.0.as_ref().is_some_and(|(k, _)| k == key) {
+                self.0 = None;
+            }
+            Ok(())
+        }
+    }
+
+    /// Get `/config` conditionally, returning the body if modified and the request sent to the server.
+    async fn get(store: &mut MemoryStore, response: &'static [u8]) -> (Option<Vec<u8>>, Vec<u8>) {
+        let mut conn = FakeConnection::new(response);
+        let mut resource = HttpResource::<_> {
+            conn: HttpConnection::Plain(&mut conn),
+            host: "example.com",
+            base_path: "/api",
+            response_config: ResponseConfig::new(),
+            forward_proxy: None,
+            metrics: None,
+            auth: None,
+            credentials: None,
+        };
+        let mut rx_buf = [0; 256];
+        let body = match resource.get_conditional("config", store, &mut rx_buf).await.unwrap() {
+            Conditional::NotModified => None,
+            Conditional::Modified(response) => Some(response.body().read_to_end().await.unwrap().to_vec()),
+        };
+        (body, conn.request)
+    }
+
+    #[tokio::test]
+    async fn can_get_conditionally() {
+        let mut store = MemoryStore::default();
+
+        let (body, request) = get(
+            &mut store,
+            b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nLast-Modified: Sun, 06 Nov 1994 08:49:37 GMT\r\nContent-Length: 5\r\n\r\nHELLO",
+        )
+        .await;
+        assert_eq!(Some(b"HELLO".as_slice()), body.as_deref());
+        assert_eq!(
+            b"GET /api/config HTTP/1.1\r\nHost: example.com\r\n\r\n",
+            request.as_slice()
+        );
+        assert_eq!("example.com/api/config", store.0.as_ref().unwrap().0);
+
+        let (body, request) = get(&mut store, b"HTTP/1.1 304 Not Modified\r\nContent-Length: 5\r\n\r\n").await;
+        assert_eq!(None, body);
+        assert_eq!(
+            b"GET /api/config HTTP/1.1\r\nHost: example.com\r\nIf-None-Match: \"v1\"\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\r\n",
+            request.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn replaces_validators_of_modified_resource() {
+        let mut store = MemoryStore::default();
+
+        get(
+            &mut store,
+            b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        get(
+            &mut store,
+            b"HTTP/1.1 200 OK\r\nETag: \"v2\"\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        assert_eq!(Some("\"v2\""), store.0.as_ref().unwrap().1.etag.as_deref());
+
+        // Errors do not affect the stored validators
+        get(
+            &mut store,
+            b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        assert_eq!(Some("\"v2\""), store.0.as_ref().unwrap().1.etag.as_deref());
+
+        get(&mut store, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(store.0.is_none());
+    }
+
+    #[test]
+    fn can_create_resource_key() {
+        assert_eq!(
+            "example.com/api/config",
+            resource_key("example.com", "/api/", "config").unwrap().as_str()
+        );
+    }
+}
@@ -0,0 +1,455 @@
+/// WebSocket client (RFC 6455), using the opening handshake to upgrade the connection of a request.
+///
+/// Frames are read and written as a whole, so the buffer must be large enough to contain the largest frame received.
+/// Control frames are returned like any other frame, so the application must answer pings and close frames itself.
+///
+use base64::engine::{general_purpose, Engine as _};
+use embedded_io::Error as _;
+use embedded_io_async::{Read, Write};
+use heapless::String;
+use rand_core::RngCore;
+use sha1::{Digest as _, Sha1};
+
+use crate::client::{HttpConnection, HttpRequestHandle};
+use crate::request::{Request, RequestBuilder};
+use crate::response::{Response, ResponseConfig, Status};
+use crate::Error;
+
+/// The GUID appended to the key of the handshake to compute the accept value.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The maximum payload length of a control frame.
+const MAX_CONTROL_PAYLOAD_LEN: usize = 125;
+
+/// The opcode of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Opcode {
+    Continuation = 0x0,
+    Text = 0x1,
+    Binary = 0x2,
+    Close = 0x8,
+    Ping = 0x9,
+    Pong = 0xa,
+}
+
+impl Opcode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0x0 => Some(Opcode::Continuation),
+            0x1 => Some(Opcode::Text),
+            0x2 => Some(Opcode::Binary),
+            0x8 => Some(Opcode::Close),
+            0x9 => Some(Opcode::Ping),
+            0xa => Some(Opcode::Pong),
+            _ => None,
+        }
+    }
+
+    /// Check whether this is the opcode of a control frame, which is never fragmented.
+    pub fn is_control(self) -> bool {
+        self as u8 & 0x8 != 0
+    }
+}
+
+/// A frame received from the server.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Frame<'a> {
+    /// Whether this is the final frame of a message.
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub payload: &'a [u8],
+}
+
+impl Frame<'_> {
+    /// Get the status code and reason of a close frame, if any.
+    pub fn close_reason(&self) -> Option<(u16, &str)> {
+        if self.opcode != Opcode::Close || self.payload.len() < 2 {
+            return None;
+        }
+        let code = u16::from_be_bytes([self.payload[0], self.payload[1]]);
+        Some((code, core::str::from_utf8(&self.payload[2..]).ok()?))
+    }
+}
+
+/// A WebSocket connection, after a successful opening handshake.
+pub struct WebSocket<'conn, 'buf, C, R>
+where
+    C: Read + Write,
+    R: RngCore,
+{
+    conn: &'conn mut C,
+    buf: &'buf mut [u8],
+    /// The start of the bytes in `buf` that were read but not yet returned.
+    pos: usize,
+    /// The end of the bytes in `buf` that were read.
+    len: usize,
+    /// The source of the masking keys of the frames that are written.
+    rng: R,
+}
+
+/// Upgrade the connection to a WebSocket by sending `request` as the opening handshake.
+///
+/// The response headers are read into `rx_buf`, which is then used to read frames. The masking keys and the key of
+/// the handshake are taken from `rng`, which should be a cryptographically secure random number generator.
+pub async fn connect<'conn, 'buf, C, R>(
+    conn: &'conn mut C,
+    request: &Request<'_, ()>,
+    mut rng: R,
+    rx_buf: &'buf mut [u8],
+    config: &ResponseConfig,
+) -> Result<WebSocket<'conn, 'buf, C, R>, Error>
+where
+    C: Read + Write,
+    R: RngCore,
+{
+    let mut nonce = [0; 16];
+    rng.fill_bytes(&mut nonce);
+    let mut key = [0; 24];
+    general_purpose::STANDARD
+        .encode_slice(nonce, &mut key)
+        .map_err(|_| Error::Codec)?;
+    let key = core::str::from_utf8(&key)?;
+
+    let headers = [
+        ("Upgrade", "websocket"),
+        ("Connection", "Upgrade"),
+        ("Sec-WebSocket-Key", key),
+        ("Sec-WebSocket-Version", "13"),
+    ];
+    request.write_header_with(conn, request.auth.as_ref(), &headers).await?;
+    conn.flush().await.map_err(|e| e.kind())?;
+
+    let response: Response<'_, '_, C> = Response::read_with_config(conn, request.method, rx_buf, config).await?;
+    if response.status != Status::SwitchingProtocols {
+        return Err(Error::UpgradeRejected(response.status));
+    }
+    let upgrade = response
+        .header("upgrade")
+        .is_some_and(|value| value.eq_ignore_ascii_case(b"websocket"));
+    let connection = response.connection.is_some_and(|connection| connection.upgrade);
+    let accept = accept_key(key)?;
+    if !upgrade || !connection || response.header("sec-websocket-accept") != Some(accept.as_bytes()) {
+        warn!("Invalid WebSocket handshake response");
+        return Err(Error::Codec);
+    }
+
+    let (conn, buf, len) = response.into_upgraded();
+    Ok(WebSocket {
+        conn,
+        buf,
+        pos: 0,
+        len,
+        rng,
+    })
+}
+
+impl<'conn, C> HttpRequestHandle<'conn, C, ()>
+where
+    C: Read + Write,
+{
+    /// Send the request as the opening handshake of a WebSocket, see [`connect()`].
+    pub async fn websocket<'req, 'buf, R: RngCore>(
+        &'req mut self,
+        rng: R,
+        rx_buf: &'buf mut [u8],
+    ) -> Result<WebSocket<'req, 'buf, HttpConnection<'conn, C>, R>, Error> {
+        let request = self.request.take().ok_or(Error::AlreadySent)?.build();
+        connect(&mut self.conn, &request, rng, rx_buf, &self.response_config).await
+    }
+}
+
+impl<C, R> WebSocket<'_, '_, C, R>
+where
+    C: Read + Write,
+    R: RngCore,
+{
+    /// Read the next frame.
+    ///
+    /// Fragmented messages are returned as a frame per fragment, where the following frames have the
+    /// [`Opcode::Continuation`] opcode and the final frame has `fin` set.
+    pub async fn read_frame(&mut self) -> Result<Frame<'_>, Error> {
+        let (first, header_len, end) = loop {
+            if let Some((header_len, payload_len)) = parse_header(&self.buf[self.pos..self.len])? {
+                let frame_len = header_len.checked_add(payload_len).ok_or(Error::BufferTooSmall)?;
+                if frame_len <= self.len - self.pos {
+                    break (self.buf[self.pos], header_len, self.pos + frame_len);
+                }
+                if frame_len > self.buf.len() {
+                    return Err(Error::BufferTooSmall);
+                }
+            }
+
+            // Move the partial frame to the beginning of the buffer
+            self.buf.copy_within(self.pos..self.len, 0);
+            self.len -= self.pos;
+            self.pos = 0;
+            if self.len == self.buf.len() {
+                return Err(Error::BufferTooSmall);
+            }
+
+            let n = self.conn.read(&mut self.buf[self.len..]).await.map_err(|e| e.kind())?;
+            if n == 0 {
+                return Err(Error::ConnectionAborted);
+            }
+            self.len += n;
+        };
+
+        let start = self.pos + header_len;
+        self.pos = end;
+        Ok(Frame {
+            fin: first & 0x80 != 0,
+            // The opcode was validated by parse_header()
+            opcode: Opcode::from_bits(first & 0x0f).unwrap(),
+            payload: &self.buf[start..end],
+        })
+    }
+
+    /// Write a frame, masked with a random masking key.
+    pub async fn write_frame(&mut self, fin: bool, opcode: Opcode, payload: &[u8]) -> Result<(), Error> {
+        if opcode.is_control() && (!fin || payload.len() > MAX_CONTROL_PAYLOAD_LEN) {
+            return Err(Error::Codec);
+        }
+
+        let mut header = [0; 14];
+        header[0] = (fin as u8) << 7 | opcode as u8;
+        let mut header_len = match payload.len() {
+            len @ 0..=125 => {
+                header[1] = len as u8;
+                2
+            }
+            len @ 126..=0xffff => {
+                header[1] = 126;
+                header[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+                4
+            }
+            len => {
+                header[1] = 127;
+                header[2..10].copy_from_slice(&(len as u64).to_be_bytes());
+                10
+            }
+        };
+        // Frames sent by a client are always masked
+        header[1] |= 0x80;
+        let mut mask = [0; 4];
+        self.rng.fill_bytes(&mut mask);
+        header[header_len..header_len + 4].copy_from_slice(&mask);
+        header_len += 4;
+        self.conn.write_all(&header[..header_len]).await.map_err(|e| e.kind())?;
+
+        // The chunk length is a multiple of 4, so every chunk starts at the first byte of the mask
+        let mut chunk = [0; 128];
+        for part in payload.chunks(chunk.len()) {
+            for (i, (masked, b)) in chunk.iter_mut().zip(part).enumerate() {
+                *masked = b ^ mask[i % 4];
+            }
+            self.conn.write_all(&chunk[..part.len()]).await.map_err(|e| e.kind())?;
+        }
+        self.conn.flush().await.map_err(|e| e.kind())?;
+        Ok(())
+    }
+
+    /// Write a text message in a single frame.
+    pub async fn write_text(&mut self, text: &str) -> Result<(), Error> {
+        self.write_frame(true, Opcode::Text, text.as_bytes()).await
+    }
+
+    /// Write a binary message in a single frame.
+    pub async fn write_binary(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.write_frame(true, Opcode::Binary, data).await
+    }
+
+    /// Write a ping, which the server answers with a pong with the same payload.
+    pub async fn write_ping(&mut self, payload: &[u8]) -> Result<(), Error> {
+        self.write_frame(true, Opcode::Ping, payload).await
+    }
+
+    /// Write a pong, e.g. to answer a ping with its payload.
+    pub async fn write_pong(&mut self, payload: &[u8]) -> Result<(), Error> {
+        self.write_frame(true, Opcode::Pong, payload).await
+    }
+
+    /// Write a close frame with a status code and reason, after which the server closes the connection.
+    pub async fn write_close(&mut self, code: u16, reason: &str) -> Result<(), Error> {
+        let mut payload = [0; MAX_CONTROL_PAYLOAD_LEN];
+        let len = 2 + reason.len();
+        if len > payload.len() {
+            return Err(Error::Codec);
+        }
+        payload[..2].copy_from_slice(&code.to_be_bytes());
+        payload[2..len].copy_from_slice(reason.as_bytes());
+        self.write_frame(true, Opcode::Close, &payload[..len]).await
+    }
+}
+
+/// Parse the header of a frame sent by the server, returning the length of the header and the payload if complete.
+fn parse_header(data: &[u8]) -> Result<Option<(usize, usize)>, Error> {
+    let [first, second, ..] = *data else {
+        return Ok(None);
+    };
+    // Extensions using the reserved bits are not negotiated, and frames sent by a server are never masked
+    if first & 0x70 != 0 || second & 0x80 != 0 {
+        return Err(Error::Codec);
+    }
+    let opcode = Opcode::from_bits(first & 0x0f).ok_or(Error::Codec)?;
+
+    let (header_len, payload_len) = match second & 0x7f {
+        126 => match data.get(2..4) {
+            Some(len) => (4, u16::from_be_bytes([len[0], len[1]]) as u64),
+            None => return Ok(None),
+        },
+        127 => match data.get(2..10) {
+            Some(len) => (10, u64::from_be_bytes(len.try_into().unwrap())),
+            None => return Ok(None),
+        },
+        len => (2, len as u64),
+    };
+    if opcode.is_control() && (first & 0x80 == 0 || payload_len > MAX_CONTROL_PAYLOAD_LEN as u64) {
+        return Err(Error::Codec);
+    }
+    let payload_len = usize::try_from(payload_len).map_err(|_| Error::BufferTooSmall)?;
+    Ok(Some((header_len, payload_len)))
+}
+
+/// Compute the `Sec-WebSocket-Accept` value expected in response to `key`.
+fn accept_key(key: &str) -> Result<String<28>, Error> {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(GUID.as_bytes());
+    let mut accept = [0; 28];
+    general_purpose::STANDARD
+        .encode_slice(sha1.finalize(), &mut accept)
+        .map_err(|_| Error::Codec)?;
+    let mut value = String::new();
+    value.push_str(core::str::from_utf8(&accept)?).unwrap();
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+    use rand_chacha::ChaCha8Rng;
+    use rand_core::SeedableRng;
+
+    use super::*;
+    use crate::fake::FakeConnection;
+    use crate::request::RequestBuilder;
+    use crate::response::StatusCode;
+
+    /// A server that answers the opening handshake with `status`, followed by the `frames`.
+    struct FakeServer {
+        status: &'static str,
+        frames: &'static [u8],
+        conn: FakeConnection,
+    }
+
+    impl ErrorType for FakeServer {
+        type Error = Infallible;
+    }
+
+    impl Read for FakeServer {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.conn.response.is_empty() && !self.conn.request.is_empty() {
+                let request = std::str::from_utf8(&self.conn.request).unwrap();
+                let key = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Sec-WebSocket-Key: "))
+                    .unwrap();
+                let mut response = format!(
+                    "HTTP/1.1 {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                    self.status,
+                    accept_key(key).unwrap()
+                )
+                .into_bytes();
+                response.extend_from_slice(self.frames);
+                // The fake connection reads from a static response, so the response lives until the test ends
+                self.conn.response = response.leak();
+                self.conn.request.clear();
+            }
+            self.conn.read(buf).await
+        }
+    }
+
+    impl Write for FakeServer {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.conn.write(buf).await
+        }
+    }
+
+    #[test]
+    fn rfc6455_accept_key() {
+        assert_eq!(
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=",
+            accept_key("dGhlIHNhbXBsZSBub25jZQ==").unwrap().as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn can_exchange_frames() {
+        let mut server = FakeServer {
+            status: "101 Switching Protocols",
+            frames: b"\x01\x03Hel\x80\x02lo\x89\x02hi\x88\x05\x03\xe8bye",
+            conn: FakeConnection::new(b""),
+        };
+        let request = Request::get("/chat").host("example.com").build();
+        let mut rx_buf = [0; 256];
+        let mut ws = connect(
+            &mut server,
+            &request,
+            ChaCha8Rng::seed_from_u64(0),
+            &mut rx_buf,
+            &ResponseConfig::new(),
+        )
+        .await
+        .unwrap();
+
+        let frame = ws.read_frame().await.unwrap();
+        assert_eq!(
+            (false, Opcode::Text, b"Hel".as_slice()),
+            (frame.fin, frame.opcode, frame.payload)
+        );
+        let frame = ws.read_frame().await.unwrap();
+        assert_eq!(
+            (true, Opcode::Continuation, b"lo".as_slice()),
+            (frame.fin, frame.opcode, frame.payload)
+        );
+        let frame = ws.read_frame().await.unwrap();
+        assert_eq!(
+            (true, Opcode::Ping, b"hi".as_slice()),
+            (frame.fin, frame.opcode, frame.payload)
+        );
+        let frame = ws.read_frame().await.unwrap();
+        assert_eq!(Some((1000, "bye")), frame.close_reason());
+        assert!(matches!(ws.read_frame().await, Err(Error::ConnectionAborted)));
+
+        ws.write_pong(b"hi").await.unwrap();
+        let sent = &server.conn.request;
+        assert_eq!([0x8a, 0x82], sent[..2]);
+        let mask = &sent[2..6];
+        let payload: Vec<u8> = sent[6..].iter().enumerate().map(|(i, b)| b ^ mask[i % 4]).collect();
+        assert_eq!(b"hi", payload.as_slice());
+    }
+
+    #[tokio::test]
+    async fn rejects_other_status() {
+        let mut server = FakeServer {
+            status: "200 OK",
+            frames: b"",
+            conn: FakeConnection::new(b""),
+        };
+        let request = Request::get("/chat").host("example.com").build();
+        let mut rx_buf = [0; 256];
+        let result = connect(
+            &mut server,
+            &request,
+            ChaCha8Rng::seed_from_u64(0),
+            &mut rx_buf,
+            &ResponseConfig::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::UpgradeRejected(StatusCode(200)))));
+    }
+}
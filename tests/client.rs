@@ -1,38 +1,633 @@
 use embedded_io_async::{BufRead, Write};
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 use hyper::server::conn::Http;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Server};
+#[cfg(feature = "embedded-tls")]
 use rand::rngs::OsRng;
+#[cfg(feature = "embedded-tls")]
 use rand::RngCore;
-use reqwless::client::HttpClient;
+use reqwless::auth::{Credentials, TokenProvider};
+use reqwless::client::{HttpClient, HttpConnection};
+use reqwless::clock::fetch_time;
 use reqwless::headers::ContentType;
-use reqwless::request::{Method, RequestBody, RequestBuilder};
+use reqwless::metrics::{Counters, Metered};
+use reqwless::pool::HttpClientPool;
+use reqwless::request::{Method, Request, RequestBody, RequestBuilder};
 use reqwless::response::Status;
+use reqwless::segmented::{SegmentStorage, SegmentedDownload};
+use reqwless::timeout::Timeouts;
+use reqwless::tls::TlsProvider;
+use reqwless::transport::ConnectionDecorator;
+use std::cell::RefCell;
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 use std::net::SocketAddr;
 use std::sync::Once;
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 use tokio_rustls::rustls;
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 use tokio_rustls::TlsAcceptor;
 
-mod connection;
+mod connection;
+
+use connection::*;
+
+static INIT: Once = Once::new();
+
+fn setup() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+static TCP: TokioTcp = TokioTcp;
+static LOOPBACK_DNS: LoopbackDns = LoopbackDns;
+static PUBLIC_DNS: StdDns = StdDns;
+static DUAL_STACK_DNS: DualStackLoopbackDns = DualStackLoopbackDns;
+
+#[tokio::test]
+async fn test_request_response_notls() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    for _ in 0..2 {
+        let mut request = client
+            .request(Method::POST, &url)
+            .await
+            .unwrap()
+            .body(b"PING".as_slice())
+            .content_type(ContentType::TextPlain);
+        let response = request.send(&mut rx_buf).await.unwrap();
+        let body = response.body().read_to_end().await;
+        assert_eq!(body.unwrap(), b"PING");
+    }
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_connect_falls_back_to_other_address_family() {
+    setup();
+    // Only listening on IPv4, so connecting to the IPv6 loopback address is refused
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://localhost:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &DUAL_STACK_DNS);
+    let mut rx_buf = [0; 4096];
+    let mut request = client
+        .request(Method::POST, &url)
+        .await
+        .unwrap()
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain);
+    let response = request.send(&mut rx_buf).await.unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_resource_notls() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource(&url).await.unwrap();
+    for _ in 0..2 {
+        let response = resource
+            .post("/")
+            .body(b"PING".as_slice())
+            .content_type(ContentType::TextPlain)
+            .send(&mut rx_buf)
+            .await
+            .unwrap();
+        let body = response.body().read_to_end().await;
+        assert_eq!(body.unwrap(), b"PING");
+    }
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+/// A "TLS" provider that hands out the plain connection, to run against a plain server.
+struct PassthroughTls {
+    host: Option<String>,
+}
+
+impl<C> TlsProvider<C> for PassthroughTls
+where
+    C: embedded_io_async::Read + Write,
+{
+    type Connection<'a>
+        = C
+    where
+        Self: 'a,
+        C: 'a;
+
+    async fn connect<'a>(&'a mut self, host: &str, conn: C) -> Result<Self::Connection<'a>, reqwless::Error>
+    where
+        C: 'a,
+    {
+        self.host = Some(host.to_string());
+        Ok(conn)
+    }
+}
+
+#[tokio::test]
+async fn test_resource_tls_provider() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("https://localhost:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut provider = PassthroughTls { host: None };
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource_with_tls(&mut provider, &url).await.unwrap();
+    assert!(resource.conn.is_encrypted());
+    let response = resource
+        .post("/")
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain)
+        .send(&mut rx_buf)
+        .await
+        .unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+    drop(resource);
+    assert_eq!(Some("localhost"), provider.host.as_deref());
+
+    let url = format!("http://localhost:{}", addr.port());
+    assert!(matches!(
+        client.resource_with_tls(&mut provider, &url).await,
+        Err(reqwless::Error::InvalidUrl(_))
+    ));
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_to_address() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    // The host is not resolved, the request is sent to the local server instead
+    let mut client = HttpClient::new(&TCP, &PUBLIC_DNS);
+    let mut rx_buf = [0; 4096];
+    let mut request = client
+        .request_to(addr, Method::POST, "http://example.com/")
+        .await
+        .unwrap()
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain);
+    let response = request.send(&mut rx_buf).await.unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[derive(Clone)]
+struct TokioDelay;
+
+impl reqwless::retry::Delay for TokioDelay {
+    async fn delay_secs(&mut self, secs: u64) {
+        tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+    }
+
+    async fn delay(&mut self, duration: std::time::Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[tokio::test]
+async fn test_request_first_byte_timeout() {
+    setup();
+    // A server that accepts connections, but never responds
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let t = tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let timeouts = Timeouts::new(TokioDelay)
+        .connect(std::time::Duration::from_secs(1))
+        .first_byte(std::time::Duration::from_millis(100));
+    let mut rx_buf = [0; 4096];
+    let mut request = client.request_with_timeouts(timeouts, Method::GET, &url).await.unwrap();
+    assert!(matches!(
+        request.send(&mut rx_buf).await,
+        Err(reqwless::Error::Network(embedded_io::ErrorKind::TimedOut))
+    ));
+
+    t.abort();
+}
+
+#[tokio::test]
+async fn test_raw_tunnel() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut conn = client.tunnel("127.0.0.1", addr.port()).await.unwrap();
+    conn.write_all(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nPING")
+        .await
+        .unwrap();
+    conn.flush().await.unwrap();
+
+    let mut rx_buf = [0; 4096];
+    let response = reqwless::response::Response::read(&mut conn, Method::POST, &mut rx_buf)
+        .await
+        .unwrap();
+    assert_eq!(response.body().read_to_end().await.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+/// A decorator counting the bytes written to the connection.
+struct WriteCounter {
+    written: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+struct CountingConnection<C> {
+    conn: C,
+    written: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl<C> ConnectionDecorator<C> for WriteCounter
+where
+    C: embedded_io_async::Read + Write,
+{
+    type Connection = CountingConnection<C>;
+
+    fn decorate(&self, conn: C) -> Self::Connection {
+        CountingConnection {
+            conn,
+            written: self.written.clone(),
+        }
+    }
+}
+
+impl<C: embedded_io::ErrorType> embedded_io::ErrorType for CountingConnection<C> {
+    type Error = C::Error;
+}
+
+impl<C: embedded_io_async::Read> embedded_io_async::Read for CountingConnection<C> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.conn.read(buf).await
+    }
+}
+
+impl<C: Write> Write for CountingConnection<C> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let written = self.conn.write(buf).await?;
+        self.written.fetch_add(written, std::sync::atomic::Ordering::Relaxed);
+        Ok(written)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.conn.flush().await
+    }
+}
+
+#[tokio::test]
+async fn test_resource_decorated() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let written = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS).decorate(WriteCounter {
+        written: written.clone(),
+    });
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource(&url).await.unwrap();
+    let response = resource
+        .post("/")
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain)
+        .send(&mut rx_buf)
+        .await
+        .unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+    assert!(written.load(std::sync::atomic::Ordering::Relaxed) > b"PING".len());
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_buffered() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut tx_buf = [0; 1024];
+    let mut client = HttpClient::new_buffered(&TCP, &LOOPBACK_DNS, &mut tx_buf);
+    let mut rx_buf = [0; 4096];
+    let mut request = client
+        .request(Method::POST, &url)
+        .await
+        .unwrap()
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain);
+    assert!(matches!(request.conn, HttpConnection::PlainBuffered(_)));
+    let response = request.send(&mut rx_buf).await.unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_buffered_with_timeouts() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut tx_buf = [0; 1024];
+    let mut client = HttpClient::new_buffered(&TCP, &LOOPBACK_DNS, &mut tx_buf);
+    let timeouts = Timeouts::new(TokioDelay).inactivity(std::time::Duration::from_secs(1));
+    let mut rx_buf = [0; 4096];
+    let mut request = client
+        .request_with_timeouts(timeouts, Method::POST, &url)
+        .await
+        .unwrap()
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain);
+    assert!(matches!(request.conn, HttpConnection::PlainBuffered(_)));
+    let response = request.send(&mut rx_buf).await.unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_client_buffers() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut rx_buf = [0; 4096];
+    let mut tx_buf = [0; 1024];
+    let mut client = HttpClient::new_with_buffers(&TCP, &LOOPBACK_DNS, &mut rx_buf, &mut tx_buf);
+    for body in [b"PING".as_slice(), b"PONG"] {
+        let mut request = client
+            .request(Method::POST, &url)
+            .await
+            .unwrap()
+            .body(body)
+            .content_type(ContentType::TextPlain);
+        let response = request.fetch().await.unwrap();
+        let received = response.body().read_to_end().await;
+        assert_eq!(received.unwrap(), body);
+    }
+
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut request = client.request(Method::GET, &url).await.unwrap();
+    assert!(matches!(request.fetch().await, Err(reqwless::Error::BufferTooSmall)));
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_one_shot_requests() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    let (status, body) = client
+        .post(&url, b"PING".as_slice(), ContentType::TextPlain, &mut rx_buf)
+        .await
+        .unwrap();
+    assert!(status.is_successful());
+    assert_eq!(b"PING", body);
 
-use connection::*;
+    let (status, body) = client.get(&url, &mut rx_buf).await.unwrap();
+    assert!(status.is_successful());
+    assert!(body.is_empty());
 
-static INIT: Once = Once::new();
+    let metadata = client.head(&url, &mut rx_buf).await.unwrap();
+    assert!(metadata.status.is_successful());
+    assert_eq!(None, metadata.etag);
 
-fn setup() {
-    INIT.call_once(|| {
-        env_logger::init();
-    });
+    let start = std::time::Instant::now();
+    let time = fetch_time(&mut client, &url, &mut rx_buf, || start.elapsed().as_millis() as u64)
+        .await
+        .unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    assert!(time.abs_diff(now) <= 1);
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
 }
 
-static TCP: TokioTcp = TokioTcp;
-static LOOPBACK_DNS: LoopbackDns = LoopbackDns;
-static PUBLIC_DNS: StdDns = StdDns;
+#[tokio::test]
+async fn test_segmented_download() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(ranged_asset)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}/asset.bin", addr.port());
+    let storage = MemoryStorage(RefCell::new(vec![0; ASSET_LEN]));
+    let mut first = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut second = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut first_buf = [0; 1024];
+    let mut second_buf = [0; 1024];
+    SegmentedDownload::new(&url, ASSET_LEN as u64, &storage)
+        .segments(7)
+        .run([&mut first, &mut second], [&mut first_buf, &mut second_buf])
+        .await
+        .unwrap();
+
+    assert_eq!(asset(), storage.0.into_inner());
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
 
 #[tokio::test]
-async fn test_request_response_notls() {
+async fn test_resource_metrics() {
     setup();
     let addr = ([127, 0, 0, 1], 0).into();
 
@@ -50,30 +645,50 @@ async fn test_request_response_notls() {
     });
 
     let url = format!("http://127.0.0.1:{}", addr.port());
-    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let counters = Counters::new();
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS)
+        .metrics(&counters)
+        .decorate(Metered(&counters));
     let mut rx_buf = [0; 4096];
+    let mut resource = client.resource(&url).await.unwrap();
     for _ in 0..2 {
-        let mut request = client
-            .request(Method::POST, &url)
-            .await
-            .unwrap()
+        let response = resource
+            .post("/")
             .body(b"PING".as_slice())
-            .content_type(ContentType::TextPlain);
-        let response = request.send(&mut rx_buf).await.unwrap();
+            .content_type(ContentType::TextPlain)
+            .send(&mut rx_buf)
+            .await
+            .unwrap();
         let body = response.body().read_to_end().await;
         assert_eq!(body.unwrap(), b"PING");
     }
 
+    let counts = counters.counts();
+    assert_eq!(2, counts.requests);
+    assert_eq!(0, counts.tls_handshakes);
+    assert!(counts.bytes_written > 2 * b"PING".len() as u64);
+    assert!(counts.bytes_read > 2 * b"PING".len() as u64);
+    drop(resource);
+
+    // Nothing listens on the discard port
+    assert!(client.request(Method::GET, "http://127.0.0.1:9").await.is_err());
+    assert_eq!(1, counters.counts().network_errors);
+
     tx.send(()).unwrap();
     t.await.unwrap();
 }
 
 #[tokio::test]
-async fn test_resource_notls() {
+async fn test_resource_credentials() {
     setup();
     let addr = ([127, 0, 0, 1], 0).into();
 
-    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+    let service = make_service_fn(|_| async {
+        Ok::<_, hyper::Error>(service_fn(|req| {
+            // username:password
+            authorized_echo(req, "Basic dXNlcm5hbWU6cGFzc3dvcmQ=")
+        }))
+    });
 
     let server = Server::bind(&addr).serve(service);
     let addr = server.local_addr();
@@ -89,7 +704,11 @@ async fn test_resource_notls() {
     let url = format!("http://127.0.0.1:{}", addr.port());
     let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
     let mut rx_buf = [0; 4096];
-    let mut resource = client.resource(&url).await.unwrap();
+    let mut resource = client
+        .resource(&url)
+        .await
+        .unwrap()
+        .credentials(Credentials::new("username", "password"));
     for _ in 0..2 {
         let response = resource
             .post("/")
@@ -98,9 +717,80 @@ async fn test_resource_notls() {
             .send(&mut rx_buf)
             .await
             .unwrap();
+        assert_eq!(Status::Ok, response.status);
+        let body = response.body().read_to_end().await;
+        assert_eq!(body.unwrap(), b"PING");
+    }
+
+    // Requests with their own authentication are not retried
+    let response = resource
+        .get("/")
+        .basic_auth("username", "wrong")
+        .send(&mut rx_buf)
+        .await
+        .unwrap();
+    assert_eq!(Status::Unauthorized, response.status);
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+struct RotatingToken {
+    token: &'static str,
+    refreshed: usize,
+}
+
+impl TokenProvider for RotatingToken {
+    async fn token(&mut self) -> Result<&str, reqwless::Error> {
+        Ok(self.token)
+    }
+
+    async fn refresh(&mut self) -> Result<(), reqwless::Error> {
+        self.token = "fresh";
+        self.refreshed += 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_resource_token_refresh() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service =
+        make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(|req| authorized_echo(req, "Bearer fresh"))) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource(&url).await.unwrap();
+    let mut provider = RotatingToken {
+        token: "expired",
+        refreshed: 0,
+    };
+    for _ in 0..2 {
+        let response = resource
+            .post("/")
+            .body(b"PING".as_slice())
+            .send_with_token(&mut provider, &mut rx_buf)
+            .await
+            .unwrap();
+        assert_eq!(Status::Ok, response.status);
         let body = response.body().read_to_end().await;
         assert_eq!(body.unwrap(), b"PING");
     }
+    assert_eq!(1, provider.refreshed);
 
     tx.send(()).unwrap();
     t.await.unwrap();
@@ -225,6 +915,152 @@ async fn test_resource_rustls() {
     t.await.unwrap();
 }
 
+#[tokio::test]
+#[cfg(feature = "std")]
+async fn test_resource_std_rustls() {
+    use reqwless::std_tls::RustlsProvider;
+    use std::sync::Arc;
+
+    struct AcceptAnyCertificate;
+
+    impl rustls::client::ServerCertVerifier for AcceptAnyCertificate {
+        fn verify_server_cert(
+            &self,
+            _: &rustls::Certificate,
+            _: &[rustls::Certificate],
+            _: &rustls::ServerName,
+            _: &mut dyn Iterator<Item = &[u8]>,
+            _: &[u8],
+            _: std::time::SystemTime,
+        ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+            Ok(rustls::client::ServerCertVerified::assertion())
+        }
+    }
+
+    setup();
+    let addr: SocketAddr = ([127, 0, 0, 1], 0).into();
+
+    let test_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let certs = load_certs(&test_dir.join("certs").join("cert.pem"));
+    let privkey = load_private_key(&test_dir.join("certs").join("key.pem"));
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, privkey)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let stream = acceptor.accept(stream).await.unwrap();
+                Http::new()
+                    .http1_only(true)
+                    .http1_keep_alive(true)
+                    .serve_connection(stream, service_fn(echo))
+                    .await.unwrap();
+        } => {}
+            _ = rx => {}
+        }
+    });
+
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+        .with_no_client_auth();
+    let mut provider = RustlsProvider::new(Arc::new(config));
+    let url = format!("https://localhost:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource_with_tls(&mut provider, &url).await.unwrap();
+    for _ in 0..2 {
+        let response = resource
+            .post("/")
+            .body(b"PING".as_slice())
+            .content_type(ContentType::TextPlain)
+            .send(&mut rx_buf)
+            .await
+            .unwrap();
+        let body = response.body().read_to_end().await;
+        assert_eq!(body.unwrap(), b"PING");
+    }
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
+#[tokio::test]
+#[cfg(feature = "std")]
+async fn test_resource_std_rustls_pinned() {
+    use reqwless::pinning::Pin;
+    use reqwless::std_tls::RustlsProvider;
+    use std::sync::Arc;
+
+    setup();
+    let addr: SocketAddr = ([127, 0, 0, 1], 0).into();
+
+    let test_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let certs = load_certs(&test_dir.join("certs").join("cert.pem"));
+    let privkey = load_private_key(&test_dir.join("certs").join("key.pem"));
+    let pin = Pin::of_public_key(&certs[0].0).unwrap();
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, privkey)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        Http::new()
+                            .http1_only(true)
+                            .serve_connection(stream, service_fn(echo))
+                            .await.unwrap();
+                    }
+                }
+        } => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("https://localhost:{}", addr.port());
+    let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
+
+    let mut provider = RustlsProvider::pinned(&[Pin::PublicKey([0; 32])]);
+    assert!(client.resource_with_tls(&mut provider, &url).await.is_err());
+
+    let mut provider = RustlsProvider::pinned(&[Pin::PublicKey([0; 32]), pin]);
+    let mut rx_buf = [0; 4096];
+    let mut resource = client.resource_with_tls(&mut provider, &url).await.unwrap();
+    let response = resource
+        .post("/")
+        .body(b"PING".as_slice())
+        .content_type(ContentType::TextPlain)
+        .send(&mut rx_buf)
+        .await
+        .unwrap();
+    let body = response.body().read_to_end().await;
+    assert_eq!(body.unwrap(), b"PING");
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
 #[ignore]
 #[tokio::test]
 #[cfg(feature = "embedded-tls")]
@@ -296,6 +1132,47 @@ async fn test_request_response_notls_buffered() {
     t.await.unwrap();
 }
 
+#[tokio::test]
+async fn test_pool_reuses_connection() {
+    setup();
+    let addr = ([127, 0, 0, 1], 0).into();
+
+    let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(echo)) });
+
+    let server = Server::bind(&addr).serve(service);
+    let addr = server.local_addr();
+
+    let (tx, rx) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let url = format!("http://127.0.0.1:{}", addr.port());
+    let mut pool: HttpClientPool<_, _, 2> = HttpClientPool::new(&TCP, &LOOPBACK_DNS);
+    let mut rx_buf = [0; 4096];
+    for now in 0..2 {
+        let mut resource = pool.resource(&url, now).await.unwrap();
+        let request = Request::post("/")
+            .body(b"PING".as_slice())
+            .content_type(ContentType::TextPlain)
+            .build();
+        let response = resource.send(request, &mut rx_buf).await.unwrap();
+        let body = response.body().read_to_end().await;
+        assert_eq!(body.unwrap(), b"PING");
+        resource.release();
+    }
+    assert_eq!(1, pool.open_connections());
+
+    pool.evict_idle(100);
+    assert_eq!(0, pool.open_connections());
+
+    tx.send(()).unwrap();
+    t.await.unwrap();
+}
+
 struct ChunkedBody(&'static [&'static [u8]]);
 
 impl RequestBody for ChunkedBody {
@@ -333,7 +1210,7 @@ async fn test_request_response_notls_buffered_chunked() {
     let mut client = HttpClient::new(&TCP, &LOOPBACK_DNS);
     let mut tx_buf = [0; 4096];
     let mut rx_buf = [0; 4096];
-    static CHUNKS: [&'static [u8]; 2] = [b"PART1", b"PART2"];
+    static CHUNKS: [&[u8]; 2] = [b"PART1", b"PART2"];
     let mut request = client
         .request(Method::POST, &url)
         .await
@@ -349,6 +1226,7 @@ async fn test_request_response_notls_buffered_chunked() {
     t.await.unwrap();
 }
 
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 fn load_certs(filename: &std::path::PathBuf) -> Vec<rustls::Certificate> {
     let certfile = std::fs::File::open(filename).expect("cannot open certificate file");
     let mut reader = std::io::BufReader::new(certfile);
@@ -359,6 +1237,7 @@ fn load_certs(filename: &std::path::PathBuf) -> Vec<rustls::Certificate> {
         .collect()
 }
 
+#[cfg(any(feature = "embedded-tls", feature = "std"))]
 fn load_private_key(filename: &std::path::PathBuf) -> rustls::PrivateKey {
     let keyfile = std::fs::File::open(filename).expect("cannot open private key file");
     let mut reader = std::io::BufReader::new(keyfile);
@@ -376,14 +1255,60 @@ fn load_private_key(filename: &std::path::PathBuf) -> rustls::PrivateKey {
 }
 
 async fn echo(req: hyper::Request<Body>) -> Result<hyper::Response<Body>, hyper::Error> {
-    match (req.method(), req.uri().path()) {
-        _ => Ok(hyper::Response::new(req.into_body())),
+    Ok(hyper::Response::new(req.into_body()))
+}
+
+const ASSET_LEN: usize = 10_000;
+
+fn asset() -> Vec<u8> {
+    (0..ASSET_LEN).map(|i| (i % 251) as u8).collect()
+}
+
+/// Serve the requested range of the asset.
+async fn ranged_asset(req: hyper::Request<Body>) -> Result<hyper::Response<Body>, hyper::Error> {
+    let range = req.headers()["range"].to_str().unwrap();
+    let (start, end) = range.strip_prefix("bytes=").unwrap().split_once('-').unwrap();
+    let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+    Ok(hyper::Response::builder()
+        .status(206)
+        .header("Content-Range", format!("bytes {}-{}/{}", start, end, ASSET_LEN))
+        .body(Body::from(asset()[start..=end].to_vec()))
+        .unwrap())
+}
+
+struct MemoryStorage(RefCell<Vec<u8>>);
+
+impl SegmentStorage for MemoryStorage {
+    async fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), reqwless::Error> {
+        let offset = offset as usize;
+        self.0.borrow_mut()[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+async fn authorized_echo(
+    req: hyper::Request<Body>,
+    authorization: &'static str,
+) -> Result<hyper::Response<Body>, hyper::Error> {
+    let authorized = req
+        .headers()
+        .get("authorization")
+        .is_some_and(|value| value == authorization);
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    if authorized {
+        Ok(hyper::Response::new(Body::from(body)))
+    } else {
+        Ok(hyper::Response::builder()
+            .status(401)
+            .header("WWW-Authenticate", "Basic realm=\"test\"")
+            .body(Body::from("Unauthorized"))
+            .unwrap())
     }
 }
 
 #[test]
 fn compile_tests() {
-    #[allow(dead_code)]
+    #[allow(dead_code, clippy::needless_lifetimes)]
     async fn rx_buffer_lifetime_is_propagated_to_output<'buf>(port: u16, rx_buf: &'buf mut [u8]) -> &'buf mut [u8] {
         let mut http = HttpClient::new(&TCP, &LOOPBACK_DNS);
         let url = format!("http://127.0.0.1:{}", port);
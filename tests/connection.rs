@@ -1,8 +1,10 @@
+#![allow(dead_code)]
+
 use embedded_io_adapters::tokio_1::FromTokio;
 use embedded_io_async::{ErrorType, Read, Write};
 use embedded_nal_async::AddrType;
 use reqwless::TryBufRead;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
 use tokio::net::TcpStream;
 
 #[derive(Debug)]
@@ -27,6 +29,23 @@ impl embedded_nal_async::Dns for LoopbackDns {
     }
 }
 
+/// Resolves to the IPv6 loopback address unless an IPv4 address is requested.
+pub struct DualStackLoopbackDns;
+impl embedded_nal_async::Dns for DualStackLoopbackDns {
+    type Error = TestError;
+
+    async fn get_host_by_name(&self, _: &str, addr_type: AddrType) -> Result<IpAddr, Self::Error> {
+        match addr_type {
+            AddrType::IPv4 => Ok(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            _ => Ok(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+        }
+    }
+
+    async fn get_host_by_address(&self, _: IpAddr, _: &mut [u8]) -> Result<usize, Self::Error> {
+        Err(TestError)
+    }
+}
+
 pub struct StdDns;
 
 impl embedded_nal_async::Dns for StdDns {
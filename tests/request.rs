@@ -59,9 +59,7 @@ async fn test_request_response() {
 }
 
 async fn echo(req: hyper::Request<Body>) -> Result<hyper::Response<Body>, hyper::Error> {
-    match (req.method(), req.uri().path()) {
-        _ => Ok(hyper::Response::new(req.into_body())),
-    }
+    Ok(hyper::Response::new(req.into_body()))
 }
 
 #[tokio::test]
@@ -94,7 +92,7 @@ async fn google_panic() {
 
     match result {
         Ok(body) => {
-            println!("{} -> {}", body.len(), core::str::from_utf8(&body).unwrap());
+            println!("{} -> {}", body.len(), core::str::from_utf8(body).unwrap());
         }
         Err(Error::BufferTooSmall) => println!("Buffer too small"),
         Err(e) => panic!("Unexpected error: {e:?}"),